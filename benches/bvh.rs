@@ -0,0 +1,99 @@
+//! Benchmarks the bounding volume hierarchy on a synthetic scene with many small instances.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use nalgebra_glm::{look_at, perspective, Vec3};
+
+// the viewer is a binary crate, i.e., the required modules are compiled into the benchmark
+#[allow(dead_code, unused_imports)]
+#[path = "../src/geometry/mod.rs"]
+mod geometry;
+
+#[allow(dead_code, unused_imports)]
+#[path = "../src/gpu_data/bvh.rs"]
+mod bvh;
+
+use bvh::Bvh;
+use geometry::{BBox, Frustum, Ray};
+
+/// The number of instances of the synthetic scene.
+const NUM_INSTANCES: usize = 100_000;
+
+/// The extent of the cube the instances are scattered in.
+const SCENE_SIZE: f32 = 1000f32;
+
+/// Returns the volumes of the instances of the synthetic scene, i.e., boxes of varying size
+/// scattered pseudo-randomly within the scene cube. The scene is the same for each run.
+fn create_scene() -> Vec<BBox> {
+    // linear congruential generator for reproducible positions
+    let mut state = 0x2545_f491_u32;
+    let mut random = move || {
+        state = state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+        (state >> 8) as f32 / (1u32 << 24) as f32
+    };
+
+    (0..NUM_INSTANCES)
+        .map(|_| {
+            let min = Vec3::new(random(), random(), random()) * SCENE_SIZE;
+            let size = Vec3::new(random(), random(), random()) * 5f32 + Vec3::from_element(0.1);
+
+            let mut bbox = BBox::new();
+            bbox.extend_pos(&min);
+            bbox.extend_pos(&(min + size));
+
+            bbox
+        })
+        .collect()
+}
+
+fn bench_bvh(c: &mut Criterion) {
+    let scene = create_scene();
+    let mut bvh = Bvh::new(scene.clone());
+
+    c.bench_function("bvh_build", |b| {
+        b.iter(|| Bvh::new(black_box(scene.clone())))
+    });
+
+    // looks from a corner of the scene towards its center
+    let projection = perspective(16f32 / 9f32, 0.8f32, 1f32, 4000f32);
+    let view = look_at(
+        &Vec3::from_element(-200f32),
+        &Vec3::from_element(SCENE_SIZE * 0.5),
+        &Vec3::new(0.0, 1.0, 0.0),
+    );
+    let frustum = Frustum::from_matrix(&(projection * view));
+    c.bench_function("bvh_query_frustum", |b| {
+        b.iter(|| {
+            let mut count = 0usize;
+            bvh.query_frustum(black_box(&frustum), |_| count += 1);
+            count
+        })
+    });
+
+    let ray = Ray::new(Vec3::new(-10.0, 480.0, 510.0), Vec3::new(1.0, 0.05, -0.02));
+    c.bench_function("bvh_query_ray", |b| {
+        b.iter(|| bvh.query_ray(black_box(&ray), |item| ray.intersect_bbox(&scene[item])))
+    });
+
+    let mut query = BBox::new();
+    query.extend_pos(&Vec3::from_element(400f32));
+    query.extend_pos(&Vec3::from_element(600f32));
+    c.bench_function("bvh_query_bbox", |b| {
+        b.iter(|| {
+            let mut count = 0usize;
+            bvh.query_bbox(black_box(&query), |_| count += 1);
+            count
+        })
+    });
+
+    // moves a single instance, as done when dragging a selected part
+    let moved = scene[0].transform(&nalgebra_glm::translation(&Vec3::new(1.0, 0.0, 0.0)));
+    c.bench_function("bvh_refit", |b| {
+        b.iter(|| {
+            bvh.set_item_bbox(0, black_box(moved.clone()));
+            bvh.refit();
+        })
+    });
+}
+
+criterion_group!(benches, bench_bvh);
+criterion_main!(benches);
@@ -1,29 +1,422 @@
 use anyhow::{bail, Context, Result};
-use log::info;
-use std::{env, path::PathBuf, str::FromStr};
+use clap::Parser;
+use log::{info, LevelFilter};
+use nalgebra_glm::Vec3;
+use std::path::PathBuf;
 
-/// The program arguments
+use crate::{
+    geometry::DEFAULT_CREASE_ANGLE,
+    viewer::{CameraView, DisplayMode, TransparencyMode},
+};
+
+/// A simple CAD viewer for visualizing 3D/CAD data based on the cad_import library.
+#[derive(Parser, Debug)]
+#[command(version, about)]
 pub struct Arguments {
-    pub input_file: PathBuf,
+    /// The paths to the input files.
+    #[arg(value_name = "INPUT", required = true)]
+    pub input_files: Vec<PathBuf>,
+
+    /// The offset translation for the input files, applied in the order of the input files,
+    /// e.g. '--offset 0,0,0 --offset 10,0,0'.
+    #[arg(long = "offset", value_name = "X,Y,Z", value_parser = parse_vec3)]
+    pub offsets: Vec<Vec3>,
+
+    /// Forces the loader for the given mime type (e.g. 'model/vnd.off') instead of detecting
+    /// the format by the file extension or the file content.
+    #[arg(long, value_name = "MIME")]
+    pub mime_type: Option<String>,
+
+    /// The size of the window or the snapshot in pixels.
+    #[arg(long, value_name = "WxH", default_value = "1024x768", value_parser = parse_size)]
+    pub size: (u32, u32),
+
+    /// The initial camera view [front, back, left, right, top, bottom, iso].
+    #[arg(long, value_name = "VIEW", default_value = "front")]
+    pub view: CameraView,
+
+    /// The background color, either as hex code (e.g. '#3333ff') or as comma separated floats
+    /// in [0,1] (e.g. '0.2,0.2,1.0').
+    #[arg(long, value_name = "COLOR", default_value = "0.2,0.2,1.0", value_parser = parse_color)]
+    pub background: Vec3,
+
+    /// The log level [off, error, warn, info, debug, trace].
+    #[arg(long, value_name = "LEVEL", default_value = "debug", value_parser = parse_log_level)]
+    pub log_level: LevelFilter,
+
+    /// Writes the log into the given file instead of stdout.
+    #[arg(long, value_name = "FILE")]
+    pub log_file: Option<PathBuf>,
+
+    /// The maximal time in milliseconds spent per frame for uploading data to the GPU.
+    #[arg(long, value_name = "MS", default_value_t = 10)]
+    pub upload_time_budget: u64,
+
+    /// The maximal number of MiB uploaded per frame to the GPU.
+    #[arg(long, value_name = "MIB", default_value_t = 32)]
+    pub upload_byte_budget: usize,
+
+    /// Merges the meshes of small shapes into combined GPU buffers with the instance
+    /// transformations baked in, which reduces the number of draw-calls for many tiny parts.
+    #[arg(long)]
+    pub merge_small_meshes: bool,
+
+    /// The number of coarser levels of detail created for each mesh by simplification, where
+    /// zero disables the levels of detail.
+    #[arg(long, value_name = "N", default_value_t = 3)]
+    pub lod_levels: usize,
+
+    /// Interleaves the vertex attributes of each mesh into a single GPU buffer.
+    #[arg(long)]
+    pub interleave_vertices: bool,
+
+    /// Stores the vertex positions as 16-bit integers relative to the bounding volume of their
+    /// shape.
+    #[arg(long)]
+    pub quantize_positions: bool,
+
+    /// Stores the vertex normals octahedral encoded as two 16-bit integers.
+    #[arg(long)]
+    pub octahedral_normals: bool,
+
+    /// Adds a white directional light shining into the given direction in world coordinates,
+    /// e.g. '--light 0,-1,0 --light 1,0,0'.
+    #[arg(long = "light", value_name = "X,Y,Z", value_parser = parse_direction)]
+    pub lights: Vec<Vec3>,
+
+    /// Disables the headlight, which shines along the view direction of the camera.
+    #[arg(long)]
+    pub no_headlight: bool,
+
+    /// The blending of transparent parts [sorted, oit], i.e., either sorted back-to-front per
+    /// instance or weighted blended order independent transparency.
+    #[arg(long, value_name = "MODE", default_value = "sorted")]
+    pub transparency: TransparencyMode,
+
+    /// The initial display mode [shaded, wireframe, edges, hidden-line], cycled with the D key.
+    #[arg(long, value_name = "MODE", default_value = "shaded")]
+    pub display_mode: DisplayMode,
+
+    /// The color of the feature edges, either as hex code or as comma separated floats.
+    #[arg(long, value_name = "COLOR", default_value = "0,0,0", value_parser = parse_color)]
+    pub edge_color: Vec3,
+
+    /// The angle in degrees between the normals of two adjacent triangles above which their
+    /// shared edge is drawn as feature edge.
+    #[arg(long, value_name = "DEGREES", default_value_t = DEFAULT_CREASE_ANGLE)]
+    pub crease_angle: f32,
+
+    /// Loads the clip planes from the given file if it exists, where the P key saves the current
+    /// clip planes to this file. Clip planes are added with the C key.
+    #[arg(long, value_name = "FILE")]
+    pub clip_planes: Option<PathBuf>,
+
+    /// The color of the caps of the surfaces cut open by the clip planes, either as hex code or
+    /// as comma separated floats.
+    #[arg(long, value_name = "COLOR", default_value = "0.8,0.2,0.2", value_parser = parse_color)]
+    pub cap_color: Vec3,
+
+    /// Watches the input files and reloads them whenever they are modified.
+    #[arg(long)]
+    pub watch: bool,
+
+    /// Renders offscreen and writes the frame to the given PNG file instead of opening a window.
+    #[arg(long, value_name = "PNG")]
+    pub snapshot: Option<PathBuf>,
 }
 
 impl Arguments {
     /// Parses the program arguments.
-    pub fn parse_args() -> Result<Self> {
-        let args: Vec<String> = env::args().collect();
-        let args = &args[1..];
+    pub fn parse_args() -> Result<Self, clap::Error> {
+        Self::parse_args_from(std::env::args_os())
+    }
 
-        if args.len() != 1 {
-            bail!("Invalid number of program arguments");
-        }
+    /// Parses the given arguments, where the first argument is the program name.
+    ///
+    /// # Arguments
+    /// * `args` - The arguments to parse.
+    pub fn parse_args_from<I, T>(args: I) -> Result<Self, clap::Error>
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<std::ffi::OsString> + Clone,
+    {
+        let args = Self::try_parse_from(args)?;
 
-        let input_file = PathBuf::from_str(&args[0]).context("Failed to parse input file path")?;
+        if args.offsets.len() > args.input_files.len() {
+            let mut cmd = <Self as clap::CommandFactory>::command();
+            return Err(cmd.error(
+                clap::error::ErrorKind::TooManyValues,
+                format!(
+                    "Got {} offsets, but only {} input files",
+                    args.offsets.len(),
+                    args.input_files.len()
+                ),
+            ));
+        }
 
-        Ok(Self { input_file })
+        Ok(args)
     }
 
     /// Prints all arguments into the log
     pub fn print_to_log(&self) {
-        info!("Input File: {}", self.input_file.to_string_lossy());
+        for (i, input_file) in self.input_files.iter().enumerate() {
+            match self.offsets.get(i) {
+                Some(offset) => info!(
+                    "Input File: {} (Offset: {}, {}, {})",
+                    input_file.to_string_lossy(),
+                    offset[0],
+                    offset[1],
+                    offset[2]
+                ),
+                None => info!("Input File: {}", input_file.to_string_lossy()),
+            }
+        }
+
+        if let Some(mime_type) = &self.mime_type {
+            info!("Mime Type: {}", mime_type);
+        }
+
+        info!("Size: {}x{}", self.size.0, self.size.1);
+        info!("View: {}", self.view);
+        info!(
+            "Background: ({}, {}, {})",
+            self.background[0], self.background[1], self.background[2]
+        );
+        info!("Log Level: {}", self.log_level);
+
+        if let Some(log_file) = &self.log_file {
+            info!("Log File: {}", log_file.to_string_lossy());
+        }
+
+        info!(
+            "Upload Budget: {} ms, {} MiB",
+            self.upload_time_budget, self.upload_byte_budget
+        );
+        info!("Merge Small Meshes: {}", self.merge_small_meshes);
+        info!("LOD Levels: {}", self.lod_levels);
+        info!(
+            "Vertex Format: interleaved={}, quantized positions={}, octahedral normals={}",
+            self.interleave_vertices, self.quantize_positions, self.octahedral_normals
+        );
+        info!("Headlight: {}", !self.no_headlight);
+        for light in self.lights.iter() {
+            info!("Light: ({}, {}, {})", light[0], light[1], light[2]);
+        }
+        info!("Transparency: {}", self.transparency);
+        info!("Display Mode: {}", self.display_mode);
+        info!(
+            "Edge Color: ({}, {}, {})",
+            self.edge_color[0], self.edge_color[1], self.edge_color[2]
+        );
+        info!("Crease Angle: {}", self.crease_angle);
+
+        if let Some(clip_planes) = &self.clip_planes {
+            info!("Clip Planes File: {}", clip_planes.to_string_lossy());
+        }
+
+        info!(
+            "Cap Color: ({}, {}, {})",
+            self.cap_color[0], self.cap_color[1], self.cap_color[2]
+        );
+
+        info!("Watch: {}", self.watch);
+
+        if let Some(snapshot) = &self.snapshot {
+            info!("Snapshot File: {}", snapshot.to_string_lossy());
+        }
+    }
+}
+
+/// Parses a size given in the form `<WIDTH>x<HEIGHT>`, e.g. `1920x1080`.
+///
+/// # Arguments
+/// * `s` - The string to parse.
+fn parse_size(s: &str) -> Result<(u32, u32)> {
+    let (width, height) = match s.split_once('x') {
+        Some(size) => size,
+        None => {
+            bail!("Invalid size '{}', expected <WIDTH>x<HEIGHT>", s);
+        }
+    };
+
+    let width: u32 = width.parse().context(format!("Invalid width in '{}'", s))?;
+    let height: u32 = height
+        .parse()
+        .context(format!("Invalid height in '{}'", s))?;
+
+    if width == 0 || height == 0 {
+        bail!("Invalid size '{}', width and height must be positive", s);
+    }
+
+    Ok((width, height))
+}
+
+/// Parses a direction given as comma separated floats `X,Y,Z` and normalizes it.
+///
+/// # Arguments
+/// * `s` - The string to parse.
+fn parse_direction(s: &str) -> Result<Vec3> {
+    let v = parse_vec3(s)?;
+    if v.norm() <= 0f32 {
+        bail!("Invalid direction '{}', the vector must not be zero", s);
+    }
+
+    Ok(v.normalize())
+}
+
+/// Parses a vector given as comma separated floats `X,Y,Z`.
+///
+/// # Arguments
+/// * `s` - The string to parse.
+fn parse_vec3(s: &str) -> Result<Vec3> {
+    let components: Vec<&str> = s.split(',').collect();
+    if components.len() != 3 {
+        bail!("Invalid vector '{}', expected X,Y,Z", s);
+    }
+
+    let mut v = Vec3::zeros();
+    for (i, c) in components.iter().enumerate() {
+        v[i] = c
+            .trim()
+            .parse()
+            .context(format!("Invalid vector component in '{}'", s))?;
+    }
+
+    Ok(v)
+}
+
+/// Parses the log level, e.g. `info`.
+///
+/// # Arguments
+/// * `s` - The string to parse.
+fn parse_log_level(s: &str) -> Result<LevelFilter> {
+    match s.parse() {
+        Ok(level) => Ok(level),
+        Err(_) => {
+            bail!(
+                "Invalid log level '{}', expected off, error, warn, info, debug or trace",
+                s
+            );
+        }
+    }
+}
+
+/// Parses a color either given as hex code `#RRGGBB` or as comma separated floats `R,G,B`.
+///
+/// # Arguments
+/// * `s` - The string to parse.
+fn parse_color(s: &str) -> Result<Vec3> {
+    if let Some(hex) = s.strip_prefix('#') {
+        if hex.len() != 6 || !hex.is_ascii() {
+            bail!("Invalid hex color '{}', expected #RRGGBB", s);
+        }
+
+        let mut color = Vec3::zeros();
+        for i in 0..3 {
+            let c = u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16)
+                .context(format!("Invalid hex color '{}'", s))?;
+            color[i] = c as f32 / 255f32;
+        }
+
+        return Ok(color);
+    }
+
+    let components: Vec<&str> = s.split(',').collect();
+    if components.len() != 3 {
+        bail!("Invalid color '{}', expected R,G,B or #RRGGBB", s);
+    }
+
+    let mut color = Vec3::zeros();
+    for (i, c) in components.iter().enumerate() {
+        let c: f32 = c
+            .trim()
+            .parse()
+            .context(format!("Invalid color component in '{}'", s))?;
+        if !(0f32..=1f32).contains(&c) {
+            bail!("Color component {} in '{}' is not in [0,1]", c, s);
+        }
+
+        color[i] = c;
+    }
+
+    Ok(color)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_size() {
+        assert_eq!(parse_size("1920x1080").unwrap(), (1920, 1080));
+        assert!(parse_size("1920").is_err());
+        assert!(parse_size("0x1080").is_err());
+        assert!(parse_size("1920x").is_err());
+        assert!(parse_size("-1x1080").is_err());
+    }
+
+    #[test]
+    fn test_parse_color() {
+        assert_eq!(parse_color("#3333ff").unwrap(), Vec3::new(0.2, 0.2, 1.0));
+        assert_eq!(
+            parse_color("0.2, 0.2, 1.0").unwrap(),
+            Vec3::new(0.2, 0.2, 1.0)
+        );
+        assert!(parse_color("#33f").is_err());
+        assert!(parse_color("#gg33ff").is_err());
+        assert!(parse_color("0.2,0.2").is_err());
+        assert!(parse_color("0.2,0.2,1.5").is_err());
+    }
+
+    #[test]
+    fn test_parse_vec3() {
+        assert_eq!(parse_vec3("1,-2.5,3").unwrap(), Vec3::new(1.0, -2.5, 3.0));
+        assert!(parse_vec3("1,2").is_err());
+        assert!(parse_vec3("1,2,x").is_err());
+
+        assert_eq!(
+            parse_direction("0,-2,0").unwrap(),
+            Vec3::new(0.0, -1.0, 0.0)
+        );
+        assert!(parse_direction("0,0,0").is_err());
+    }
+
+    #[test]
+    fn test_parse_args() {
+        let args = Arguments::parse_args_from([
+            "cad_viewer",
+            "--size",
+            "640x480",
+            "--background",
+            "#000000",
+            "--offset",
+            "10,0,0",
+            "a.off",
+            "b.off",
+        ])
+        .unwrap();
+
+        assert_eq!(args.input_files.len(), 2);
+        assert_eq!(args.offsets, vec![Vec3::new(10.0, 0.0, 0.0)]);
+        assert_eq!(args.size, (640, 480));
+        assert_eq!(args.background, Vec3::zeros());
+
+        assert!(Arguments::parse_args_from(["cad_viewer"]).is_err());
+        assert!(Arguments::parse_args_from(["cad_viewer", "--size", "640", "a.off"]).is_err());
+    }
+
+    #[test]
+    fn test_more_offsets_than_inputs() {
+        let err = Arguments::parse_args_from([
+            "cad_viewer",
+            "--offset",
+            "0,0,0",
+            "--offset",
+            "10,0,0",
+            "a.off",
+        ])
+        .unwrap_err();
+
+        assert_eq!(err.kind(), clap::error::ErrorKind::TooManyValues);
     }
 }
@@ -1,6 +1,7 @@
 use std::fmt;
 use std::fmt::Display;
 
+use cad_import::structure::Mesh;
 use nalgebra_glm as glm;
 
 /// An AABB bounding volume
@@ -53,18 +54,63 @@ impl BBox {
         self.max.z = self.max.z.max(rhs.max.z);
     }
 
+    /// Extends the bounding volume with all positions of the given mesh
+    ///
+    ///* `mesh` - The mesh about which the volume is extended
+    pub fn extend_mesh(&mut self, mesh: &Mesh) {
+        for p in mesh.get_vertices().get_positions().iter() {
+            self.extend_pos(&p.0);
+        }
+    }
+
+    /// Returns the bounding volume enclosing this volume transformed by the given matrix, i.e.,
+    /// the volume of the eight transformed corners.
+    ///
+    ///* `m` - The transformation matrix
+    pub fn transform(&self, m: &glm::Mat4) -> BBox {
+        let mut result = BBox::new();
+        if self.is_empty() {
+            return result;
+        }
+
+        for i in 0..8 {
+            let corner = glm::vec4(
+                if i & 1 == 0 { self.min.x } else { self.max.x },
+                if i & 2 == 0 { self.min.y } else { self.max.y },
+                if i & 4 == 0 { self.min.z } else { self.max.z },
+                1.0,
+            );
+
+            result.extend_pos(&glm::vec4_to_vec3(&(m * corner)));
+        }
+
+        result
+    }
+
+    /// Returns true if the bounding volume overlaps with the given bounding volume.
+    ///
+    ///* `rhs` - The right-hand-side bounding volume to test
+    pub fn intersects(&self, rhs: &BBox) -> bool {
+        !self.is_empty()
+            && !rhs.is_empty()
+            && self.min.x <= rhs.max.x
+            && self.min.y <= rhs.max.y
+            && self.min.z <= rhs.max.z
+            && rhs.min.x <= self.max.x
+            && rhs.min.y <= self.max.y
+            && rhs.min.z <= self.max.z
+    }
+
     /// Computes and returns the bounding box center
     #[inline]
     pub fn get_center(&self) -> glm::Vec3 {
-        let center = (self.min + self.max) / 2.0;
-        center
+        (self.min + self.max) / 2.0
     }
 
     /// Computes and returns the bounding box size
     #[inline]
     pub fn get_size(&self) -> glm::Vec3 {
-        let size = self.max - self.min;
-        size
+        self.max - self.min
     }
 
     /// Returns a reference onto the minimum
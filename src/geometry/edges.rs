@@ -0,0 +1,382 @@
+use std::collections::HashMap;
+
+use anyhow::Result;
+use cad_import::structure::{IndexData, Mesh, Point3D, PrimitiveType, Primitives, Vertices};
+use nalgebra_glm::{cross, dot, length, Vec3};
+
+use super::triangles::for_each_triangle;
+
+/// The angle in degrees between the normals of two adjacent triangles above which their shared
+/// edge is a crease edge.
+pub const DEFAULT_CREASE_ANGLE: f32 = 30.0;
+
+/// The classification of an edge of a triangle mesh by its adjacent triangles.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EdgeKind {
+    /// An edge with a single adjacent triangle.
+    Boundary,
+
+    /// An edge shared by more than two triangles.
+    NonManifold,
+
+    /// An edge between two triangles enclosing an angle above the crease angle.
+    Crease,
+
+    /// An edge between two triangles enclosing an angle up to the crease angle.
+    Smooth,
+}
+
+impl EdgeKind {
+    /// Returns true if edges of this kind are feature edges, i.e., all but the smooth edges.
+    pub fn is_feature(&self) -> bool {
+        *self != EdgeKind::Smooth
+    }
+}
+
+/// A single edge of a triangle mesh.
+#[derive(Debug, Clone)]
+pub struct MeshEdge {
+    /// The classification of the edge by its adjacent triangles.
+    pub kind: EdgeKind,
+
+    /// The positions of both end points.
+    pub positions: [Point3D; 2],
+
+    /// The unit normals of the adjacent triangles given by their winding.
+    pub normals: Vec<Vec3>,
+}
+
+// silhouettes are not drawn yet, as they have to be extracted again whenever the view changes
+#[allow(dead_code)]
+impl MeshEdge {
+    /// Returns true if the edge is a silhouette edge for the given view direction, i.e., it is
+    /// shared by exactly two triangles, of which one faces the viewer and the other faces away.
+    /// Triangles seen exactly edge-on face away. For a perspective projection, the view
+    /// direction is the direction from the eye to the edge.
+    ///
+    /// # Arguments
+    /// * `view_direction` - The direction in which the edge is viewed.
+    pub fn is_silhouette(&self, view_direction: &Vec3) -> bool {
+        match self.normals.as_slice() {
+            [n0, n1] => (dot(n0, view_direction) < 0f32) != (dot(n1, view_direction) < 0f32),
+            _ => false,
+        }
+    }
+}
+
+/// Returns the classified edges of the given triangle mesh. Vertices with equal positions are
+/// welded, s.t. attribute seams are not reported as boundaries. Degenerate triangles are
+/// ignored and meshes without triangles have no edges.
+///
+/// # Arguments
+/// * `mesh` - The mesh whose edges are extracted.
+/// * `crease_angle` - The crease angle in degrees.
+pub fn extract_edges(mesh: &Mesh, crease_angle: f32) -> Vec<MeshEdge> {
+    let src_positions = mesh.get_vertices().get_positions();
+
+    let mut welded: HashMap<[u32; 3], u32> = HashMap::new();
+    let mut positions: Vec<Vec3> = Vec::new();
+    let remap: Vec<u32> = src_positions
+        .iter()
+        .map(|p| {
+            *welded.entry(weld_key(&p.0)).or_insert_with(|| {
+                positions.push(p.0);
+                (positions.len() - 1) as u32
+            })
+        })
+        .collect();
+
+    // collect the edges of all non-degenerate triangles together with the triangle normal
+    let mut edges: Vec<([u32; 2], Vec3)> = Vec::new();
+    for_each_triangle(mesh, |t| {
+        let t = t.map(|v| remap[v as usize]);
+        let p = t.map(|v| positions[v as usize]);
+        let n = cross(&(p[1] - p[0]), &(p[2] - p[0]));
+        let l = length(&n);
+        if l <= 0f32 {
+            return;
+        }
+
+        for i in 0..3 {
+            let (a, b) = (t[i], t[(i + 1) % 3]);
+            edges.push(([a.min(b), a.max(b)], n / l));
+        }
+    });
+
+    let cos_crease_angle = crease_angle.to_radians().cos();
+
+    // the adjacent triangles of an edge are consecutive after sorting
+    edges.sort_unstable_by_key(|(edge, _)| *edge);
+    let mut mesh_edges = Vec::new();
+    let mut i = 0;
+    while i < edges.len() {
+        let mut j = i + 1;
+        while j < edges.len() && edges[j].0 == edges[i].0 {
+            j += 1;
+        }
+
+        let kind = match j - i {
+            1 => EdgeKind::Boundary,
+            2 if dot(&edges[i].1, &edges[i + 1].1) < cos_crease_angle => EdgeKind::Crease,
+            2 => EdgeKind::Smooth,
+            _ => EdgeKind::NonManifold,
+        };
+
+        let [a, b] = edges[i].0;
+        mesh_edges.push(MeshEdge {
+            kind,
+            positions: [
+                Point3D(positions[a as usize]),
+                Point3D(positions[b as usize]),
+            ],
+            normals: edges[i..j].iter().map(|(_, n)| *n).collect(),
+        });
+
+        i = j;
+    }
+
+    mesh_edges
+}
+
+/// Returns the key for welding the given position, i.e., its exact bits with negative zeros
+/// replaced by positive zeros, s.t. positions only differing by the sign of zero are welded.
+///
+/// # Arguments
+/// * `p` - The position to weld.
+fn weld_key(p: &Vec3) -> [u32; 3] {
+    [0, 1, 2].map(|axis| {
+        if p[axis] == 0f32 {
+            0u32
+        } else {
+            p[axis].to_bits()
+        }
+    })
+}
+
+/// Returns the feature edges of the given triangle mesh as line segments, i.e., the boundary,
+/// non-manifold and crease edges.
+///
+/// # Arguments
+/// * `mesh` - The mesh whose feature edges are extracted.
+/// * `crease_angle` - The crease angle in degrees.
+pub fn extract_feature_edges(mesh: &Mesh, crease_angle: f32) -> Vec<[Point3D; 2]> {
+    extract_edges(mesh, crease_angle)
+        .into_iter()
+        .filter(|edge| edge.kind.is_feature())
+        .map(|edge| edge.positions)
+        .collect()
+}
+
+/// Returns the silhouette edges of the given edges for the given view direction as line
+/// segments, s.t. the outline of curved surfaces can be drawn in addition to the feature edges.
+///
+/// # Arguments
+/// * `edges` - The classified edges of a consistently oriented triangle mesh.
+/// * `view_direction` - The direction in which the mesh is viewed in its own coordinates.
+#[allow(dead_code)]
+pub fn extract_silhouette_edges(edges: &[MeshEdge], view_direction: &Vec3) -> Vec<[Point3D; 2]> {
+    edges
+        .iter()
+        .filter(|edge| edge.is_silhouette(view_direction))
+        .map(|edge| edge.positions)
+        .collect()
+}
+
+/// Creates a mesh of single lines from the given line segments.
+///
+/// # Arguments
+/// * `segments` - The line segments of the mesh.
+pub fn create_line_mesh(segments: &[[Point3D; 2]]) -> Result<Mesh> {
+    let positions: Vec<Point3D> = segments.iter().flatten().cloned().collect();
+    let num_indices = positions.len();
+
+    let vertices = Vertices::from_positions(positions);
+    let primitives = Primitives::new(IndexData::NonIndexed(num_indices), PrimitiveType::Line)?;
+
+    Ok(Mesh::new(vertices, primitives)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::test_meshes::{create_mesh, create_sphere, load_off_mesh};
+
+    /// Returns the number of edges of each kind in the order boundary, non-manifold, crease and
+    /// smooth.
+    fn count_kinds(edges: &[MeshEdge]) -> [usize; 4] {
+        [
+            EdgeKind::Boundary,
+            EdgeKind::NonManifold,
+            EdgeKind::Crease,
+            EdgeKind::Smooth,
+        ]
+        .map(|kind| edges.iter().filter(|edge| edge.kind == kind).count())
+    }
+
+    #[test]
+    fn test_cube_edges() {
+        let cube = load_off_mesh("examples/cube.off");
+
+        // the diagonals of the faces are flat and thus smooth
+        let edges = extract_edges(&cube, DEFAULT_CREASE_ANGLE);
+        assert_eq!(count_kinds(&edges), [0, 0, 12, 6]);
+        assert_eq!(extract_feature_edges(&cube, DEFAULT_CREASE_ANGLE).len(), 12);
+
+        // the right angles of the cube are only creases below 90 degrees
+        assert_eq!(count_kinds(&extract_edges(&cube, 95.0)), [0, 0, 0, 18]);
+    }
+
+    #[test]
+    fn test_closed_sphere_edges() {
+        let sphere = create_sphere(16, 32, false);
+
+        // the angle between neighboring triangles is far below the crease angle
+        let edges = extract_edges(&sphere, DEFAULT_CREASE_ANGLE);
+        assert_eq!(count_kinds(&edges), [0, 0, 0, 3 * 32 * 15]);
+    }
+
+    #[test]
+    fn test_open_mesh_edges() {
+        // a quad folded by 90 degrees along its diagonal
+        let positions = [
+            [0.0, 0.0, 0.0],
+            [1.0, 0.0, 0.0],
+            [1.0, 1.0, 0.0],
+            [0.0, 0.0, 1.0],
+        ];
+        let mesh = create_mesh(&positions, &[0, 1, 2, 0, 2, 3]);
+
+        let edges = extract_edges(&mesh, DEFAULT_CREASE_ANGLE);
+        assert_eq!(count_kinds(&edges), [4, 0, 1, 0]);
+
+        let flat = create_mesh(
+            &[
+                [0.0, 0.0, 0.0],
+                [1.0, 0.0, 0.0],
+                [1.0, 1.0, 0.0],
+                [0.0, 1.0, 0.0],
+            ],
+            &[0, 1, 2, 0, 2, 3],
+        );
+        assert_eq!(
+            count_kinds(&extract_edges(&flat, DEFAULT_CREASE_ANGLE)),
+            [4, 0, 0, 1]
+        );
+    }
+
+    #[test]
+    fn test_non_manifold_edges() {
+        // three triangles sharing the edge along the z-axis
+        let positions = [
+            [0.0, 0.0, 0.0],
+            [0.0, 0.0, 1.0],
+            [1.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0],
+            [-1.0, -1.0, 0.0],
+        ];
+        let mesh = create_mesh(&positions, &[0, 1, 2, 0, 1, 3, 0, 1, 4]);
+
+        let edges = extract_edges(&mesh, DEFAULT_CREASE_ANGLE);
+        assert_eq!(count_kinds(&edges), [6, 1, 0, 0]);
+
+        let non_manifold = edges
+            .iter()
+            .find(|edge| edge.kind == EdgeKind::NonManifold)
+            .unwrap();
+        assert_eq!(
+            non_manifold.positions,
+            [Point3D::new(0.0, 0.0, 0.0), Point3D::new(0.0, 0.0, 1.0)]
+        );
+    }
+
+    #[test]
+    fn test_weld_signed_zero() {
+        // the shared edge is duplicated with negative zeros, i.e., an attribute seam
+        let positions = [
+            [0.0, 0.0, 0.0],
+            [1.0, 0.0, 0.0],
+            [1.0, 1.0, 0.0],
+            [-0.0, -0.0, -0.0],
+            [1.0, 1.0, -0.0],
+            [0.0, 1.0, 0.0],
+        ];
+        let mesh = create_mesh(&positions, &[0, 1, 2, 3, 4, 5]);
+
+        let edges = extract_edges(&mesh, DEFAULT_CREASE_ANGLE);
+        assert_eq!(count_kinds(&edges), [4, 0, 0, 1]);
+    }
+
+    #[test]
+    fn test_degenerate_triangles() {
+        let positions = [[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [2.0, 0.0, 0.0]];
+        let mesh = create_mesh(&positions, &[0, 1, 2]);
+
+        assert!(extract_edges(&mesh, DEFAULT_CREASE_ANGLE).is_empty());
+    }
+
+    #[test]
+    fn test_cube_silhouette() {
+        let edges = extract_edges(&load_off_mesh("examples/cube.off"), DEFAULT_CREASE_ANGLE);
+
+        // seen along an axis, the side faces are edge-on and the front face is outlined
+        let silhouette = extract_silhouette_edges(&edges, &Vec3::new(0.0, 0.0, -1.0));
+        assert_eq!(silhouette.len(), 4);
+        assert!(silhouette
+            .iter()
+            .flatten()
+            .all(|p| p.0.z == silhouette[0][0].0.z));
+
+        // seen obliquely, three faces face the viewer and the outline is a hexagon
+        let silhouette = extract_silhouette_edges(&edges, &Vec3::new(-1.0, -2.0, -3.0));
+        assert_eq!(silhouette.len(), 6);
+        assert_eq!(
+            extract_silhouette_edges(&edges, &Vec3::new(1.0, 2.0, 3.0)).len(),
+            6
+        );
+    }
+
+    #[test]
+    fn test_sphere_silhouette() {
+        let edges = extract_edges(&create_sphere(16, 32, false), DEFAULT_CREASE_ANGLE);
+
+        // the silhouette seen from above is the equator, although all edges are smooth
+        let silhouette = extract_silhouette_edges(&edges, &Vec3::new(0.0, 0.0, -1.0));
+        assert_eq!(silhouette.len(), 32);
+        assert!(silhouette.iter().flatten().all(|p| p.0.z.abs() < 1e-6));
+    }
+
+    #[test]
+    fn test_silhouette_needs_two_triangles() {
+        // a quad folded by 90 degrees, whose second triangle is seen edge-on from above
+        let positions = [
+            [0.0, 0.0, 0.0],
+            [1.0, 0.0, 0.0],
+            [1.0, 1.0, 0.0],
+            [0.0, 0.0, 1.0],
+        ];
+        let mesh = create_mesh(&positions, &[0, 1, 2, 0, 2, 3]);
+        let view_direction = Vec3::new(0.0, 0.0, -1.0);
+
+        let edges = extract_edges(&mesh, DEFAULT_CREASE_ANGLE);
+        for edge in &edges {
+            assert_eq!(
+                edge.is_silhouette(&view_direction),
+                edge.kind == EdgeKind::Crease,
+                "{:?}",
+                edge
+            );
+        }
+
+        // the non-manifold edge is never a silhouette
+        let positions = [
+            [0.0, 0.0, 0.0],
+            [0.0, 0.0, 1.0],
+            [1.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0],
+            [-1.0, -1.0, 0.0],
+        ];
+        let mesh = create_mesh(&positions, &[0, 1, 2, 0, 1, 3, 0, 1, 4]);
+        let edges = extract_edges(&mesh, DEFAULT_CREASE_ANGLE);
+        assert!(extract_silhouette_edges(&edges, &Vec3::new(1.0, 0.5, 0.0)).is_empty());
+    }
+}
@@ -0,0 +1,155 @@
+use nalgebra_glm::{Mat4, Vec3, Vec4};
+
+use super::BBox;
+
+/// The view frustum given by its six planes. The planes point inwards, i.e., a point p is on
+/// the inner side of a plane (a,b,c,d) if a*p.x + b*p.y + c*p.z + d >= 0.
+#[derive(Debug, Clone)]
+pub struct Frustum {
+    planes: [Vec4; 6],
+}
+
+impl Frustum {
+    /// Extracts the frustum planes from the given combined projection and model view matrix.
+    ///
+    /// # Arguments
+    /// * `combined_mat` - The combined projection and model view matrix.
+    pub fn from_matrix(combined_mat: &Mat4) -> Self {
+        let r0: Vec4 = combined_mat.row(0).transpose();
+        let r1: Vec4 = combined_mat.row(1).transpose();
+        let r2: Vec4 = combined_mat.row(2).transpose();
+        let r3: Vec4 = combined_mat.row(3).transpose();
+
+        // left, right, bottom, top, near and far plane
+        let planes = [r3 + r0, r3 - r0, r3 + r1, r3 - r1, r3 + r2, r3 - r2];
+
+        Self { planes }
+    }
+
+    /// Returns false if the given bounding volume is completely outside of the frustum. The
+    /// test is conservative, i.e., some volumes outside of the frustum near its edges are still
+    /// reported as intersecting.
+    ///
+    /// # Arguments
+    /// * `bbox` - The bounding volume to test.
+    pub fn intersects_bbox(&self, bbox: &BBox) -> bool {
+        if bbox.is_empty() {
+            return false;
+        }
+
+        self.planes.iter().all(|plane| {
+            // the corner of the volume which is the farthest along the plane normal
+            let p = Vec3::new(
+                if plane.x >= 0f32 {
+                    bbox.max.x
+                } else {
+                    bbox.min.x
+                },
+                if plane.y >= 0f32 {
+                    bbox.max.y
+                } else {
+                    bbox.min.y
+                },
+                if plane.z >= 0f32 {
+                    bbox.max.z
+                } else {
+                    bbox.min.z
+                },
+            );
+
+            plane.x * p.x + plane.y * p.y + plane.z * p.z + plane.w >= 0f32
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra_glm::{look_at, perspective};
+
+    use super::*;
+
+    /// Returns the frustum of a camera at (0,0,10) looking towards the origin.
+    fn camera_frustum() -> Frustum {
+        let projection = perspective(1f32, std::f32::consts::FRAC_PI_2, 1f32, 100f32);
+        let view = look_at(
+            &Vec3::new(0.0, 0.0, 10.0),
+            &Vec3::zeros(),
+            &Vec3::new(0.0, 1.0, 0.0),
+        );
+
+        Frustum::from_matrix(&(projection * view))
+    }
+
+    fn bbox(min: Vec3, max: Vec3) -> BBox {
+        let mut bbox = BBox::new();
+        bbox.extend_pos(&min);
+        bbox.extend_pos(&max);
+
+        bbox
+    }
+
+    #[test]
+    fn test_inside() {
+        let frustum = camera_frustum();
+
+        let b = bbox(Vec3::new(-1.0, -1.0, -1.0), Vec3::new(1.0, 1.0, 1.0));
+        assert!(frustum.intersects_bbox(&b));
+
+        // a volume enclosing the whole frustum
+        let b = bbox(Vec3::from_element(-1000.0), Vec3::from_element(1000.0));
+        assert!(frustum.intersects_bbox(&b));
+    }
+
+    #[test]
+    fn test_outside() {
+        let frustum = camera_frustum();
+
+        // left of the frustum, which is 10 units wide at the origin
+        let b = bbox(Vec3::new(-30.0, -1.0, -1.0), Vec3::new(-20.0, 1.0, 1.0));
+        assert!(!frustum.intersects_bbox(&b));
+
+        // above the frustum
+        let b = bbox(Vec3::new(-1.0, 20.0, -1.0), Vec3::new(1.0, 30.0, 1.0));
+        assert!(!frustum.intersects_bbox(&b));
+
+        // beyond the far plane
+        let b = bbox(Vec3::new(-1.0, -1.0, -200.0), Vec3::new(1.0, 1.0, -150.0));
+        assert!(!frustum.intersects_bbox(&b));
+
+        assert!(!frustum.intersects_bbox(&BBox::new()));
+    }
+
+    #[test]
+    fn test_straddling() {
+        let frustum = camera_frustum();
+
+        // across the left plane
+        let b = bbox(Vec3::new(-15.0, -1.0, -1.0), Vec3::new(-5.0, 1.0, 1.0));
+        assert!(frustum.intersects_bbox(&b));
+
+        // across the near plane
+        let b = bbox(Vec3::new(-0.5, -0.5, 8.0), Vec3::new(0.5, 0.5, 12.0));
+        assert!(frustum.intersects_bbox(&b));
+
+        // across the far plane
+        let b = bbox(Vec3::new(-1.0, -1.0, -100.0), Vec3::new(1.0, 1.0, -80.0));
+        assert!(frustum.intersects_bbox(&b));
+    }
+
+    #[test]
+    fn test_behind_camera() {
+        let frustum = camera_frustum();
+
+        // directly behind the camera
+        let b = bbox(Vec3::new(-1.0, -1.0, 12.0), Vec3::new(1.0, 1.0, 14.0));
+        assert!(!frustum.intersects_bbox(&b));
+
+        // behind the camera and within the mirrored frustum
+        let b = bbox(Vec3::new(-50.0, -50.0, 50.0), Vec3::new(50.0, 50.0, 60.0));
+        assert!(!frustum.intersects_bbox(&b));
+
+        // between the camera and the near plane
+        let b = bbox(Vec3::new(-0.1, -0.1, 9.2), Vec3::new(0.1, 0.1, 9.8));
+        assert!(!frustum.intersects_bbox(&b));
+    }
+}
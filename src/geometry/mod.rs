@@ -0,0 +1,19 @@
+mod bbox;
+mod edges;
+mod frustum;
+mod plane;
+mod ray;
+mod simplify;
+#[cfg(test)]
+mod test_meshes;
+mod transform;
+mod triangles;
+
+pub use bbox::BBox;
+pub use edges::{create_line_mesh, extract_feature_edges, DEFAULT_CREASE_ANGLE};
+pub use frustum::Frustum;
+pub use plane::Plane;
+pub use ray::Ray;
+pub use simplify::create_lods;
+pub use transform::compute_normal_matrix;
+pub use triangles::for_each_triangle;
@@ -0,0 +1,247 @@
+use std::{fmt::Display, str::FromStr};
+
+use anyhow::{bail, Context};
+use nalgebra_glm::{cross, dot, length, normalize, Vec3, Vec4};
+
+use super::BBox;
+
+/// A plane given by its unit normal and its distance from the origin along the normal, i.e., a
+/// point p is on the plane if dot(normal, p) = distance. The half-space into which the normal
+/// points is the front side of the plane.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Plane {
+    pub normal: Vec3,
+    pub distance: f32,
+}
+
+impl Plane {
+    /// Creates a new plane from the given normal, which is normalized, and distance.
+    ///
+    /// # Arguments
+    /// * `normal` - The normal of the plane, must not be zero.
+    /// * `distance` - The distance of the plane from the origin along the normal.
+    pub fn new(normal: Vec3, distance: f32) -> Self {
+        Self {
+            normal: normalize(&normal),
+            distance,
+        }
+    }
+
+    /// Creates the plane through the center of the given bounding volume, which is orthogonal
+    /// to the given coordinate axis. The normal points along the positive axis.
+    ///
+    /// # Arguments
+    /// * `bbox` - The bounding volume whose center is on the plane.
+    /// * `axis` - The index of the coordinate axis, i.e., 0, 1 or 2 for x, y and z.
+    pub fn from_bbox_axis(bbox: &BBox, axis: usize) -> Self {
+        let mut normal = Vec3::zeros();
+        normal[axis % 3] = 1f32;
+
+        Self {
+            normal,
+            distance: bbox.get_center()[axis % 3],
+        }
+    }
+
+    /// Returns the signed distance of the given point to the plane, which is positive on the
+    /// front side.
+    ///
+    /// # Arguments
+    /// * `p` - The point whose distance is computed.
+    pub fn signed_distance(&self, p: &Vec3) -> f32 {
+        dot(&self.normal, p) - self.distance
+    }
+
+    /// Returns the same plane with the front and back side swapped.
+    pub fn flip(&self) -> Self {
+        Self {
+            normal: -self.normal,
+            distance: -self.distance,
+        }
+    }
+
+    /// Returns the range of distances for which the plane intersects the given bounding
+    /// volume, i.e., the minimal and maximal projection of its corners onto the normal.
+    ///
+    /// # Arguments
+    /// * `bbox` - The bounding volume to project.
+    pub fn get_distance_range(&self, bbox: &BBox) -> (f32, f32) {
+        let center = bbox.get_center();
+        let extent = bbox.get_size() * 0.5f32;
+        let radius = dot(&extent, &self.normal.abs());
+        let d = dot(&self.normal, &center);
+
+        (d - radius, d + radius)
+    }
+
+    /// Returns the plane moved by the given offset along its normal. The distance is clamped,
+    /// s.t. the plane still intersects the given bounding volume.
+    ///
+    /// # Arguments
+    /// * `offset` - The offset to move the plane by.
+    /// * `bbox` - The bounding volume the plane is restricted to.
+    pub fn translate(&self, offset: f32, bbox: &BBox) -> Self {
+        let (min, max) = self.get_distance_range(bbox);
+
+        Self {
+            normal: self.normal,
+            distance: (self.distance + offset).clamp(min, max),
+        }
+    }
+
+    /// Returns the corners of a square on the plane, which covers the section of the plane with
+    /// the given bounding volume. The corners are ordered for drawing a triangle strip.
+    ///
+    /// # Arguments
+    /// * `bbox` - The bounding volume to cover.
+    pub fn get_quad(&self, bbox: &BBox) -> [Vec3; 4] {
+        let center = bbox.get_center();
+        let center = center - self.normal * self.signed_distance(&center);
+        let radius = length(&bbox.get_size());
+
+        // any axis which is not parallel to the normal spans the plane
+        let axis = if self.normal.x.abs() < 0.9f32 {
+            Vec3::new(1f32, 0f32, 0f32)
+        } else {
+            Vec3::new(0f32, 1f32, 0f32)
+        };
+        let u = normalize(&cross(&self.normal, &axis)) * radius;
+        let v = cross(&self.normal, &u);
+
+        [
+            center - u - v,
+            center + u - v,
+            center - u + v,
+            center + u + v,
+        ]
+    }
+
+    /// Returns the plane as coefficients (a,b,c,d), s.t. a*p.x + b*p.y + c*p.z + d is the
+    /// signed distance of a point p.
+    pub fn to_vec4(self) -> Vec4 {
+        Vec4::new(self.normal.x, self.normal.y, self.normal.z, -self.distance)
+    }
+}
+
+impl Display for Plane {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} {} {} {}",
+            self.normal.x, self.normal.y, self.normal.z, self.distance
+        )
+    }
+}
+
+impl FromStr for Plane {
+    type Err = anyhow::Error;
+
+    /// Parses a plane given in the form `<NX> <NY> <NZ> <DISTANCE>`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let values = s
+            .split_whitespace()
+            .map(|value| value.parse::<f32>())
+            .collect::<Result<Vec<f32>, _>>()
+            .context(format!("Invalid plane '{}'", s))?;
+
+        if values.len() != 4 {
+            bail!("Invalid plane '{}', expected <NX> <NY> <NZ> <DISTANCE>", s);
+        }
+
+        let normal = Vec3::new(values[0], values[1], values[2]);
+        if length(&normal) <= 0f32 {
+            bail!("Invalid plane '{}', the normal must not be zero", s);
+        }
+
+        Ok(Self::new(normal, values[3]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bbox() -> BBox {
+        let mut bbox = BBox::new();
+        bbox.extend_pos(&Vec3::new(-1.0, 2.0, 0.0));
+        bbox.extend_pos(&Vec3::new(3.0, 4.0, 10.0));
+
+        bbox
+    }
+
+    #[test]
+    fn test_from_bbox_axis() {
+        let center = bbox().get_center();
+        for axis in 0..3 {
+            let plane = Plane::from_bbox_axis(&bbox(), axis);
+
+            let mut normal = Vec3::zeros();
+            normal[axis] = 1.0;
+            assert_eq!(plane.normal, normal);
+            assert_eq!(plane.distance, center[axis]);
+            assert_eq!(plane.signed_distance(&center), 0.0);
+        }
+
+        // the axes wrap around
+        assert_eq!(
+            Plane::from_bbox_axis(&bbox(), 4),
+            Plane::from_bbox_axis(&bbox(), 1)
+        );
+    }
+
+    #[test]
+    fn test_signed_distance() {
+        let plane = Plane::new(Vec3::new(0.0, 0.0, 2.0), 1.0);
+        assert_eq!(plane.normal, Vec3::new(0.0, 0.0, 1.0));
+        assert_eq!(plane.signed_distance(&Vec3::new(5.0, 5.0, 3.0)), 2.0);
+        assert_eq!(plane.signed_distance(&Vec3::new(5.0, 5.0, -1.0)), -2.0);
+
+        let flipped = plane.flip();
+        assert_eq!(flipped.signed_distance(&Vec3::new(5.0, 5.0, 3.0)), -2.0);
+
+        // the coefficients evaluate to the signed distance
+        let p = Vec3::new(1.0, 2.0, 3.0);
+        let coefficients = plane.to_vec4();
+        assert_eq!(dot(&coefficients, &p.push(1.0)), plane.signed_distance(&p));
+    }
+
+    #[test]
+    fn test_translate_clamps_offset() {
+        let plane = Plane::from_bbox_axis(&bbox(), 2);
+        assert_eq!(plane.get_distance_range(&bbox()), (0.0, 10.0));
+
+        assert_eq!(plane.translate(2.0, &bbox()).distance, 7.0);
+        assert_eq!(plane.translate(-4.5, &bbox()).distance, 0.5);
+        assert_eq!(plane.translate(100.0, &bbox()).distance, 10.0);
+        assert_eq!(plane.translate(-100.0, &bbox()).distance, 0.0);
+
+        // the range of a flipped plane is negated
+        let flipped = plane.flip();
+        assert_eq!(flipped.translate(100.0, &bbox()).distance, 0.0);
+        assert_eq!(flipped.translate(-100.0, &bbox()).distance, -10.0);
+    }
+
+    #[test]
+    fn test_translate_diagonal_plane() {
+        // the range of a diagonal plane covers the projections of the corners
+        let plane = Plane::new(Vec3::new(1.0, 1.0, 0.0), 0.0);
+        let (min, max) = plane.get_distance_range(&bbox());
+        let sqrt2 = 2f32.sqrt();
+        assert!((min - 1.0 / sqrt2).abs() < 1e-5);
+        assert!((max - 7.0 / sqrt2).abs() < 1e-5);
+
+        assert!((plane.translate(-1.0, &bbox()).distance - min).abs() < 1e-5);
+        assert!((plane.translate(10.0, &bbox()).distance - max).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_parse() {
+        let plane: Plane = "0 0 2 1.5".parse().unwrap();
+        assert_eq!(plane, Plane::new(Vec3::new(0.0, 0.0, 1.0), 1.5));
+        assert_eq!(plane.to_string().parse::<Plane>().unwrap(), plane);
+
+        assert!("0 0 0 1".parse::<Plane>().is_err());
+        assert!("1 0 0".parse::<Plane>().is_err());
+        assert!("1 0 x 0".parse::<Plane>().is_err());
+    }
+}
@@ -0,0 +1,340 @@
+use cad_import::structure::Mesh;
+use nalgebra_glm::{cross, dot, inverse, length, vec4_to_vec3, Mat4, Vec3, Vec4};
+
+use super::{for_each_triangle, BBox};
+
+/// The tolerance for detecting rays parallel to a triangle. It is relative to the lengths of the
+/// ray direction and the triangle edges, i.e., it bounds the sine of the angle between the ray
+/// and the triangle plane.
+const PARALLEL_EPSILON: f32 = 1e-7;
+
+/// A ray defined by an origin and a direction, i.e., p(t) = origin + t * dir.
+#[derive(Debug, Clone, Copy)]
+pub struct Ray {
+    pub origin: Vec3,
+    pub dir: Vec3,
+}
+
+impl Ray {
+    /// Creates a new ray.
+    ///
+    /// # Arguments
+    /// * `origin` - The origin of the ray.
+    /// * `dir` - The direction of the ray. It doesn't need to be normalized.
+    pub fn new(origin: Vec3, dir: Vec3) -> Self {
+        Self { origin, dir }
+    }
+
+    /// Creates the ray through the given point in normalized device coordinates, i.e., the ray
+    /// starts on the near plane and points towards the far plane.
+    ///
+    /// # Arguments
+    /// * `combined_mat` - The combined projection and model view matrix.
+    /// * `x` - The x coordinate in normalized device coordinates, i.e., in [-1,1].
+    /// * `y` - The y coordinate in normalized device coordinates, i.e., in [-1,1].
+    pub fn from_ndc(combined_mat: &Mat4, x: f32, y: f32) -> Self {
+        let inv = inverse(combined_mat);
+
+        let unproject = |z: f32| -> Vec3 {
+            let p = inv * Vec4::new(x, y, z, 1f32);
+            vec4_to_vec3(&p) / p.w
+        };
+
+        let near = unproject(-1f32);
+        let far = unproject(1f32);
+
+        Self::new(near, far - near)
+    }
+
+    /// Returns the point on the ray for the given ray parameter.
+    ///
+    /// # Arguments
+    /// * `t` - The ray parameter.
+    #[inline]
+    pub fn at(&self, t: f32) -> Vec3 {
+        self.origin + self.dir * t
+    }
+
+    /// Returns the ray transformed by the given matrix. The ray parameters of the transformed
+    /// ray are the same as for the original ray.
+    ///
+    /// # Arguments
+    /// * `m` - The transformation matrix.
+    pub fn transform(&self, m: &Mat4) -> Self {
+        let o = m * Vec4::new(self.origin.x, self.origin.y, self.origin.z, 1f32);
+        let d = m * Vec4::new(self.dir.x, self.dir.y, self.dir.z, 0f32);
+
+        Self::new(vec4_to_vec3(&o), vec4_to_vec3(&d))
+    }
+
+    /// Intersects the ray with the given triangle and returns the ray parameter of the hit if
+    /// there is any. Both sides of the triangle are hit and only non-negative ray parameters
+    /// are reported.
+    ///
+    /// # Arguments
+    /// * `v0` - The first vertex of the triangle.
+    /// * `v1` - The second vertex of the triangle.
+    /// * `v2` - The third vertex of the triangle.
+    pub fn intersect_triangle(&self, v0: &Vec3, v1: &Vec3, v2: &Vec3) -> Option<f32> {
+        // Möller–Trumbore intersection
+        let e1 = v1 - v0;
+        let e2 = v2 - v0;
+
+        let p = cross(&self.dir, &e2);
+        let det = dot(&e1, &p);
+        let scale = length(&self.dir) * length(&e1) * length(&e2);
+        if det.abs() <= PARALLEL_EPSILON * scale {
+            return None;
+        }
+
+        let inv_det = 1f32 / det;
+        let s = self.origin - v0;
+        let u = dot(&s, &p) * inv_det;
+        if !(0f32..=1f32).contains(&u) {
+            return None;
+        }
+
+        let q = cross(&s, &e1);
+        let v = dot(&self.dir, &q) * inv_det;
+        if v < 0f32 || u + v > 1f32 {
+            return None;
+        }
+
+        let t = dot(&e2, &q) * inv_det;
+        if t < 0f32 {
+            return None;
+        }
+
+        Some(t)
+    }
+
+    /// Intersects the ray with the given bounding volume and returns the ray parameter where the
+    /// ray enters the volume if there is any. If the origin is inside the volume, zero is
+    /// returned.
+    ///
+    /// # Arguments
+    /// * `bbox` - The bounding volume to intersect with.
+    pub fn intersect_bbox(&self, bbox: &BBox) -> Option<f32> {
+        if bbox.is_empty() {
+            return None;
+        }
+
+        let mut t_min = 0f32;
+        let mut t_max = f32::INFINITY;
+
+        // slab test, i.e., clip the ray against the pair of planes of each axis
+        for axis in 0..3 {
+            let (o, d) = (self.origin[axis], self.dir[axis]);
+            let (min, max) = (bbox.get_min()[axis], bbox.get_max()[axis]);
+
+            if d == 0f32 {
+                // the ray is parallel to the planes and never enters the slab from outside
+                if o < min || o > max {
+                    return None;
+                }
+
+                continue;
+            }
+
+            let t0 = (min - o) / d;
+            let t1 = (max - o) / d;
+            let (t0, t1) = if t0 <= t1 { (t0, t1) } else { (t1, t0) };
+
+            t_min = t_min.max(t0);
+            t_max = t_max.min(t1);
+            if t_min > t_max {
+                return None;
+            }
+        }
+
+        Some(t_min)
+    }
+
+    /// Intersects the ray with all triangles of the given mesh and returns the ray parameter of
+    /// the closest hit accepted by the given filter if there is any.
+    ///
+    /// # Arguments
+    /// * `mesh` - The mesh to intersect with.
+    /// * `accept` - Returns true if the hit at the given ray parameter is accepted, e.g., hits
+    ///   cut away by clip planes are skipped.
+    pub fn intersect_mesh(&self, mesh: &Mesh, accept: impl Fn(f32) -> bool) -> Option<f32> {
+        let positions = mesh.get_vertices().get_positions();
+        let mut closest: Option<f32> = None;
+
+        for_each_triangle(mesh, |[i0, i1, i2]| {
+            let v0 = &positions[i0 as usize].0;
+            let v1 = &positions[i1 as usize].0;
+            let v2 = &positions[i2 as usize].0;
+
+            if let Some(t) = self.intersect_triangle(v0, v1, v2) {
+                if closest.is_none_or(|closest| t < closest) && accept(t) {
+                    closest = Some(t);
+                }
+            }
+        });
+
+        closest
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::test_meshes::load_off_mesh;
+
+    fn triangle() -> [Vec3; 3] {
+        [
+            Vec3::new(0.0, 0.0, 0.0),
+            Vec3::new(1.0, 0.0, 0.0),
+            Vec3::new(0.0, 1.0, 0.0),
+        ]
+    }
+
+    fn unit_bbox() -> BBox {
+        let mut bbox = BBox::new();
+        bbox.extend_pos(&Vec3::new(-1.0, -1.0, -1.0));
+        bbox.extend_pos(&Vec3::new(1.0, 1.0, 1.0));
+
+        bbox
+    }
+
+    #[test]
+    fn test_intersect_triangle_hit() {
+        let [v0, v1, v2] = triangle();
+
+        // both sides are hit
+        let ray = Ray::new(Vec3::new(0.25, 0.25, 2.0), Vec3::new(0.0, 0.0, -1.0));
+        assert_eq!(ray.intersect_triangle(&v0, &v1, &v2), Some(2.0));
+
+        let ray = Ray::new(Vec3::new(0.25, 0.25, -2.0), Vec3::new(0.0, 0.0, 4.0));
+        assert_eq!(ray.intersect_triangle(&v0, &v1, &v2), Some(0.5));
+    }
+
+    #[test]
+    fn test_intersect_triangle_miss() {
+        let [v0, v1, v2] = triangle();
+
+        let ray = Ray::new(Vec3::new(0.75, 0.75, 2.0), Vec3::new(0.0, 0.0, -1.0));
+        assert_eq!(ray.intersect_triangle(&v0, &v1, &v2), None);
+
+        let ray = Ray::new(Vec3::new(-0.25, 0.25, 2.0), Vec3::new(0.0, 0.0, -1.0));
+        assert_eq!(ray.intersect_triangle(&v0, &v1, &v2), None);
+
+        // the triangle is behind the origin
+        let ray = Ray::new(Vec3::new(0.25, 0.25, 2.0), Vec3::new(0.0, 0.0, 1.0));
+        assert_eq!(ray.intersect_triangle(&v0, &v1, &v2), None);
+    }
+
+    #[test]
+    fn test_intersect_triangle_edge() {
+        let [v0, v1, v2] = triangle();
+
+        // the edges and the vertices belong to the triangle
+        for p in [
+            Vec3::new(0.5, 0.0, 2.0),
+            Vec3::new(0.0, 0.5, 2.0),
+            Vec3::new(0.5, 0.5, 2.0),
+            Vec3::new(1.0, 0.0, 2.0),
+        ] {
+            let ray = Ray::new(p, Vec3::new(0.0, 0.0, -1.0));
+            let t = ray.intersect_triangle(&v0, &v1, &v2);
+            assert!(
+                t.is_some_and(|t| (t - 2.0).abs() < 1e-6),
+                "{:?}: {:?}",
+                p,
+                t
+            );
+        }
+    }
+
+    #[test]
+    fn test_intersect_triangle_parallel() {
+        let [v0, v1, v2] = triangle();
+
+        // in the plane of the triangle
+        let ray = Ray::new(Vec3::new(-1.0, 0.25, 0.0), Vec3::new(1.0, 0.0, 0.0));
+        assert_eq!(ray.intersect_triangle(&v0, &v1, &v2), None);
+
+        // grazing rays, which only deviate numerically from the plane, are treated as parallel
+        let ray = Ray::new(Vec3::new(-1.0, 0.25, 1e-8), Vec3::new(1.0, 0.0, -1e-8));
+        assert_eq!(ray.intersect_triangle(&v0, &v1, &v2), None);
+
+        // the tolerance is relative, i.e., tiny triangles are still hit
+        let scale = 1e-7;
+        let ray = Ray::new(
+            Vec3::new(0.25, 0.25, 1.0) * scale,
+            Vec3::new(0.0, 0.0, -1.0),
+        );
+        let t = ray.intersect_triangle(&(v0 * scale), &(v1 * scale), &(v2 * scale));
+        assert!(t.is_some_and(|t| (t - scale).abs() < 1e-12), "{:?}", t);
+    }
+
+    #[test]
+    fn test_intersect_bbox() {
+        let bbox = unit_bbox();
+
+        let ray = Ray::new(Vec3::new(0.0, 0.0, 5.0), Vec3::new(0.0, 0.0, -1.0));
+        assert_eq!(ray.intersect_bbox(&bbox), Some(4.0));
+
+        let ray = Ray::new(Vec3::new(-3.0, -3.0, -3.0), Vec3::new(1.0, 1.0, 1.0));
+        assert_eq!(ray.intersect_bbox(&bbox), Some(2.0));
+
+        // the origin is inside
+        let ray = Ray::new(Vec3::new(0.5, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0));
+        assert_eq!(ray.intersect_bbox(&bbox), Some(0.0));
+    }
+
+    #[test]
+    fn test_intersect_bbox_miss() {
+        let bbox = unit_bbox();
+
+        let ray = Ray::new(Vec3::new(0.0, 0.0, 5.0), Vec3::new(0.0, 0.0, 1.0));
+        assert_eq!(ray.intersect_bbox(&bbox), None);
+
+        let ray = Ray::new(Vec3::new(0.0, 3.0, 5.0), Vec3::new(1.0, 0.0, -1.0));
+        assert_eq!(ray.intersect_bbox(&bbox), None);
+
+        let ray = Ray::new(Vec3::new(0.0, 0.0, 5.0), Vec3::new(0.0, 0.0, -1.0));
+        assert_eq!(ray.intersect_bbox(&BBox::new()), None);
+    }
+
+    #[test]
+    fn test_intersect_bbox_parallel() {
+        let bbox = unit_bbox();
+
+        // parallel to the x and y slabs, but inside of them
+        let ray = Ray::new(Vec3::new(0.5, 0.5, 5.0), Vec3::new(0.0, 0.0, -1.0));
+        assert_eq!(ray.intersect_bbox(&bbox), Some(4.0));
+
+        // grazing along a face
+        let ray = Ray::new(Vec3::new(1.0, 0.0, 5.0), Vec3::new(0.0, 0.0, -1.0));
+        assert_eq!(ray.intersect_bbox(&bbox), Some(4.0));
+
+        // parallel and outside of the x slab
+        let ray = Ray::new(Vec3::new(1.5, 0.0, 5.0), Vec3::new(0.0, 0.0, -1.0));
+        assert_eq!(ray.intersect_bbox(&bbox), None);
+    }
+
+    #[test]
+    fn test_intersect_mesh() {
+        let cube = load_off_mesh("examples/cube.off");
+        let mut bbox = BBox::new();
+        bbox.extend_mesh(&cube);
+        let center = bbox.get_center();
+
+        // the ray enters and leaves the cube, where the front hit is the closest
+        let origin = center - Vec3::new(0.0, 0.0, bbox.get_size().z * 2.0);
+        let ray = Ray::new(origin, Vec3::new(0.0, 0.0, 1.0));
+        let front = ray.intersect_mesh(&cube, |_| true).unwrap();
+        assert!((ray.at(front).z - bbox.get_min().z).abs() < 1e-5);
+
+        // skipping the front hit returns the back hit
+        let back = ray.intersect_mesh(&cube, |t| t > front).unwrap();
+        assert!((ray.at(back).z - bbox.get_max().z).abs() < 1e-5);
+
+        assert_eq!(ray.intersect_mesh(&cube, |_| false), None);
+
+        let miss = Ray::new(origin, Vec3::new(0.0, 1.0, 0.0));
+        assert_eq!(miss.intersect_mesh(&cube, |_| true), None);
+    }
+}
@@ -0,0 +1,832 @@
+use std::{
+    cmp::Ordering,
+    collections::{BinaryHeap, HashMap},
+};
+
+use anyhow::{bail, Result};
+use cad_import::structure::{IndexData, Mesh, Point3D, PrimitiveType, Primitives, Vertices};
+use nalgebra_glm::{cross, dot, length, normalize, DMat3, DVec3};
+
+use super::triangles::for_each_triangle;
+
+/// The weight of the planes added along boundary edges for preserving the boundary.
+const BOUNDARY_WEIGHT: f64 = 1000.0;
+
+/// The tolerance for detecting singular quadric matrices.
+const SINGULAR_EPSILON: f64 = 1e-12;
+
+/// The angle in degrees between adjacent triangles up to which the normals of a simplified
+/// mesh are smoothed across their shared vertex.
+const NORMAL_CREASE_ANGLE: f64 = 30.0;
+
+/// The minimal number of triangles of a mesh for creating levels of detail.
+const MIN_LOD_TRIANGLES: usize = 256;
+
+/// The maximal ratio of triangles between a level of detail and the previous level. Coarser
+/// levels are only kept if they reduce the number of triangles sufficiently.
+const MAX_LOD_RATIO: f32 = 0.75;
+
+/// A symmetric 4x4 matrix measuring the squared distance of a point to a set of planes.
+#[derive(Debug, Clone, Copy, Default)]
+struct Quadric {
+    /// The upper triangle of the matrix in row major order.
+    m: [f64; 10],
+}
+
+impl Quadric {
+    /// Returns the weighted quadric of the plane with the given normal through the given point.
+    ///
+    /// # Arguments
+    /// * `normal` - The normalized normal of the plane.
+    /// * `p` - A point on the plane.
+    /// * `weight` - The weight of the plane.
+    fn from_plane(normal: &DVec3, p: &DVec3, weight: f64) -> Self {
+        let (a, b, c) = (normal[0], normal[1], normal[2]);
+        let d = -dot(normal, p);
+
+        Self {
+            m: [
+                a * a * weight,
+                a * b * weight,
+                a * c * weight,
+                a * d * weight,
+                b * b * weight,
+                b * c * weight,
+                b * d * weight,
+                c * c * weight,
+                c * d * weight,
+                d * d * weight,
+            ],
+        }
+    }
+
+    /// Adds the given quadric.
+    ///
+    /// # Arguments
+    /// * `rhs` - The quadric to add.
+    fn add(&mut self, rhs: &Quadric) {
+        for (a, b) in self.m.iter_mut().zip(rhs.m.iter()) {
+            *a += b;
+        }
+    }
+
+    /// Returns the sum of the squared weighted distances of the point to the planes.
+    ///
+    /// # Arguments
+    /// * `p` - The point to evaluate.
+    fn evaluate(&self, p: &DVec3) -> f64 {
+        let m = &self.m;
+        let (x, y, z) = (p[0], p[1], p[2]);
+
+        m[0] * x * x
+            + 2.0 * m[1] * x * y
+            + 2.0 * m[2] * x * z
+            + 2.0 * m[3] * x
+            + m[4] * y * y
+            + 2.0 * m[5] * y * z
+            + 2.0 * m[6] * y
+            + m[7] * z * z
+            + 2.0 * m[8] * z
+            + m[9]
+    }
+
+    /// Returns the point minimizing the quadric or none if the minimum is not unique.
+    fn minimize(&self) -> Option<DVec3> {
+        let m = &self.m;
+        let a = DMat3::new(m[0], m[1], m[2], m[1], m[4], m[5], m[2], m[5], m[7]);
+        if a.determinant().abs() <= SINGULAR_EPSILON {
+            return None;
+        }
+
+        a.try_inverse()
+            .map(|inv| inv * DVec3::new(-m[3], -m[6], -m[8]))
+    }
+}
+
+/// A candidate for collapsing an edge into a single vertex.
+#[derive(Debug, Clone, Copy)]
+struct Collapse {
+    /// The error introduced by the collapse.
+    cost: f64,
+
+    /// The vertex the edge collapses into.
+    v0: u32,
+
+    /// The vertex removed by the collapse.
+    v1: u32,
+
+    /// The versions of both vertices when the candidate has been computed.
+    versions: [u32; 2],
+
+    /// The position of the vertex after the collapse.
+    position: DVec3,
+}
+
+impl PartialEq for Collapse {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Collapse {}
+
+impl PartialOrd for Collapse {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Collapse {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // the cheapest collapse has the highest priority, ties are broken by the vertices
+        other
+            .cost
+            .total_cmp(&self.cost)
+            .then_with(|| (other.v0, other.v1).cmp(&(self.v0, self.v1)))
+    }
+}
+
+/// The state of the simplification of a triangle mesh.
+struct Simplifier {
+    /// True if the simplified mesh gets normals, as the source mesh has normals.
+    has_normals: bool,
+
+    positions: Vec<DVec3>,
+    quadrics: Vec<Quadric>,
+    versions: Vec<u32>,
+    removed_vertices: Vec<bool>,
+
+    triangles: Vec<[u32; 3]>,
+    removed_triangles: Vec<bool>,
+    num_triangles: usize,
+
+    /// The indices of the triangles adjacent to each vertex.
+    vertex_triangles: Vec<Vec<usize>>,
+
+    heap: BinaryHeap<Collapse>,
+}
+
+impl Simplifier {
+    /// Creates the simplification state for the given mesh. Vertices with equal positions are
+    /// welded, s.t. the simplification does not tear the mesh apart along attribute seams.
+    ///
+    /// # Arguments
+    /// * `mesh` - The triangle mesh to simplify.
+    fn new(mesh: &Mesh) -> Self {
+        let src_positions = mesh.get_vertices().get_positions();
+
+        let mut welded: HashMap<[u32; 3], u32> = HashMap::new();
+        let mut positions = Vec::new();
+        let remap: Vec<u32> = src_positions
+            .iter()
+            .map(|p| {
+                let key = [p.0[0].to_bits(), p.0[1].to_bits(), p.0[2].to_bits()];
+                *welded.entry(key).or_insert_with(|| {
+                    positions.push(DVec3::new(p.0[0] as f64, p.0[1] as f64, p.0[2] as f64));
+                    (positions.len() - 1) as u32
+                })
+            })
+            .collect();
+
+        let mut triangles = Vec::new();
+        for_each_triangle(mesh, |t| {
+            let t = [
+                remap[t[0] as usize],
+                remap[t[1] as usize],
+                remap[t[2] as usize],
+            ];
+
+            if t[0] != t[1] && t[1] != t[2] && t[2] != t[0] {
+                triangles.push(t);
+            }
+        });
+
+        let num_vertices = positions.len();
+        let mut vertex_triangles = vec![Vec::new(); num_vertices];
+        for (triangle_index, t) in triangles.iter().enumerate() {
+            for v in t.iter() {
+                vertex_triangles[*v as usize].push(triangle_index);
+            }
+        }
+
+        let mut simplifier = Self {
+            has_normals: mesh.get_vertices().get_normals().is_some(),
+            positions,
+            quadrics: vec![Quadric::default(); num_vertices],
+            versions: vec![0; num_vertices],
+            removed_vertices: vec![false; num_vertices],
+            num_triangles: triangles.len(),
+            removed_triangles: vec![false; triangles.len()],
+            triangles,
+            vertex_triangles,
+            heap: BinaryHeap::new(),
+        };
+
+        simplifier.initialize_quadrics();
+        simplifier.initialize_collapses();
+
+        simplifier
+    }
+
+    /// Computes the quadrics of the vertices from the planes of their adjacent triangles and
+    /// the planes along the boundary edges.
+    fn initialize_quadrics(&mut self) {
+        let mut edges: Vec<([u32; 2], usize)> = Vec::with_capacity(self.triangles.len() * 3);
+
+        for (triangle_index, t) in self.triangles.iter().enumerate() {
+            let p = t.map(|v| self.positions[v as usize]);
+            let n = cross(&(p[1] - p[0]), &(p[2] - p[0]));
+            let area2 = length(&n);
+            if area2 <= 0.0 {
+                continue;
+            }
+
+            let quadric = Quadric::from_plane(&(n / area2), &p[0], area2 * 0.5);
+            for v in t.iter() {
+                self.quadrics[*v as usize].add(&quadric);
+            }
+
+            for i in 0..3 {
+                let (a, b) = (t[i], t[(i + 1) % 3]);
+                edges.push(([a.min(b), a.max(b)], triangle_index));
+            }
+        }
+
+        // edges with a single adjacent triangle are boundary edges
+        edges.sort_unstable();
+        let mut i = 0;
+        while i < edges.len() {
+            let mut j = i + 1;
+            while j < edges.len() && edges[j].0 == edges[i].0 {
+                j += 1;
+            }
+
+            if j - i == 1 {
+                let ([a, b], triangle_index) = edges[i];
+                let t = self.triangles[triangle_index];
+                let p = t.map(|v| self.positions[v as usize]);
+                let face_normal = cross(&(p[1] - p[0]), &(p[2] - p[0]));
+
+                let (pa, pb) = (self.positions[a as usize], self.positions[b as usize]);
+                let edge = pb - pa;
+                let n = cross(&edge, &face_normal);
+                let l = length(&n);
+                if l > 0.0 {
+                    let weight = BOUNDARY_WEIGHT * dot(&edge, &edge);
+                    let quadric = Quadric::from_plane(&(n / l), &pa, weight);
+                    self.quadrics[a as usize].add(&quadric);
+                    self.quadrics[b as usize].add(&quadric);
+                }
+            }
+
+            i = j;
+        }
+    }
+
+    /// Computes the collapse candidates for all edges.
+    fn initialize_collapses(&mut self) {
+        let mut edges: Vec<[u32; 2]> = Vec::with_capacity(self.triangles.len() * 3);
+        for t in self.triangles.iter() {
+            for i in 0..3 {
+                let (a, b) = (t[i], t[(i + 1) % 3]);
+                edges.push([a.min(b), a.max(b)]);
+            }
+        }
+
+        edges.sort_unstable();
+        edges.dedup();
+
+        for [a, b] in edges {
+            let collapse = self.compute_collapse(a, b);
+            self.heap.push(collapse);
+        }
+    }
+
+    /// Computes the collapse of the given edge into the position with the smallest error.
+    ///
+    /// # Arguments
+    /// * `v0` - The vertex the edge collapses into.
+    /// * `v1` - The vertex removed by the collapse.
+    fn compute_collapse(&self, v0: u32, v1: u32) -> Collapse {
+        let mut quadric = self.quadrics[v0 as usize];
+        quadric.add(&self.quadrics[v1 as usize]);
+
+        let p0 = self.positions[v0 as usize];
+        let p1 = self.positions[v1 as usize];
+
+        let position = match quadric.minimize() {
+            Some(p) => p,
+            None => {
+                // fall back to the best of the end points and the midpoint
+                let candidates = [p0, p1, (p0 + p1) * 0.5];
+                let mut best = candidates[0];
+                for c in candidates.iter().skip(1) {
+                    if quadric.evaluate(c) < quadric.evaluate(&best) {
+                        best = *c;
+                    }
+                }
+
+                best
+            }
+        };
+
+        Collapse {
+            cost: quadric.evaluate(&position).max(0.0),
+            v0,
+            v1,
+            versions: [self.versions[v0 as usize], self.versions[v1 as usize]],
+            position,
+        }
+    }
+
+    /// Returns the vertices adjacent to the given vertex in ascending order.
+    ///
+    /// # Arguments
+    /// * `v` - The vertex whose neighbors are returned.
+    fn get_neighbors(&self, v: u32) -> Vec<u32> {
+        let mut neighbors: Vec<u32> = self.vertex_triangles[v as usize]
+            .iter()
+            .flat_map(|triangle_index| self.triangles[*triangle_index])
+            .filter(|n| *n != v)
+            .collect();
+
+        neighbors.sort_unstable();
+        neighbors.dedup();
+
+        neighbors
+    }
+
+    /// Returns true if the collapse keeps the mesh manifold, i.e., the vertices of the edge
+    /// share no other neighbors than the opposite vertices of the triangles of the edge.
+    ///
+    /// # Arguments
+    /// * `collapse` - The collapse to check.
+    fn is_manifold_collapse(&self, collapse: &Collapse) -> bool {
+        let num_shared_triangles = self.vertex_triangles[collapse.v1 as usize]
+            .iter()
+            .filter(|triangle_index| self.triangles[**triangle_index].contains(&collapse.v0))
+            .count();
+
+        let n0 = self.get_neighbors(collapse.v0);
+        let n1 = self.get_neighbors(collapse.v1);
+        let num_shared_neighbors = n0.iter().filter(|n| n1.binary_search(n).is_ok()).count();
+
+        num_shared_neighbors <= num_shared_triangles
+    }
+
+    /// Returns true if moving the given vertex to the new position flips the orientation of
+    /// any of its triangles, which are not removed by the collapse.
+    ///
+    /// # Arguments
+    /// * `v` - The moved vertex.
+    /// * `other` - The other vertex of the collapsed edge.
+    /// * `position` - The new position of the vertex.
+    fn flips_triangles(&self, v: u32, other: u32, position: &DVec3) -> bool {
+        for triangle_index in self.vertex_triangles[v as usize].iter() {
+            let t = self.triangles[*triangle_index];
+            if t.contains(&other) {
+                continue;
+            }
+
+            let p = t.map(|i| self.positions[i as usize]);
+            let q = t.map(|i| {
+                if i == v {
+                    *position
+                } else {
+                    self.positions[i as usize]
+                }
+            });
+
+            let n_old = cross(&(p[1] - p[0]), &(p[2] - p[0]));
+            let n_new = cross(&(q[1] - q[0]), &(q[2] - q[0]));
+            if dot(&n_old, &n_old) > 0.0 && dot(&n_old, &n_new) <= 0.0 {
+                return true;
+            }
+        }
+
+        false
+    }
+
+    /// Collapses edges in the order of their cost until the given number of triangles has been
+    /// reached or no further edge can be collapsed.
+    ///
+    /// # Arguments
+    /// * `target_num_triangles` - The number of triangles to reach.
+    fn simplify(&mut self, target_num_triangles: usize) {
+        while self.num_triangles > target_num_triangles {
+            let collapse = match self.heap.pop() {
+                Some(collapse) => collapse,
+                None => break,
+            };
+
+            let (v0, v1) = (collapse.v0 as usize, collapse.v1 as usize);
+            if self.removed_vertices[v0]
+                || self.removed_vertices[v1]
+                || collapse.versions != [self.versions[v0], self.versions[v1]]
+            {
+                continue;
+            }
+
+            if !self.is_manifold_collapse(&collapse)
+                || self.flips_triangles(collapse.v0, collapse.v1, &collapse.position)
+                || self.flips_triangles(collapse.v1, collapse.v0, &collapse.position)
+            {
+                continue;
+            }
+
+            self.apply_collapse(&collapse);
+        }
+    }
+
+    /// Collapses the edge of the given candidate, i.e., the second vertex is merged into the
+    /// first one.
+    ///
+    /// # Arguments
+    /// * `collapse` - The collapse to apply.
+    fn apply_collapse(&mut self, collapse: &Collapse) {
+        let (v0, v1) = (collapse.v0 as usize, collapse.v1 as usize);
+
+        self.positions[v0] = collapse.position;
+        let q1 = self.quadrics[v1];
+        self.quadrics[v0].add(&q1);
+
+        let triangles_v1 = std::mem::take(&mut self.vertex_triangles[v1]);
+        for triangle_index in triangles_v1 {
+            let t = &mut self.triangles[triangle_index];
+            if t.contains(&collapse.v0) {
+                self.removed_triangles[triangle_index] = true;
+                self.num_triangles -= 1;
+            } else {
+                for v in t.iter_mut() {
+                    if *v == collapse.v1 {
+                        *v = collapse.v0;
+                    }
+                }
+
+                self.vertex_triangles[v0].push(triangle_index);
+            }
+        }
+
+        // remove the collapsed triangles from the adjacency lists of their remaining vertices
+        let removed_triangles = &self.removed_triangles;
+        for v in self
+            .get_neighbors(collapse.v0)
+            .into_iter()
+            .chain([collapse.v0])
+        {
+            self.vertex_triangles[v as usize].retain(|t| !removed_triangles[*t]);
+        }
+
+        self.removed_vertices[v1] = true;
+        self.versions[v0] += 1;
+
+        for n in self.get_neighbors(collapse.v0) {
+            let collapse = self.compute_collapse(collapse.v0, n);
+            self.heap.push(collapse);
+        }
+    }
+
+    /// Creates the simplified mesh from the remaining triangles. If normals are needed, each
+    /// corner of a triangle gets the area weighted normal of the adjacent triangles whose
+    /// normals are within the crease angle, i.e., vertices are split along creases.
+    fn to_mesh(&self) -> Result<Mesh> {
+        let face_normals: Vec<DVec3> = self
+            .triangles
+            .iter()
+            .map(|t| {
+                let p = t.map(|v| self.positions[v as usize]);
+                cross(&(p[1] - p[0]), &(p[2] - p[0]))
+            })
+            .collect();
+        let cos_crease_angle = NORMAL_CREASE_ANGLE.to_radians().cos();
+
+        let mut remap: HashMap<(u32, [u64; 3]), u32> = HashMap::new();
+        let mut positions = Vec::new();
+        let mut normals = Vec::new();
+        let mut indices = Vec::with_capacity(self.num_triangles * 3);
+
+        for (triangle_index, t) in self.triangles.iter().enumerate() {
+            if self.removed_triangles[triangle_index] {
+                continue;
+            }
+
+            for v in t.iter() {
+                let normal = if self.has_normals {
+                    self.compute_corner_normal(*v, triangle_index, cos_crease_angle, &face_normals)
+                } else {
+                    DVec3::zeros()
+                };
+
+                let key = (*v, normal.map(|c| c.to_bits()).into());
+                let index = *remap.entry(key).or_insert_with(|| {
+                    let p = self.positions[*v as usize];
+                    positions.push(Point3D::new(p[0] as f32, p[1] as f32, p[2] as f32));
+                    if self.has_normals {
+                        normals.push(Point3D::new(
+                            normal[0] as f32,
+                            normal[1] as f32,
+                            normal[2] as f32,
+                        ));
+                    }
+                    (positions.len() - 1) as u32
+                });
+
+                indices.push(index);
+            }
+        }
+
+        let mut vertices = Vertices::from_positions(positions);
+        if self.has_normals {
+            vertices.set_normals(normals)?;
+        }
+
+        let primitives = Primitives::new(IndexData::Indices(indices), PrimitiveType::Triangles)?;
+
+        Ok(Mesh::new(vertices, primitives)?)
+    }
+
+    /// Returns the normal of the given vertex for the given adjacent triangle, i.e., the sum
+    /// of the area weighted normals of all adjacent triangles within the crease angle.
+    ///
+    /// # Arguments
+    /// * `v` - The vertex whose normal is computed.
+    /// * `triangle_index` - The index of the triangle the normal is computed for.
+    /// * `cos_crease_angle` - The cosine of the crease angle.
+    /// * `face_normals` - The area weighted normals of all triangles.
+    fn compute_corner_normal(
+        &self,
+        v: u32,
+        triangle_index: usize,
+        cos_crease_angle: f64,
+        face_normals: &[DVec3],
+    ) -> DVec3 {
+        let unit_normal = |n: &DVec3| {
+            let l = length(n);
+            if l > 0f64 {
+                Some(n / l)
+            } else {
+                None
+            }
+        };
+
+        let face_normal = match unit_normal(&face_normals[triangle_index]) {
+            Some(n) => n,
+            None => return DVec3::new(0f64, 0f64, 1f64),
+        };
+
+        let mut normal = DVec3::zeros();
+        for other in self.vertex_triangles[v as usize].iter() {
+            if self.removed_triangles[*other] {
+                continue;
+            }
+
+            if let Some(n) = unit_normal(&face_normals[*other]) {
+                if dot(&n, &face_normal) >= cos_crease_angle {
+                    normal += face_normals[*other];
+                }
+            }
+        }
+
+        // the triangle itself is always within the crease angle
+        normalize(&normal)
+    }
+}
+
+/// Returns the number of triangles of the given mesh, where strips and fans are counted as
+/// single triangles.
+///
+/// # Arguments
+/// * `mesh` - The mesh whose triangles are counted.
+pub fn count_triangles(mesh: &Mesh) -> usize {
+    let mut num_triangles = 0;
+    for_each_triangle(mesh, |_| num_triangles += 1);
+
+    num_triangles
+}
+
+/// Simplifies the given triangle mesh by collapsing edges with the smallest quadric error
+/// until the given number of triangles has been reached. The result consists of triangles,
+/// which get normals smoothed up to a crease angle if the given mesh has normals, s.t. smooth
+/// shaded surfaces stay smooth. The simplification is deterministic, i.e., the same mesh
+/// always results in the same simplified mesh.
+///
+/// # Arguments
+/// * `mesh` - The mesh to simplify.
+/// * `target_num_triangles` - The number of triangles of the simplified mesh.
+pub fn simplify_mesh(mesh: &Mesh, target_num_triangles: usize) -> Result<Mesh> {
+    if count_triangles(mesh) == 0 {
+        bail!("Cannot simplify a mesh without triangles");
+    }
+
+    let mut simplifier = Simplifier::new(mesh);
+    simplifier.simplify(target_num_triangles);
+
+    simplifier.to_mesh()
+}
+
+/// Creates the simplified CPU meshes of the coarser levels of detail for the given mesh,
+/// each with half the triangles of the previous level. Meshes with only a few triangles or
+/// without triangles have no levels of detail.
+///
+/// # Arguments
+/// * `mesh` - The full detail CPU mesh.
+/// * `num_lod_levels` - The maximal number of levels of detail to create.
+pub fn create_lods(mesh: &Mesh, num_lod_levels: usize) -> Result<Vec<Mesh>> {
+    let mut lods = Vec::new();
+
+    let mut num_triangles = count_triangles(mesh);
+    if num_triangles < MIN_LOD_TRIANGLES {
+        return Ok(lods);
+    }
+
+    for _ in 0..num_lod_levels {
+        let simplified = simplify_mesh(lods.last().unwrap_or(mesh), num_triangles / 2)?;
+
+        let num_simplified_triangles = count_triangles(&simplified);
+        if num_simplified_triangles as f32 > num_triangles as f32 * MAX_LOD_RATIO {
+            break;
+        }
+
+        num_triangles = num_simplified_triangles;
+        lods.push(simplified);
+    }
+
+    Ok(lods)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::test_meshes::{
+        create_mesh, create_mesh_with_normals, create_sphere, load_off_mesh,
+    };
+
+    /// Returns the positions as bits and the indices of the given mesh for exact comparisons.
+    fn mesh_data(mesh: &Mesh) -> (Vec<[u32; 3]>, Vec<u32>) {
+        let positions = mesh
+            .get_vertices()
+            .get_positions()
+            .iter()
+            .map(|p| [p.0[0].to_bits(), p.0[1].to_bits(), p.0[2].to_bits()])
+            .collect();
+
+        let mut indices = Vec::new();
+        for_each_triangle(mesh, |t| indices.extend(t));
+
+        (positions, indices)
+    }
+
+    #[test]
+    fn test_simplify_cube() {
+        let cube = load_off_mesh("examples/cube.off");
+        assert_eq!(count_triangles(&cube), 12);
+
+        // the cube can't lose any triangle without losing its shape, but it is still reduced
+        let simplified = simplify_mesh(&cube, 12).unwrap();
+        assert_eq!(count_triangles(&simplified), 12);
+
+        let simplified = simplify_mesh(&cube, 6).unwrap();
+        let num_triangles = count_triangles(&simplified);
+        assert!((4..12).contains(&num_triangles), "{}", num_triangles);
+
+        // too few triangles for any level of detail
+        assert!(create_lods(&cube, 3).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_simplify_sphere() {
+        let sphere = create_sphere(32, 64, false);
+        assert_eq!(count_triangles(&sphere), 3968);
+
+        for target in [2000, 1000, 100] {
+            let simplified = simplify_mesh(&sphere, target).unwrap();
+            assert_eq!(count_triangles(&simplified), target);
+
+            // the simplified vertices stay close to the unit sphere
+            for p in simplified.get_vertices().get_positions().iter() {
+                assert!((length(&p.0) - 1f32).abs() < 0.1, "{:?}", p);
+            }
+        }
+    }
+
+    #[test]
+    fn test_create_lods() {
+        let sphere = create_sphere(32, 64, false);
+
+        let lods = create_lods(&sphere, 3).unwrap();
+        let num_triangles: Vec<usize> = lods.iter().map(count_triangles).collect();
+        assert_eq!(num_triangles, vec![1984, 992, 496]);
+
+        assert!(create_lods(&sphere, 0).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_simplification_is_deterministic() {
+        let sphere = create_sphere(32, 64, false);
+        let cube = load_off_mesh("examples/cube.off");
+
+        for (mesh, target) in [(&sphere, 500), (cube.as_ref(), 6)] {
+            let expected = mesh_data(&simplify_mesh(mesh, target).unwrap());
+            for _ in 0..3 {
+                assert_eq!(mesh_data(&simplify_mesh(mesh, target).unwrap()), expected);
+            }
+        }
+
+        let expected: Vec<_> = create_lods(&sphere, 3)
+            .unwrap()
+            .iter()
+            .map(mesh_data)
+            .collect();
+        let lods: Vec<_> = create_lods(&sphere, 3)
+            .unwrap()
+            .iter()
+            .map(mesh_data)
+            .collect();
+        assert_eq!(lods, expected);
+    }
+
+    #[test]
+    fn test_simplify_without_triangles() {
+        let mesh = create_mesh(&[[0.0, 0.0, 0.0]], &[]);
+        assert!(simplify_mesh(&mesh, 1).is_err());
+    }
+
+    #[test]
+    fn test_simplified_normals_are_smooth() {
+        let sphere = create_sphere(32, 64, true);
+        let simplified = simplify_mesh(&sphere, 500).unwrap();
+        let vertices = simplified.get_vertices();
+        let normals = vertices.get_normals().unwrap();
+
+        // the smooth normals of the sphere point along the positions and aren't split
+        for (p, n) in vertices.get_positions().iter().zip(normals.iter()) {
+            assert!((length(&n.0) - 1f32).abs() < 1e-5);
+            assert!(dot(&n.0, &p.0.normalize()) > 0.95, "{:?} {:?}", p, n);
+        }
+
+        let welded = Simplifier::new(&simplified).positions.len();
+        assert_eq!(vertices.len(), welded);
+
+        // the levels of detail keep the normals
+        for lod in create_lods(&sphere, 3).unwrap() {
+            assert!(lod.get_vertices().get_normals().is_some());
+        }
+        assert!(simplify_mesh(&create_sphere(32, 64, false), 500)
+            .unwrap()
+            .get_vertices()
+            .get_normals()
+            .is_none());
+    }
+
+    #[test]
+    fn test_simplified_normals_are_split_at_creases() {
+        // a cube with its faces split into 2x2 quads and normals per face
+        let mut positions = Vec::new();
+        let mut normals = Vec::new();
+        let mut indices = Vec::new();
+        for axis in 0..3 {
+            for sign in [-1f32, 1f32] {
+                let (u, v) = ((axis + 1) % 3, (axis + 2) % 3);
+                let first = positions.len() as u32;
+                for i in 0..3 {
+                    for j in 0..3 {
+                        let mut p = [0f32; 3];
+                        p[axis] = sign;
+                        p[u] = i as f32 - 1f32;
+                        p[v] = j as f32 - 1f32;
+                        positions.push(p);
+
+                        let mut n = [0f32; 3];
+                        n[axis] = sign;
+                        normals.push(n);
+                    }
+                }
+
+                for i in 0..2 {
+                    for j in 0..2 {
+                        let a = first + i * 3 + j;
+                        let quad = [a, a + 3, a + 4, a, a + 4, a + 1];
+                        if sign > 0f32 {
+                            indices.extend(quad);
+                        } else {
+                            indices.extend(quad.iter().rev());
+                        }
+                    }
+                }
+            }
+        }
+        let cube = create_mesh_with_normals(&positions, Some(&normals), &indices);
+        assert_eq!(count_triangles(&cube), 48);
+
+        let simplified = simplify_mesh(&cube, 12).unwrap();
+        assert_eq!(count_triangles(&simplified), 12);
+
+        // the corners are split into one vertex for each adjacent face with its face normal
+        let vertices = simplified.get_vertices();
+        assert_eq!(vertices.len(), 24);
+        for n in vertices.get_normals().unwrap().iter() {
+            assert_eq!(n.0.abs().max(), 1f32, "{:?}", n);
+            assert_eq!(n.0.abs().sum(), 1f32, "{:?}", n);
+        }
+    }
+}
@@ -0,0 +1,112 @@
+use std::{f32::consts::PI, path::Path, rc::Rc};
+
+use cad_import::{
+    loader::Manager,
+    structure::{IndexData, Mesh, Node, Point3D, PrimitiveType, Primitives, Vertices},
+};
+
+/// Returns the triangle mesh with the given positions and indices.
+///
+/// # Arguments
+/// * `positions` - The vertex positions.
+/// * `indices` - The vertex indices, three per triangle.
+pub fn create_mesh(positions: &[[f32; 3]], indices: &[u32]) -> Mesh {
+    create_mesh_with_normals(positions, None, indices)
+}
+
+/// Returns the triangle mesh with the given positions, optional normals and indices.
+///
+/// # Arguments
+/// * `positions` - The vertex positions.
+/// * `normals` - The vertex normals, one for each position.
+/// * `indices` - The vertex indices, three per triangle.
+pub fn create_mesh_with_normals(
+    positions: &[[f32; 3]],
+    normals: Option<&[[f32; 3]]>,
+    indices: &[u32],
+) -> Mesh {
+    let to_points = |values: &[[f32; 3]]| {
+        values
+            .iter()
+            .map(|p| Point3D::new(p[0], p[1], p[2]))
+            .collect::<Vec<_>>()
+    };
+
+    let mut vertices = Vertices::from_positions(to_points(positions));
+    if let Some(normals) = normals {
+        vertices.set_normals(to_points(normals)).unwrap();
+    }
+
+    let primitives = Primitives::new(
+        IndexData::Indices(indices.to_vec()),
+        PrimitiveType::Triangles,
+    )
+    .unwrap();
+
+    Mesh::new(vertices, primitives).unwrap()
+}
+
+/// Returns a closed unit sphere, which is tessellated by the given number of rings between the
+/// poles and segments around the z-axis. The sphere has 2 * num_segments * (num_rings - 1)
+/// triangles.
+///
+/// # Arguments
+/// * `num_rings` - The number of rings, at least two.
+/// * `num_segments` - The number of segments, at least three.
+/// * `with_normals` - If true, the vertices get the smooth normals of the sphere.
+pub fn create_sphere(num_rings: u32, num_segments: u32, with_normals: bool) -> Mesh {
+    let mut positions = vec![[0f32, 0f32, 1f32], [0f32, 0f32, -1f32]];
+    for ring in 1..num_rings {
+        let theta = PI * ring as f32 / num_rings as f32;
+        for segment in 0..num_segments {
+            let phi = 2f32 * PI * segment as f32 / num_segments as f32;
+            positions.push([
+                theta.sin() * phi.cos(),
+                theta.sin() * phi.sin(),
+                theta.cos(),
+            ]);
+        }
+    }
+
+    let vertex = |ring: u32, segment: u32| 2 + (ring - 1) * num_segments + segment % num_segments;
+    let mut indices = Vec::new();
+    for segment in 0..num_segments {
+        indices.extend([0, vertex(1, segment), vertex(1, segment + 1)]);
+        indices.extend([
+            1,
+            vertex(num_rings - 1, segment + 1),
+            vertex(num_rings - 1, segment),
+        ]);
+
+        for ring in 1..num_rings - 1 {
+            let (a, b) = (vertex(ring, segment), vertex(ring, segment + 1));
+            let (c, d) = (vertex(ring + 1, segment), vertex(ring + 1, segment + 1));
+            indices.extend([a, c, d, a, d, b]);
+        }
+    }
+
+    // the normals of the unit sphere are its positions
+    let normals = with_normals.then_some(positions.as_slice());
+
+    create_mesh_with_normals(&positions, normals, &indices)
+}
+
+/// Loads the given OFF file and returns the mesh of its first shape part.
+///
+/// # Arguments
+/// * `path` - The path of the OFF file, relative to the crate root.
+pub fn load_off_mesh(path: &str) -> Rc<Mesh> {
+    let manager = Manager::new();
+    let loader = manager.get_loader_by_mime_type("model/vnd.off").unwrap();
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join(path);
+    let cad_data = loader.read_file(&path, "model/vnd.off").unwrap();
+
+    fn find_mesh(node: &Node) -> Option<Rc<Mesh>> {
+        match node.get_shapes().first() {
+            Some(shape) => Some(shape.get_parts()[0].get_mesh()),
+            None => node.get_children().iter().find_map(find_mesh),
+        }
+    }
+
+    find_mesh(cad_data.get_root_node()).unwrap()
+}
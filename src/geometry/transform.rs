@@ -0,0 +1,17 @@
+use nalgebra_glm::{determinant, inverse, mat4_to_mat3, transpose, Mat3, Mat4};
+
+/// Returns the matrix for transforming normals with the given transformation, i.e., the
+/// inverse transpose of its upper 3x3 matrix. Singular matrices are returned unchanged.
+///
+/// # Arguments
+/// * `m` - The transformation of the positions.
+pub fn compute_normal_matrix(m: &Mat4) -> Mat3 {
+    let m = mat4_to_mat3(m);
+
+    let d: f32 = determinant(&m);
+    if d.abs() <= 1e-9 {
+        m
+    } else {
+        transpose(&inverse(&m))
+    }
+}
@@ -0,0 +1,49 @@
+use cad_import::structure::{IndexData, Mesh, PrimitiveType};
+
+/// Calls the given function for each triangle of the mesh with the vertex indices of the
+/// triangle. Triangle strips and fans are decomposed into single triangles. Meshes that don't
+/// consist of triangles, i.e., points and lines, are skipped.
+///
+/// # Arguments
+/// * `mesh` - The mesh whose triangles are visited.
+/// * `f` - The function called with the three vertex indices of each triangle.
+pub fn for_each_triangle<F: FnMut([u32; 3])>(mesh: &Mesh, mut f: F) {
+    let primitives = mesh.get_primitives();
+    let index_data = primitives.get_raw_index_data();
+
+    let index = |i: usize| -> u32 {
+        match index_data {
+            IndexData::Indices(indices) => indices[i],
+            IndexData::NonIndexed(_) => i as u32,
+        }
+    };
+
+    let num_indices = index_data.num_indices();
+
+    match primitives.get_primitive_type() {
+        PrimitiveType::Triangles => {
+            for i in (0..num_indices).step_by(3) {
+                f([index(i), index(i + 1), index(i + 2)]);
+            }
+        }
+        PrimitiveType::TriangleStrip => {
+            for i in 2..num_indices {
+                // every second triangle has a flipped orientation
+                if i % 2 == 0 {
+                    f([index(i - 2), index(i - 1), index(i)]);
+                } else {
+                    f([index(i - 1), index(i - 2), index(i)]);
+                }
+            }
+        }
+        PrimitiveType::TriangleFan => {
+            for i in 2..num_indices {
+                f([index(0), index(i - 1), index(i)]);
+            }
+        }
+        PrimitiveType::Point
+        | PrimitiveType::Line
+        | PrimitiveType::LineLoop
+        | PrimitiveType::LineStrip => {}
+    }
+}
@@ -7,7 +7,6 @@ use crate::{gl_call, viewer::gl_call::handle_glow_error};
 #[repr(u32)]
 pub enum Usage {
     Static = glow::STATIC_DRAW,
-    Dynamic = glow::DYNAMIC_DRAW,
     Stream = glow::STREAM_DRAW,
 }
 
@@ -30,7 +29,7 @@ impl<C: HasContext, const TARGET: u32> Buffer<C, TARGET> {
     /// * `context` - The GLOW context.
     /// * `data` - The data to copied into the GPU buffer.
     pub fn set_data<T: Sized>(&self, context: &C, data: &[T], usage: Usage) {
-        let num_bytes = data.len() * std::mem::size_of::<T>();
+        let num_bytes = std::mem::size_of_val(data);
         let data = unsafe { std::slice::from_raw_parts(data.as_ptr() as *const u8, num_bytes) };
 
         self.bind(context);
@@ -41,4 +40,9 @@ impl<C: HasContext, const TARGET: u32> Buffer<C, TARGET> {
     pub fn bind(&self, context: &C) {
         gl_call!(context, bind_buffer, TARGET, Some(self.buffer));
     }
+
+    /// Deletes the buffer on the GPU.
+    pub fn cleanup(&self, context: &C) {
+        gl_call!(context, delete_buffer, self.buffer);
+    }
 }
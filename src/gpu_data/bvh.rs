@@ -0,0 +1,393 @@
+use crate::geometry::{BBox, Frustum, Ray};
+
+/// The maximal number of items stored in a leaf node.
+const MAX_LEAF_SIZE: usize = 4;
+
+/// A node of the bounding volume hierarchy. Inner nodes reference their two children, leaf
+/// nodes a range of items.
+#[derive(Debug, Clone)]
+struct BvhNode {
+    /// The bounding volume of all items of the subtree.
+    bbox: BBox,
+
+    /// The index of the left child for inner nodes or the index of the first item for leaf
+    /// nodes.
+    first: usize,
+
+    /// The index of the right child for inner nodes.
+    right: usize,
+
+    /// The number of items of a leaf node or zero for inner nodes.
+    count: usize,
+}
+
+impl BvhNode {
+    #[inline]
+    fn is_leaf(&self) -> bool {
+        self.count > 0
+    }
+}
+
+/// A bounding volume hierarchy over a set of items, e.g. the shape instances, given by their
+/// bounding volumes. The items are identified by their index in the initial list of volumes.
+#[derive(Debug, Clone)]
+pub struct Bvh {
+    /// The nodes in depth-first order, i.e., the first node is the root node.
+    nodes: Vec<BvhNode>,
+
+    /// The item indices ordered s.t. the items of each leaf are contiguous.
+    items: Vec<usize>,
+
+    /// The bounding volumes of the items.
+    item_bboxes: Vec<BBox>,
+}
+
+impl Bvh {
+    /// Builds a new hierarchy over the given bounding volumes by recursively splitting the
+    /// items at the median of the longest axis of their centers.
+    ///
+    /// # Arguments
+    /// * `item_bboxes` - The bounding volumes of the items.
+    pub fn new(item_bboxes: Vec<BBox>) -> Self {
+        let mut bvh = Self {
+            nodes: Vec::new(),
+            items: (0..item_bboxes.len()).collect(),
+            item_bboxes,
+        };
+
+        if !bvh.items.is_empty() {
+            bvh.build_node(0, bvh.items.len());
+        }
+
+        bvh
+    }
+
+    /// Calls the given function for each item whose bounding volume intersects the frustum.
+    ///
+    /// # Arguments
+    /// * `frustum` - The frustum to test against.
+    /// * `f` - The function called with the index of each intersecting item.
+    pub fn query_frustum<F: FnMut(usize)>(&self, frustum: &Frustum, f: F) {
+        self.query(|bbox| frustum.intersects_bbox(bbox), f);
+    }
+
+    /// Calls the given function for each item whose bounding volume overlaps the given volume.
+    ///
+    /// # Arguments
+    /// * `bbox` - The bounding volume to test against.
+    /// * `f` - The function called with the index of each overlapping item.
+    pub fn query_bbox<F: FnMut(usize)>(&self, bbox: &BBox, f: F) {
+        self.query(|item_bbox| item_bbox.intersects(bbox), f);
+    }
+
+    /// Calls the given function for each item whose bounding volume is hit by the ray. The
+    /// function returns the ray parameter of the hit with the item itself if there is any.
+    /// Nodes entered behind the closest reported hit are skipped. Returns the closest reported
+    /// hit.
+    ///
+    /// # Arguments
+    /// * `ray` - The ray to test against.
+    /// * `f` - The function called with the index of each hit item.
+    pub fn query_ray<F: FnMut(usize) -> Option<f32>>(&self, ray: &Ray, mut f: F) -> Option<f32> {
+        let mut closest: Option<f32> = None;
+        if self.nodes.is_empty() {
+            return closest;
+        }
+
+        let is_hit = |bbox: &BBox, closest: Option<f32>| match ray.intersect_bbox(bbox) {
+            Some(t) => closest.is_none_or(|closest| t <= closest),
+            None => false,
+        };
+
+        let mut stack = vec![0];
+        while let Some(node_index) = stack.pop() {
+            let node = &self.nodes[node_index];
+            if !is_hit(&node.bbox, closest) {
+                continue;
+            }
+
+            if !node.is_leaf() {
+                stack.push(node.right);
+                stack.push(node.first);
+                continue;
+            }
+
+            for &item in self.items[node.first..node.first + node.count].iter() {
+                if !is_hit(&self.item_bboxes[item], closest) {
+                    continue;
+                }
+
+                if let Some(t) = f(item) {
+                    if closest.is_none_or(|closest| t < closest) {
+                        closest = Some(t);
+                    }
+                }
+            }
+        }
+
+        closest
+    }
+
+    /// Updates the bounding volume of the given item. The hierarchy must be refitted afterwards.
+    ///
+    /// # Arguments
+    /// * `item` - The index of the item.
+    /// * `bbox` - The new bounding volume of the item.
+    pub fn set_item_bbox(&mut self, item: usize, bbox: BBox) {
+        self.item_bboxes[item] = bbox;
+    }
+
+    /// Recomputes the bounding volumes of all nodes bottom-up after the volumes of items have
+    /// changed. The structure of the hierarchy is kept.
+    pub fn refit(&mut self) {
+        // children are always stored behind their parent
+        for node_index in (0..self.nodes.len()).rev() {
+            let node = &self.nodes[node_index];
+
+            let mut bbox = BBox::new();
+            if node.is_leaf() {
+                for &item in self.items[node.first..node.first + node.count].iter() {
+                    bbox.extend_bbox(&self.item_bboxes[item]);
+                }
+            } else {
+                bbox.extend_bbox(&self.nodes[node.first].bbox);
+                bbox.extend_bbox(&self.nodes[node.right].bbox);
+            }
+
+            self.nodes[node_index].bbox = bbox;
+        }
+    }
+
+    /// Traverses the hierarchy and calls the given function for each item whose bounding
+    /// volume passes the test. Subtrees whose volume fails the test are skipped.
+    ///
+    /// # Arguments
+    /// * `test` - The test for the bounding volumes.
+    /// * `f` - The function called with the index of each item passing the test.
+    fn query<T: Fn(&BBox) -> bool, F: FnMut(usize)>(&self, test: T, mut f: F) {
+        if self.nodes.is_empty() {
+            return;
+        }
+
+        let mut stack = vec![0];
+        while let Some(node_index) = stack.pop() {
+            let node = &self.nodes[node_index];
+            if !test(&node.bbox) {
+                continue;
+            }
+
+            if node.is_leaf() {
+                for &item in self.items[node.first..node.first + node.count].iter() {
+                    if test(&self.item_bboxes[item]) {
+                        f(item);
+                    }
+                }
+            } else {
+                stack.push(node.right);
+                stack.push(node.first);
+            }
+        }
+    }
+
+    /// Creates the node for the given range of items and its subtree. Returns the node index.
+    ///
+    /// # Arguments
+    /// * `begin` - The first item of the range.
+    /// * `end` - The end of the item range.
+    fn build_node(&mut self, begin: usize, end: usize) -> usize {
+        let mut bbox = BBox::new();
+        let mut centers = BBox::new();
+        for &item in self.items[begin..end].iter() {
+            let item_bbox = &self.item_bboxes[item];
+            bbox.extend_bbox(item_bbox);
+            if !item_bbox.is_empty() {
+                centers.extend_pos(&item_bbox.get_center());
+            }
+        }
+
+        let node_index = self.nodes.len();
+        self.nodes.push(BvhNode {
+            bbox,
+            first: begin,
+            right: 0,
+            count: end - begin,
+        });
+
+        let size = centers.get_size();
+        if end - begin <= MAX_LEAF_SIZE || centers.is_empty() || size.max() <= 0f32 {
+            return node_index;
+        }
+
+        // split at the median of the longest axis
+        let axis = size.imax();
+        let mid = begin + (end - begin) / 2;
+        let item_bboxes = &self.item_bboxes;
+        let center = |item: &usize| {
+            let item_bbox = &item_bboxes[*item];
+            if item_bbox.is_empty() {
+                0f32
+            } else {
+                item_bbox.get_center()[axis]
+            }
+        };
+        self.items[begin..end]
+            .select_nth_unstable_by(mid - begin, |a, b| center(a).total_cmp(&center(b)));
+
+        let left = self.build_node(begin, mid);
+        let right = self.build_node(mid, end);
+
+        let node = &mut self.nodes[node_index];
+        node.first = left;
+        node.right = right;
+        node.count = 0;
+
+        node_index
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra_glm::{look_at, perspective, Vec3};
+
+    use super::*;
+
+    /// Returns the volume of the given size at the given position.
+    fn bbox(min: Vec3, size: f32) -> BBox {
+        let mut bbox = BBox::new();
+        bbox.extend_pos(&min);
+        bbox.extend_pos(&(min + Vec3::from_element(size)));
+
+        bbox
+    }
+
+    /// Returns a grid of n x n x n unit cubes with a spacing of 2.
+    fn grid(n: usize) -> Vec<BBox> {
+        let mut bboxes = Vec::with_capacity(n * n * n);
+        for x in 0..n {
+            for y in 0..n {
+                for z in 0..n {
+                    let min = Vec3::new(x as f32, y as f32, z as f32) * 2f32;
+                    bboxes.push(bbox(min, 1f32));
+                }
+            }
+        }
+
+        bboxes
+    }
+
+    /// Returns the sorted items reported by the given query.
+    fn collect<Q: FnOnce(&mut dyn FnMut(usize))>(query: Q) -> Vec<usize> {
+        let mut items = Vec::new();
+        query(&mut |item| items.push(item));
+        items.sort_unstable();
+
+        items
+    }
+
+    #[test]
+    fn test_empty() {
+        let bvh = Bvh::new(Vec::new());
+
+        let items = collect(|f| bvh.query_bbox(&bbox(Vec3::zeros(), 1f32), f));
+        assert!(items.is_empty());
+
+        let ray = Ray::new(Vec3::zeros(), Vec3::new(1.0, 0.0, 0.0));
+        assert_eq!(bvh.query_ray(&ray, |_| Some(0f32)), None);
+    }
+
+    #[test]
+    fn test_query_frustum() {
+        let bboxes = grid(8);
+        let bvh = Bvh::new(bboxes.clone());
+
+        // a camera in front of the grid looking at one of its corners
+        let projection = perspective(1f32, 0.5f32, 1f32, 100f32);
+        let view = look_at(
+            &Vec3::new(0.0, 0.0, 40.0),
+            &Vec3::zeros(),
+            &Vec3::new(0.0, 1.0, 0.0),
+        );
+        let frustum = Frustum::from_matrix(&(projection * view));
+
+        let items = collect(|f| bvh.query_frustum(&frustum, f));
+        let expected: Vec<usize> = (0..bboxes.len())
+            .filter(|&item| frustum.intersects_bbox(&bboxes[item]))
+            .collect();
+
+        assert!(!items.is_empty() && items.len() < bboxes.len());
+        assert_eq!(items, expected);
+    }
+
+    #[test]
+    fn test_query_bbox() {
+        let bboxes = grid(8);
+        let bvh = Bvh::new(bboxes.clone());
+
+        // overlaps the cubes at 2 and 4 along each axis
+        let query = bbox(Vec3::from_element(2.5), 1.75);
+        let items = collect(|f| bvh.query_bbox(&query, f));
+        let expected: Vec<usize> = (0..bboxes.len())
+            .filter(|&item| bboxes[item].intersects(&query))
+            .collect();
+
+        assert_eq!(items.len(), 8);
+        assert_eq!(items, expected);
+
+        // in between the cubes
+        let query = bbox(Vec3::from_element(1.25), 0.5);
+        assert!(collect(|f| bvh.query_bbox(&query, f)).is_empty());
+    }
+
+    #[test]
+    fn test_query_ray() {
+        let bboxes = grid(8);
+        let bvh = Bvh::new(bboxes.clone());
+
+        // along the x-axis through the first row of cubes
+        let ray = Ray::new(Vec3::new(-10.0, 0.5, 0.5), Vec3::new(1.0, 0.0, 0.0));
+        let mut visited = Vec::new();
+        let closest = bvh.query_ray(&ray, |item| {
+            visited.push(item);
+            ray.intersect_bbox(&bboxes[item])
+        });
+
+        assert_eq!(closest, Some(10.0));
+        assert!(visited.contains(&0));
+        assert!(visited
+            .iter()
+            .all(|&item| ray.intersect_bbox(&bboxes[item]).is_some()));
+
+        // the items may reject the hit of their volume
+        let closest = bvh.query_ray(&ray, |item| match item {
+            0 => None,
+            _ => ray.intersect_bbox(&bboxes[item]),
+        });
+        assert_eq!(closest, Some(12.0));
+
+        let ray = Ray::new(Vec3::new(-10.0, 1.5, 0.5), Vec3::new(1.0, 0.0, 0.0));
+        assert_eq!(bvh.query_ray(&ray, |_| Some(0f32)), None);
+    }
+
+    #[test]
+    fn test_refit() {
+        let bboxes = grid(4);
+        let mut bvh = Bvh::new(bboxes);
+
+        let query = bbox(Vec3::from_element(100.0), 1f32);
+        assert!(collect(|f| bvh.query_bbox(&query, f)).is_empty());
+
+        // move the first cube far away from all others
+        bvh.set_item_bbox(0, bbox(Vec3::from_element(100.5), 1f32));
+        bvh.refit();
+
+        assert_eq!(collect(|f| bvh.query_bbox(&query, f)), vec![0]);
+        assert_eq!(bvh.nodes[0].bbox.max, Vec3::from_element(101.5));
+
+        let query = bbox(Vec3::zeros(), 0.5);
+        assert!(collect(|f| bvh.query_bbox(&query, f)).is_empty());
+
+        let ray = Ray::new(Vec3::new(200.0, 101.0, 101.0), Vec3::new(-1.0, 0.0, 0.0));
+        let closest = bvh.query_ray(&ray, |item| ray.intersect_bbox(&bvh.item_bboxes[item]));
+        assert_eq!(closest, Some(98.5));
+    }
+}
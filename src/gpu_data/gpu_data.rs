@@ -1,33 +1,157 @@
-use std::{collections::HashMap, rc::Rc};
+use std::{
+    collections::{BTreeMap, HashMap},
+    ops::Range,
+    rc::Rc,
+    time::{Duration, Instant},
+};
 
 use anyhow::Result;
 use cad_import::{
-    structure::{CADData, Material, Node, Shape},
+    structure::{CADData, Material, Mesh, Node, PrimitiveType, Shape},
     ID,
 };
 use glow::HasContext;
+use log::{debug, trace};
 use nalgebra_glm::Mat4;
 
-use super::gpu_mesh::GPUMesh;
+use crate::geometry::{
+    compute_normal_matrix, create_line_mesh, extract_feature_edges, BBox, Frustum, Ray,
+    DEFAULT_CREASE_ANGLE,
+};
+
+use super::{
+    bvh::Bvh,
+    gpu_mesh::GPUMesh,
+    memory_stats::{BufferBytes, MemoryStats},
+    merged_mesh::{
+        get_merged_primitive_type, MergedMesh, MergedMeshBuilder, MAX_MERGED_MESH_VERTICES,
+        MAX_MERGED_SHAPE_VERTICES,
+    },
+    scene_node::SceneNode,
+    vertex_format::{get_dequantization_matrix, VertexFormat},
+};
 
 pub struct GPUMeshWithMaterial<C: HasContext> {
     pub material: Rc<Material>,
-    pub mesh: GPUMesh<C>,
+
+    /// The full detail mesh or none if it has been released after merging all instances.
+    pub mesh: Option<GPUMesh<C>>,
+
+    /// The CPU mesh the GPU mesh has been created from, e.g., used for picking.
+    pub cpu_mesh: Rc<Mesh>,
+
+    /// The simplified meshes of the coarser levels of detail, starting with level one.
+    pub lods: Vec<GPUMesh<C>>,
+}
+
+impl<C: HasContext> GPUMeshWithMaterial<C> {
+    /// Returns the mesh for the given level of detail, where level zero is the full detail
+    /// mesh. Levels beyond the coarsest available level return the coarsest mesh. Returns none
+    /// if the meshes have been released after merging.
+    ///
+    /// # Arguments
+    /// * `lod` - The level of detail.
+    pub fn get_lod_mesh(&self, lod: usize) -> Option<&GPUMesh<C>> {
+        match lod.min(self.lods.len()) {
+            0 => self.mesh.as_ref(),
+            lod => self.lods.get(lod - 1),
+        }
+    }
+
+    /// Deletes the GPU meshes of the part, i.e., the full detail mesh and the levels of detail.
+    /// The CPU mesh is kept.
+    ///
+    /// # Arguments
+    /// * `context` - The GLOW context used for deleting the meshes.
+    pub fn release_meshes(&mut self, context: &C) {
+        for mesh in self.mesh.take().iter().chain(self.lods.iter()) {
+            mesh.cleanup(context);
+        }
+
+        self.lods.clear();
+    }
 }
 
 pub struct GPUShape<C: HasContext> {
     pub parts: Vec<GPUMeshWithMaterial<C>>,
+
+    /// The ID of the CPU shape the GPU shape has been created from.
+    pub shape_id: ID,
+
+    /// The bounding volume of the shape in its local coordinates.
+    pub bbox: BBox,
+
+    /// The transformation of the positions stored on the GPU into the local coordinates of the
+    /// shape, i.e., the dequantization of quantized positions or the identity.
+    pub position_transform: Mat4,
+
+    /// The feature edges of all parts as lines or none if the shape has no feature edges.
+    pub edges: Option<GPUMesh<C>>,
+}
+
+impl<C: HasContext> GPUShape<C> {
+    /// Returns an iterator over all GPU meshes of the shape, i.e., the meshes of the parts with
+    /// their levels of detail and the feature edges.
+    pub fn meshes(&self) -> impl Iterator<Item = &GPUMesh<C>> {
+        self.parts
+            .iter()
+            .flat_map(|part| part.mesh.iter().chain(part.lods.iter()))
+            .chain(self.edges.iter())
+    }
 }
 
 pub struct GPUShapeInstance {
     pub transform: Mat4,
     pub shape_index: usize,
+
+    /// The ID of the node the shape instance is attached to.
+    pub node_id: ID,
+
+    /// The index of the scene node the shape instance is attached to.
+    pub node_index: usize,
+
+    /// Determines if the shape instance is rendered.
+    pub visible: bool,
+
+    /// The bounding volume of the shape instance in world coordinates.
+    pub bbox: BBox,
+
+    /// Determines if the shape instance is drawn as part of the merged meshes.
+    pub merged: bool,
+}
+
+/// The budget for a single step of an incremental upload. A step stops as soon as one of the
+/// limits has been reached.
+#[derive(Debug, Clone, Copy)]
+pub struct UploadBudget {
+    /// The maximal time spent for uploading data.
+    pub max_time: Duration,
+
+    /// The maximal number of bytes to upload.
+    pub max_bytes: usize,
+}
+
+impl Default for UploadBudget {
+    fn default() -> Self {
+        Self {
+            max_time: Duration::from_millis(10),
+            max_bytes: 32 * 1024 * 1024,
+        }
+    }
 }
 
 /// All data on the GPU
 pub struct GPUData<C: HasContext> {
     shapes: Vec<GPUShape<C>>,
     instances: Vec<GPUShapeInstance>,
+    nodes: Vec<SceneNode>,
+    merged_meshes: Vec<MergedMesh<C>>,
+    bvh: Option<Bvh>,
+    upload: Option<TraversalData>,
+    vertex_format: VertexFormat,
+
+    /// The angle in degrees between adjacent triangles above which their edge is a feature edge.
+    crease_angle: f32,
 }
 
 impl<C: HasContext> GPUData<C> {
@@ -36,21 +160,279 @@ impl<C: HasContext> GPUData<C> {
         Self {
             shapes: Vec::new(),
             instances: Vec::new(),
+            nodes: Vec::new(),
+            merged_meshes: Vec::new(),
+            bvh: None,
+            upload: None,
+            vertex_format: VertexFormat::default(),
+            crease_angle: DEFAULT_CREASE_ANGLE,
         }
     }
 
-    /// Adds the given cad data to the gpu data.
+    /// Starts adding the given cad data to the gpu data. The data is uploaded incrementally by
+    /// calling `continue_upload` until it returns true.
     ///
     /// # Arguments
-    /// * `context` - The GLOW context used for initializing all GPU data.
     /// * `cad_data` - The CAD data to add.
-    pub fn add_cad_data(&mut self, context: &C, cad_data: &CADData) -> Result<()> {
+    /// * `shape_bboxes` - The local bounding volumes of the shapes of the CAD data. Missing
+    ///   volumes are computed on demand.
+    /// * `shape_lods` - The simplified meshes of the coarser levels of detail for the parts of
+    ///   the shapes of the CAD data. Shapes without an entry have no levels of detail.
+    /// * `vertex_format` - The format of the vertex data of the uploaded meshes.
+    /// * `crease_angle` - The angle in degrees between adjacent triangles above which their edge
+    ///   is a feature edge.
+    pub fn begin_upload(
+        &mut self,
+        cad_data: &CADData,
+        shape_bboxes: HashMap<ID, BBox>,
+        shape_lods: HashMap<ID, Vec<Vec<Mesh>>>,
+        vertex_format: VertexFormat,
+        crease_angle: f32,
+    ) {
         let root_node = cad_data.get_root_node();
         let traversal_context = TraversalContext::new(root_node);
-        let mut traversal_data = TraversalData::new();
-        self.traverse(context, root_node, traversal_context, &mut traversal_data)?;
 
-        Ok(())
+        let mut traversal_data = TraversalData::new(shape_bboxes, shape_lods);
+        traversal_data.pending.push(PendingNode {
+            context: traversal_context,
+            next_shape: 0,
+            parent: None,
+            scene_node: None,
+        });
+
+        self.upload = Some(traversal_data);
+        self.vertex_format = vertex_format;
+        self.crease_angle = crease_angle;
+    }
+
+    /// Continues the upload started by `begin_upload` until the given budget is exhausted.
+    /// Returns true if the upload has been finished.
+    ///
+    /// # Arguments
+    /// * `context` - The GLOW context used for initializing all GPU data.
+    /// * `cad_data` - The CAD data passed to `begin_upload`.
+    /// * `budget` - The budget for this upload step.
+    pub fn continue_upload(
+        &mut self,
+        context: &C,
+        cad_data: &CADData,
+        budget: &UploadBudget,
+    ) -> Result<bool> {
+        let mut traversal_data = match self.upload.take() {
+            Some(traversal_data) => traversal_data,
+            None => return Ok(true),
+        };
+
+        // on failure, the upload state is dropped, i.e., the upload is aborted
+        let start = Instant::now();
+        let mut num_bytes = 0;
+        let finished = self.traverse(
+            context,
+            cad_data.get_root_node(),
+            &mut traversal_data,
+            |num_bytes| num_bytes >= budget.max_bytes || start.elapsed() >= budget.max_time,
+            &mut num_bytes,
+        )?;
+
+        trace!(
+            "Uploaded {} bytes in {:?}, #shapes={}, #instances={}",
+            num_bytes,
+            start.elapsed(),
+            self.shapes.len(),
+            self.instances.len()
+        );
+
+        if finished {
+            self.build_bvh();
+        } else {
+            self.upload = Some(traversal_data);
+        }
+
+        Ok(finished)
+    }
+
+    /// Returns true if an upload has been started and is not finished yet.
+    pub fn is_uploading(&self) -> bool {
+        self.upload.is_some()
+    }
+
+    /// Returns the number of bytes occupied by the vertex and index buffers on the GPU for each
+    /// category of meshes. Each buffer is counted once, i.e., the released meshes of merged
+    /// shapes are only counted as part of the merged meshes.
+    pub fn get_memory_stats(&self) -> MemoryStats {
+        let mut shapes = BufferBytes::default();
+        let mut lods = BufferBytes::default();
+        for part in self.shapes.iter().flat_map(|shape| shape.parts.iter()) {
+            if let Some(mesh) = &part.mesh {
+                shapes.add_mesh(mesh);
+            }
+
+            for lod in part.lods.iter() {
+                lods.add_mesh(lod);
+            }
+        }
+
+        let mut edges = BufferBytes::default();
+        for edge_mesh in self.shapes.iter().filter_map(|shape| shape.edges.as_ref()) {
+            edges.add_mesh(edge_mesh);
+        }
+
+        let mut merged = BufferBytes::default();
+        for merged_mesh in self.merged_meshes.iter() {
+            merged.add_mesh(&merged_mesh.mesh);
+        }
+
+        MemoryStats {
+            shapes,
+            lods,
+            edges,
+            merged,
+        }
+    }
+
+    /// Returns the number of bytes the vertex data of all meshes would occupy on the GPU if
+    /// stored as 32-bit floats.
+    pub fn get_num_uncompressed_vertex_bytes(&self) -> usize {
+        self.meshes()
+            .map(|mesh| mesh.get_num_uncompressed_vertex_bytes())
+            .sum()
+    }
+
+    /// Merges the meshes of all instances of small shapes into combined GPU meshes, one for
+    /// each material and primitive type, with the transformations of the instances baked into
+    /// the vertices. The merged instances are drawn via the merged meshes and the GPU meshes of
+    /// their shapes are released, while the CPU meshes are kept, e.g., for picking. Returns the
+    /// number of merged instances.
+    ///
+    /// # Arguments
+    /// * `context` - The GLOW context used for creating the merged meshes.
+    pub fn merge_small_meshes(&mut self, context: &C) -> Result<usize> {
+        let start = Instant::now();
+
+        let is_mergeable: Vec<bool> = self
+            .shapes
+            .iter()
+            .map(|shape| {
+                let num_vertices: usize = shape
+                    .parts
+                    .iter()
+                    .map(|part| part.cpu_mesh.get_vertices().len())
+                    .sum();
+
+                // transparent parts are sorted per instance and cannot be merged
+                num_vertices <= MAX_MERGED_SHAPE_VERTICES
+                    && shape.parts.iter().all(|part| {
+                        get_merged_primitive_type(&part.cpu_mesh).is_some()
+                            && !is_transparent(&part.material)
+                    })
+            })
+            .collect();
+
+        // the builders currently filled for each material, primitive type and normals flag
+        let mut builders: Vec<MergedMeshBuilder> = Vec::new();
+        let mut open_builders: BTreeMap<(*const Material, PrimitiveType, bool), usize> =
+            BTreeMap::new();
+        let mut merged_instances = Vec::new();
+
+        for (instance_index, instance) in self.instances.iter().enumerate() {
+            if instance.merged || !is_mergeable[instance.shape_index] {
+                continue;
+            }
+
+            let normal_mat = compute_normal_matrix(&instance.transform);
+            let shape = &self.shapes[instance.shape_index];
+            for part in shape.parts.iter() {
+                let mesh = part.cpu_mesh.as_ref();
+                let primitive_type = get_merged_primitive_type(mesh).unwrap();
+                let has_normals = mesh.get_vertices().get_normals().is_some();
+                let key = (Rc::as_ptr(&part.material), primitive_type, has_normals);
+
+                let num_vertices = mesh.get_vertices().len();
+                let builder_index = match open_builders.get(&key) {
+                    Some(&index)
+                        if builders[index].num_vertices() + num_vertices
+                            <= MAX_MERGED_MESH_VERTICES =>
+                    {
+                        index
+                    }
+                    _ => {
+                        builders.push(MergedMeshBuilder::new(
+                            part.material.clone(),
+                            primitive_type,
+                            has_normals,
+                        ));
+                        open_builders.insert(key, builders.len() - 1);
+                        builders.len() - 1
+                    }
+                };
+
+                builders[builder_index].add(mesh, &instance.transform, &normal_mat, instance_index);
+            }
+
+            merged_instances.push(instance_index);
+        }
+
+        let mut merged_meshes = Vec::with_capacity(builders.len());
+        for builder in builders {
+            match builder.build(context, &self.vertex_format) {
+                Ok(merged_mesh) => merged_meshes.push(merged_mesh),
+                Err(err) => {
+                    for merged_mesh in merged_meshes.iter() {
+                        merged_mesh.mesh.cleanup(context);
+                    }
+
+                    return Err(err);
+                }
+            }
+        }
+
+        for instance_index in merged_instances.iter() {
+            self.instances[*instance_index].merged = true;
+        }
+
+        // all instances of the mergeable shapes are merged, i.e., only their CPU meshes for
+        // picking and their feature edges are still needed
+        for (shape, _) in self
+            .shapes
+            .iter_mut()
+            .zip(is_mergeable)
+            .filter(|(_, is_mergeable)| *is_mergeable)
+        {
+            for part in shape.parts.iter_mut() {
+                part.release_meshes(context);
+            }
+        }
+
+        debug!(
+            "Merged {} instances into {} meshes in {:?}",
+            merged_instances.len(),
+            merged_meshes.len(),
+            start.elapsed()
+        );
+        self.merged_meshes.extend(merged_meshes);
+
+        Ok(merged_instances.len())
+    }
+
+    /// Deletes all GPU resources and removes all shapes and instances.
+    ///
+    /// # Arguments
+    /// * `context` - The GLOW context used for deleting the GPU data.
+    pub fn cleanup(&mut self, context: &C) {
+        for mesh in self.shapes.iter().flat_map(|shape| shape.meshes()) {
+            mesh.cleanup(context);
+        }
+
+        for merged_mesh in self.merged_meshes.iter() {
+            merged_mesh.mesh.cleanup(context);
+        }
+
+        self.shapes.clear();
+        self.instances.clear();
+        self.nodes.clear();
+        self.merged_meshes.clear();
+        self.bvh = None;
+        self.upload = None;
     }
 
     /// Returns a reference onto the internally stored shapes.
@@ -63,39 +445,283 @@ impl<C: HasContext> GPUData<C> {
         &self.instances
     }
 
+    /// Returns the merged meshes of the small shape instances.
+    pub fn get_merged_meshes(&self) -> &[MergedMesh<C>] {
+        &self.merged_meshes
+    }
+
+    /// Returns the nodes of the scene tree in depth-first order, i.e., the first node is the
+    /// root node.
+    pub fn get_nodes(&self) -> &[SceneNode] {
+        &self.nodes
+    }
+
+    /// Sets the transformations of the given shape instances and updates their bounding
+    /// volumes. The bounding volume hierarchy is refitted once for all instances. Merged
+    /// instances are skipped, as their transformation is baked into the merged meshes.
+    ///
+    /// # Arguments
+    /// * `transforms` - The instance indices together with their new transformations.
+    // not used by the viewer yet, which has no way to move instances
+    #[allow(dead_code)]
+    pub fn set_instance_transforms(&mut self, transforms: &[(usize, Mat4)]) {
+        for (instance_index, transform) in transforms.iter() {
+            let instance = &mut self.instances[*instance_index];
+            if instance.merged {
+                continue;
+            }
+
+            instance.transform = *transform;
+            instance.bbox = self.shapes[instance.shape_index].bbox.transform(transform);
+
+            if let Some(bvh) = &mut self.bvh {
+                bvh.set_item_bbox(*instance_index, instance.bbox.clone());
+            }
+        }
+
+        if let Some(bvh) = &mut self.bvh {
+            bvh.refit();
+        }
+    }
+
+    /// Returns the indices of all shape instances whose bounding volume overlaps the given
+    /// volume in ascending order, regardless of their visibility.
+    ///
+    /// # Arguments
+    /// * `bbox` - The bounding volume to test against.
+    // not used by the viewer yet, which has no box selection
+    #[allow(dead_code)]
+    pub fn query_bbox(&self, bbox: &BBox) -> Vec<usize> {
+        let mut result = Vec::new();
+
+        match &self.bvh {
+            Some(bvh) => {
+                bvh.query_bbox(bbox, |instance_index| result.push(instance_index));
+                result.sort_unstable();
+            }
+            None => {
+                // the hierarchy is only available once the upload has been finished
+                for (instance_index, instance) in self.instances.iter().enumerate() {
+                    if instance.bbox.intersects(bbox) {
+                        result.push(instance_index);
+                    }
+                }
+            }
+        }
+
+        result
+    }
+
+    /// Returns the indices of all shape instances whose bounding volume intersects the given
+    /// frustum in ascending order, regardless of their visibility.
+    ///
+    /// # Arguments
+    /// * `frustum` - The frustum to test against.
+    pub fn query_frustum(&self, frustum: &Frustum) -> Vec<usize> {
+        let mut result = Vec::new();
+
+        match &self.bvh {
+            Some(bvh) => {
+                bvh.query_frustum(frustum, |instance_index| result.push(instance_index));
+                result.sort_unstable();
+            }
+            None => {
+                // the hierarchy is only available once the upload has been finished
+                for (instance_index, instance) in self.instances.iter().enumerate() {
+                    if frustum.intersects_bbox(&instance.bbox) {
+                        result.push(instance_index);
+                    }
+                }
+            }
+        }
+
+        result
+    }
+
+    /// Calls the given function for each shape instance whose bounding volume is hit by the
+    /// ray. The function returns the ray parameter of the hit with the instance if there is
+    /// any. Returns the closest reported hit.
+    ///
+    /// # Arguments
+    /// * `ray` - The ray in world coordinates.
+    /// * `f` - The function called with the index of each hit instance.
+    pub fn query_ray<F: FnMut(usize) -> Option<f32>>(&self, ray: &Ray, mut f: F) -> Option<f32> {
+        if let Some(bvh) = &self.bvh {
+            return bvh.query_ray(ray, f);
+        }
+
+        let mut closest: Option<f32> = None;
+        for (instance_index, instance) in self.instances.iter().enumerate() {
+            if ray.intersect_bbox(&instance.bbox).is_none() {
+                continue;
+            }
+
+            if let Some(t) = f(instance_index) {
+                if closest.is_none_or(|closest| t < closest) {
+                    closest = Some(t);
+                }
+            }
+        }
+
+        closest
+    }
+
+    /// Shows or hides the given range of shape instances.
+    ///
+    /// # Arguments
+    /// * `instances` - The range of the instances to update.
+    /// * `visible` - The new visibility of the instances.
+    pub fn set_visible(&mut self, instances: Range<usize>, visible: bool) {
+        for instance in self.instances[instances].iter_mut() {
+            instance.visible = visible;
+        }
+    }
+
+    /// Returns true if at least one instance of the given range is visible.
+    ///
+    /// # Arguments
+    /// * `instances` - The range of the instances to check.
+    pub fn is_any_visible(&self, instances: Range<usize>) -> bool {
+        self.instances[instances]
+            .iter()
+            .any(|instance| instance.visible)
+    }
+
+    /// Returns an iterator over all meshes on the GPU, i.e., the meshes of the shapes with
+    /// their levels of detail and feature edges and the merged meshes.
+    fn meshes(&self) -> impl Iterator<Item = &GPUMesh<C>> {
+        self.shapes.iter().flat_map(|shape| shape.meshes()).chain(
+            self.merged_meshes
+                .iter()
+                .map(|merged_mesh| &merged_mesh.mesh),
+        )
+    }
+
     /// Internal function for traversing over the node structure and copying all data to GPU.
+    /// The traversal is depth-first and stops as soon as the budget is exhausted. Returns true
+    /// if all nodes have been visited.
     ///
     /// # Arguments
     /// * `context` - The GLOW context used for initializing all GPU data.
-    /// * `node` - The currently visited node.
-    /// * `traversal_context` - Additional information used during traversal.
-    fn traverse(
+    /// * `root_node` - The root node of the traversed CAD data.
+    /// * `traversal_data` - The state of the traversal, i.e., the pending nodes.
+    /// * `is_exhausted` - Returns true if the budget is exhausted for the uploaded bytes.
+    /// * `num_bytes` - The number of uploaded bytes, updated during traversal.
+    fn traverse<F: Fn(usize) -> bool>(
         &mut self,
         context: &C,
-        node: &Node,
-        traversal_context: TraversalContext,
+        root_node: &Node,
         traversal_data: &mut TraversalData,
-    ) -> Result<()> {
-        let shapes = node.get_shapes();
+        is_exhausted: F,
+        num_bytes: &mut usize,
+    ) -> Result<bool> {
+        let mut made_progress = false;
+
+        while let Some(pending) = traversal_data.pending.last_mut() {
+            let node = pending.context.resolve(root_node);
+            let shapes = node.get_shapes();
+
+            // register the node in the scene tree when visiting it for the first time
+            let scene_node = match pending.scene_node {
+                Some(scene_node) => scene_node,
+                None => {
+                    let scene_node = self.add_scene_node(node, pending.parent);
+                    pending.scene_node = Some(scene_node);
+                    scene_node
+                }
+            };
 
-        for shape in shapes {
-            let shape_index = self.get_shape_index(context, shape, traversal_data)?;
+            if pending.next_shape < shapes.len() {
+                // always make progress, even if the budget is too small for a single shape
+                if made_progress && is_exhausted(*num_bytes) {
+                    return Ok(false);
+                }
+
+                let shape = &shapes[pending.next_shape];
+                let transform = pending.context.transform;
+                let node_id = node.get_id();
+                pending.next_shape += 1;
+
+                let shape_index =
+                    self.get_shape_index(context, shape, traversal_data, num_bytes)?;
+                let bbox = self.shapes[shape_index].bbox.transform(&transform);
+
+                self.instances.push(GPUShapeInstance {
+                    transform,
+                    shape_index,
+                    node_id,
+                    node_index: scene_node,
+                    visible: true,
+                    bbox,
+                    merged: false,
+                });
+
+                // extend the instance ranges of the node and all its ancestors
+                let num_instances = self.instances.len();
+                let mut current = Some(scene_node);
+                while let Some(index) = current {
+                    self.nodes[index].instances.end = num_instances;
+                    current = self.nodes[index].parent;
+                }
+
+                made_progress = true;
+                continue;
+            }
 
-            self.instances.push(GPUShapeInstance {
-                transform: traversal_context.transform,
-                shape_index,
-            });
+            // all shapes are done, continue with the children of the current node
+            let pending = traversal_data.pending.pop().unwrap();
+            for (child_index, child) in node.get_children().iter().enumerate().rev() {
+                traversal_data.pending.push(PendingNode {
+                    context: pending.context.derive(child, child_index),
+                    next_shape: 0,
+                    parent: Some(scene_node),
+                    scene_node: None,
+                });
+            }
         }
 
-        // traverse over the children of the current node
-        for child in node.get_children().iter() {
-            // compute new transform
-            let child_traversal_context = traversal_context.derive(child);
+        Ok(true)
+    }
+
+    /// Builds the bounding volume hierarchy over the bounding volumes of all instances.
+    fn build_bvh(&mut self) {
+        let start = Instant::now();
+        let bboxes = self
+            .instances
+            .iter()
+            .map(|instance| instance.bbox.clone())
+            .collect();
+
+        self.bvh = Some(Bvh::new(bboxes));
+        debug!(
+            "Built BVH over {} instances in {:?}",
+            self.instances.len(),
+            start.elapsed()
+        );
+    }
 
-            self.traverse(context, child, child_traversal_context, traversal_data)?;
+    /// Adds a new scene node for the given CAD node and returns its index.
+    ///
+    /// # Arguments
+    /// * `node` - The CAD node.
+    /// * `parent` - The index of the parent scene node or none for the root node.
+    fn add_scene_node(&mut self, node: &Node, parent: Option<usize>) -> usize {
+        let index = self.nodes.len();
+        let num_instances = self.instances.len();
+
+        self.nodes.push(SceneNode {
+            node_id: node.get_id(),
+            label: node.get_label().to_owned(),
+            parent,
+            children: Vec::new(),
+            instances: num_instances..num_instances,
+        });
+
+        if let Some(parent) = parent {
+            self.nodes[parent].children.push(index);
         }
 
-        Ok(())
+        index
     }
 
     /// Returns an index for the given shape
@@ -103,25 +729,46 @@ impl<C: HasContext> GPUData<C> {
     /// # Arguments
     /// * `context` - The GLOW context.
     /// * `shape` - The CPU
+    /// * `traversal_data` - The state of the traversal with the already created GPU shapes.
+    /// * `num_bytes` - The number of uploaded bytes, updated if a new GPU shape is created.
     fn get_shape_index(
         &mut self,
         context: &C,
         shape: &Shape,
         traversal_data: &mut TraversalData,
+        num_bytes: &mut usize,
     ) -> Result<usize> {
         let shape_id = shape.get_id();
 
         // check if a GPU shape for this shape already
-        match traversal_data.shape_map.get(&shape_id) {
-            Some(index) => {
-                return Ok(*index);
-            }
-            None => {}
+        if let Some(index) = traversal_data.shape_map.get(&shape_id) {
+            return Ok(*index);
         }
 
-        let index = traversal_data.shape_map.len();
+        let index = self.shapes.len();
 
-        let gpu_shape = Self::create_gpu_shape(context, shape)?;
+        let bbox = match traversal_data.shape_bboxes.remove(&shape_id) {
+            Some(bbox) => bbox,
+            None => compute_shape_bbox(shape),
+        };
+
+        let part_lods = traversal_data
+            .shape_lods
+            .remove(&shape_id)
+            .unwrap_or_default();
+
+        let gpu_shape = Self::create_gpu_shape(
+            context,
+            shape,
+            bbox,
+            part_lods,
+            &self.vertex_format,
+            self.crease_angle,
+        )?;
+        *num_bytes += gpu_shape
+            .meshes()
+            .map(|mesh| mesh.get_num_bytes())
+            .sum::<usize>();
         self.shapes.push(gpu_shape);
 
         traversal_data.shape_map.insert(shape_id, index);
@@ -129,28 +776,104 @@ impl<C: HasContext> GPUData<C> {
         Ok(index)
     }
 
-    /// Creates a GPU shape based on the given CPU shape.
+    /// Creates a GPU shape based on the given CPU shape. Quantized positions of all parts and
+    /// their levels of detail share a single volume, s.t. one dequantization fits the shape.
     ///
     /// # Arguments
     /// * `context` - The GLOW context.
     /// * `shape` - The CPU shape.
-    fn create_gpu_shape(context: &C, shape: &Shape) -> Result<GPUShape<C>> {
-        let mut parts = Vec::with_capacity(shape.get_parts().len());
+    /// * `bbox` - The local bounding volume of the CPU shape.
+    /// * `part_lods` - The simplified meshes of the coarser levels of detail for each part.
+    ///   Parts without an entry have no levels of detail.
+    /// * `vertex_format` - The format of the vertex data of the GPU meshes.
+    /// * `crease_angle` - The angle in degrees above which an edge is a feature edge.
+    fn create_gpu_shape(
+        context: &C,
+        shape: &Shape,
+        bbox: BBox,
+        mut part_lods: Vec<Vec<Mesh>>,
+        vertex_format: &VertexFormat,
+        crease_angle: f32,
+    ) -> Result<GPUShape<C>> {
+        part_lods.resize_with(shape.get_parts().len(), Vec::new);
+
+        // simplified vertices may be placed slightly outside of the shape volume
+        let mut volume = bbox.clone();
+        for lod in part_lods.iter().flatten() {
+            volume.extend_mesh(lod);
+        }
 
-        for part in shape.get_parts() {
+        let mut parts = Vec::with_capacity(shape.get_parts().len());
+        for (part, lods) in shape.get_parts().iter().zip(part_lods) {
             let material = part.get_material();
 
-            let gpu_mesh = GPUMesh::new(context, part.get_mesh().as_ref())?;
+            let cpu_mesh = part.get_mesh();
+            let gpu_mesh = GPUMesh::new(context, cpu_mesh.as_ref(), vertex_format, &volume)?;
+            let lods = lods
+                .iter()
+                .map(|lod| GPUMesh::new(context, lod, vertex_format, &volume))
+                .collect::<Result<Vec<_>>>()?;
 
             let gpu_part = GPUMeshWithMaterial {
                 material: material.clone(),
-                mesh: gpu_mesh,
+                mesh: Some(gpu_mesh),
+                cpu_mesh,
+                lods,
             };
 
             parts.push(gpu_part);
         }
 
-        Ok(GPUShape { parts })
+        // the feature edges of all parts are drawn at once with the same color
+        let edge_segments: Vec<_> = shape
+            .get_parts()
+            .iter()
+            .flat_map(|part| extract_feature_edges(part.get_mesh().as_ref(), crease_angle))
+            .collect();
+        let edges = if edge_segments.is_empty() {
+            None
+        } else {
+            let edge_mesh = create_line_mesh(&edge_segments)?;
+            Some(GPUMesh::new(context, &edge_mesh, vertex_format, &volume)?)
+        };
+
+        let position_transform = if vertex_format.quantized_positions {
+            get_dequantization_matrix(&volume)
+        } else {
+            Mat4::identity()
+        };
+
+        Ok(GPUShape {
+            parts,
+            shape_id: shape.get_id(),
+            bbox,
+            position_transform,
+            edges,
+        })
+    }
+}
+
+/// Computes the bounding volume of the given shape in its local coordinates.
+///
+/// # Arguments
+/// * `shape` - The shape whose volume is computed.
+pub fn compute_shape_bbox(shape: &Shape) -> BBox {
+    let mut bbox = BBox::new();
+    for part in shape.get_parts() {
+        bbox.extend_mesh(&part.get_mesh());
+    }
+
+    bbox
+}
+
+/// Returns true if the given material is at least partially transparent.
+///
+/// # Arguments
+/// * `material` - The material to check.
+pub fn is_transparent(material: &Material) -> bool {
+    match material {
+        Material::PhongMaterial(p) => p.transparency > 0f32,
+        Material::None => false,
     }
 }
 
@@ -159,6 +882,9 @@ impl<C: HasContext> GPUData<C> {
 struct TraversalContext {
     /// The current transformation matrix
     transform: Mat4,
+
+    /// The path of child indices from the root node to the current node.
+    node_path: Vec<usize>,
 }
 
 impl TraversalContext {
@@ -169,35 +895,76 @@ impl TraversalContext {
             None => Mat4::identity(),
         };
 
-        Self { transform }
+        Self {
+            transform,
+            node_path: Vec::new(),
+        }
     }
 
     /// Returns a new traversal context by visiting the given node.
     ///
     /// # Arguments
     /// * `node` - The node to visit based on the current traversal context
-    pub fn derive(&self, node: &Node) -> Self {
+    /// * `child_index` - The index of the node within the children of the current node.
+    pub fn derive(&self, node: &Node, child_index: usize) -> Self {
         let mut result = self.clone();
 
-        match node.get_transform() {
-            Some(t) => {
-                result.transform *= t;
-            }
-            None => {}
+        if let Some(t) = node.get_transform() {
+            result.transform *= t;
         }
 
+        result.node_path.push(child_index);
+
         result
     }
+
+    /// Returns the node of this context.
+    ///
+    /// # Arguments
+    /// * `root_node` - The root node the path of this context starts from.
+    pub fn resolve<'a>(&self, root_node: &'a Node) -> &'a Node {
+        self.node_path.iter().fold(root_node, |node, child_index| {
+            &node.get_children()[*child_index]
+        })
+    }
+}
+
+/// A node whose shapes have not been completely uploaded yet.
+struct PendingNode {
+    /// The traversal context of the node.
+    context: TraversalContext,
+
+    /// The index of the next shape of the node to upload.
+    next_shape: usize,
+
+    /// The index of the parent scene node or none for the root node.
+    parent: Option<usize>,
+
+    /// The index of the scene node or none if the node hasn't been visited yet.
+    scene_node: Option<usize>,
 }
 
+/// The state of an incremental upload.
 struct TraversalData {
     pub shape_map: HashMap<ID, usize>,
+
+    /// The precomputed local bounding volumes of the shapes without a GPU shape yet.
+    pub shape_bboxes: HashMap<ID, BBox>,
+
+    /// The stack of nodes that still need to be visited.
+    pub pending: Vec<PendingNode>,
+
+    /// The precomputed levels of detail for the parts of the shapes without a GPU shape yet.
+    pub shape_lods: HashMap<ID, Vec<Vec<Mesh>>>,
 }
 
 impl TraversalData {
-    pub fn new() -> Self {
+    pub fn new(shape_bboxes: HashMap<ID, BBox>, shape_lods: HashMap<ID, Vec<Vec<Mesh>>>) -> Self {
         Self {
             shape_map: HashMap::new(),
+            shape_bboxes,
+            pending: Vec::new(),
+            shape_lods,
         }
     }
 }
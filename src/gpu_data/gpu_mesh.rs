@@ -1,17 +1,33 @@
+use std::ops::Range;
+
 use anyhow::Result;
-use cad_import::structure::{IndexData, Mesh, PrimitiveType, Vertices};
+use cad_import::structure::{IndexData, Mesh, Normal, Point3D, PrimitiveType, Vertices};
 use glow::HasContext;
 
-use crate::{gl_call, viewer::gl_call::handle_glow_error};
+use crate::{geometry::BBox, gl_call, viewer::gl_call::handle_glow_error};
 
-use super::buffer::Buffer;
+use super::{
+    buffer::Buffer,
+    instance_buffer::InstanceBuffer,
+    vertex_format::{encode_vertices, AttributeLayout, VertexFormat},
+};
 
 type VertexBuffer<C> = Buffer<C, { glow::ARRAY_BUFFER }>;
 type IndexBuffer<C> = Buffer<C, { glow::ELEMENT_ARRAY_BUFFER }>;
 
+/// The maximal number of vertices of a mesh for storing its indices as 16-bit integers.
+const MAX_U16_INDEX_VERTICES: usize = u16::MAX as usize + 1;
+
+/// A vertex buffer together with the layout of the attributes stored in it.
+struct VertexBufferWithLayout<C: HasContext> {
+    pub buffer: VertexBuffer<C>,
+    pub stride: i32,
+    pub attributes: Vec<AttributeLayout>,
+}
+
 struct VertexAttributes<C: HasContext> {
-    pub position: VertexBuffer<C>,
-    pub normal: Option<VertexBuffer<C>>,
+    pub buffers: Vec<VertexBufferWithLayout<C>>,
+    pub has_normals: bool,
 }
 
 /// A single GPU mesh defined by vertices and primitives.
@@ -22,6 +38,13 @@ pub struct GPUMesh<C: HasContext> {
     primitive_type: u32,
     num_indices: u32,
     indices: Option<IndexBuffer<C>>,
+
+    /// The OpenGL type of a single index, i.e., 16-bit or 32-bit unsigned integers.
+    index_type: u32,
+
+    num_vertex_bytes: usize,
+    num_index_bytes: usize,
+    num_uncompressed_vertex_bytes: usize,
 }
 
 impl<C: HasContext> GPUMesh<C> {
@@ -30,18 +53,39 @@ impl<C: HasContext> GPUMesh<C> {
     /// # Arguments
     /// * `context` - The GLOW context used for accessing the GPU.
     /// * `mesh` - The CPU mesh data to copy to GPU.
-    pub fn new(context: &C, mesh: &Mesh) -> Result<Self> {
+    /// * `format` - The format of the vertex data on the GPU.
+    /// * `volume` - The volume enclosing the positions, used for quantizing the positions.
+    pub fn new(context: &C, mesh: &Mesh, format: &VertexFormat, volume: &BBox) -> Result<Self> {
         let primitives = mesh.get_primitives();
         let primitive_type = Self::translate_primitive_type(primitives.get_primitive_type())?;
         let num_indices = primitives.get_raw_index_data().num_indices() as u32;
 
         let vertices = mesh.get_vertices();
-        let (vertices, vertex_array) = Self::create_vertex_data(context, vertices)?;
+        let mut num_uncompressed_vertex_bytes = vertices.len() * std::mem::size_of::<Point3D>();
+        if vertices.get_normals().is_some() {
+            num_uncompressed_vertex_bytes += vertices.len() * std::mem::size_of::<Normal>();
+        }
+
+        let num_vertices = vertices.len();
+        let (vertices, vertex_array, num_vertex_bytes) =
+            Self::create_vertex_data(context, vertices, format, volume)?;
 
+        let mut index_type = glow::UNSIGNED_INT;
+        let mut num_index_bytes = 0;
         let indices = match primitives.get_raw_index_data() {
             IndexData::Indices(raw_indices) => {
                 let indices = IndexBuffer::<C>::new(context)?;
-                indices.set_data(context, raw_indices, super::buffer::Usage::Static);
+
+                // small meshes only need half of the index memory
+                if num_vertices < MAX_U16_INDEX_VERTICES {
+                    let raw_indices: Vec<u16> = raw_indices.iter().map(|i| *i as u16).collect();
+                    indices.set_data(context, &raw_indices, super::buffer::Usage::Static);
+                    index_type = glow::UNSIGNED_SHORT;
+                    num_index_bytes = std::mem::size_of_val(raw_indices.as_slice());
+                } else {
+                    indices.set_data(context, raw_indices, super::buffer::Usage::Static);
+                    num_index_bytes = std::mem::size_of_val(raw_indices.as_slice());
+                }
 
                 Some(indices)
             }
@@ -54,32 +98,76 @@ impl<C: HasContext> GPUMesh<C> {
             primitive_type,
             num_indices,
             indices,
+            index_type,
+            num_vertex_bytes,
+            num_index_bytes,
+            num_uncompressed_vertex_bytes,
         })
     }
 
-    /// Renders the whole GPU mesh.
-    pub fn draw(&self, context: &C) {
+    /// Renders the whole GPU mesh once for each of the given instances.
+    ///
+    /// # Arguments
+    /// * `context` - The GLOW context.
+    /// * `instances` - The buffer with the attributes of the instances.
+    /// * `first_instance` - The index of the first instance within the buffer.
+    /// * `num_instances` - The number of instances to draw.
+    pub fn draw_instanced(
+        &self,
+        context: &C,
+        instances: &InstanceBuffer<C>,
+        first_instance: usize,
+        num_instances: usize,
+    ) {
+        self.draw_range_instanced(
+            context,
+            instances,
+            first_instance,
+            num_instances,
+            0..self.num_indices as usize,
+        );
+    }
+
+    /// Renders the given range of indices of the GPU mesh once for each of the given instances.
+    ///
+    /// # Arguments
+    /// * `context` - The GLOW context.
+    /// * `instances` - The buffer with the attributes of the instances.
+    /// * `first_instance` - The index of the first instance within the buffer.
+    /// * `num_instances` - The number of instances to draw.
+    /// * `range` - The range of the indices to draw.
+    pub fn draw_range_instanced(
+        &self,
+        context: &C,
+        instances: &InstanceBuffer<C>,
+        first_instance: usize,
+        num_instances: usize,
+        range: Range<usize>,
+    ) {
         gl_call!(context, bind_vertex_array, Some(self.vertex_array));
+        instances.bind_attributes(context, first_instance);
 
         match &self.indices {
             Some(indices) => {
                 indices.bind(context);
                 gl_call!(
                     context,
-                    draw_elements,
+                    draw_elements_instanced,
                     self.primitive_type,
-                    self.num_indices as i32,
-                    glow::UNSIGNED_INT,
-                    0
+                    range.len() as i32,
+                    self.index_type,
+                    (range.start * self.get_index_size()) as i32,
+                    num_instances as i32
                 );
             }
             None => {
                 gl_call!(
                     context,
-                    draw_arrays,
+                    draw_arrays_instanced,
                     self.primitive_type,
-                    0,
-                    self.num_indices as i32
+                    range.start as i32,
+                    range.len() as i32,
+                    num_instances as i32
                 );
             }
         }
@@ -87,9 +175,55 @@ impl<C: HasContext> GPUMesh<C> {
         gl_call!(context, bind_vertex_array, None);
     }
 
+    /// Deletes the vertex array and all buffers of the mesh on the GPU.
+    pub fn cleanup(&self, context: &C) {
+        gl_call!(context, delete_vertex_array, self.vertex_array);
+
+        for vertex_buffer in self.vertices.buffers.iter() {
+            vertex_buffer.buffer.cleanup(context);
+        }
+
+        if let Some(indices) = &self.indices {
+            indices.cleanup(context);
+        }
+    }
+
+    /// Returns the number of bytes occupied by the mesh buffers on the GPU.
+    pub fn get_num_bytes(&self) -> usize {
+        self.num_vertex_bytes + self.num_index_bytes
+    }
+
+    /// Returns the number of bytes occupied by the vertex data on the GPU.
+    pub fn get_num_vertex_bytes(&self) -> usize {
+        self.num_vertex_bytes
+    }
+
+    /// Returns the number of bytes occupied by the indices on the GPU.
+    pub fn get_num_index_bytes(&self) -> usize {
+        self.num_index_bytes
+    }
+
+    /// Returns the number of bytes the vertex data would occupy if stored as 32-bit floats.
+    pub fn get_num_uncompressed_vertex_bytes(&self) -> usize {
+        self.num_uncompressed_vertex_bytes
+    }
+
     /// Returns true if normals are defined
     pub fn has_normals(&self) -> bool {
-        self.vertices.normal.is_some()
+        self.vertices.has_normals
+    }
+
+    /// Returns the OpenGL primitive type of the mesh.
+    pub fn get_primitive_type(&self) -> u32 {
+        self.primitive_type
+    }
+
+    /// Returns the number of bytes of a single index.
+    fn get_index_size(&self) -> usize {
+        match self.index_type {
+            glow::UNSIGNED_SHORT => std::mem::size_of::<u16>(),
+            _ => std::mem::size_of::<u32>(),
+        }
     }
 
     /// Translates the given cad_import primitive type to glow primitive type.
@@ -108,49 +242,53 @@ impl<C: HasContext> GPUMesh<C> {
         }
     }
 
-    /// Creates the vertex array from the given vertex data.
+    /// Creates the vertex array from the given vertex data. Returns the vertex buffers, the
+    /// vertex array and the number of bytes of the vertex buffers.
     ///
     /// # Arguments
     /// * `context` - The GLOW context to use for creating the vertex array.
     /// * `vertices` - The vertex data on CPU memory to transfer to the GPU.
+    /// * `format` - The format of the vertex data on the GPU.
+    /// * `volume` - The volume enclosing the positions, used for quantizing the positions.
     fn create_vertex_data(
         context: &C,
         vertices: &Vertices,
-    ) -> Result<(VertexAttributes<C>, C::VertexArray)> {
-        // copy position data onto the GPU
-        let position = VertexBuffer::<C>::new(context)?;
-        position.set_data(
-            context,
-            vertices.get_positions().as_slice(),
-            super::buffer::Usage::Static,
-        );
+        format: &VertexFormat,
+        volume: &BBox,
+    ) -> Result<(VertexAttributes<C>, C::VertexArray, usize)> {
+        let mut num_bytes = 0;
+        let mut buffers = Vec::new();
+        for encoded in encode_vertices(vertices, format, volume) {
+            let buffer = VertexBuffer::<C>::new(context)?;
+            buffer.set_data(
+                context,
+                encoded.data.as_slice(),
+                super::buffer::Usage::Static,
+            );
+            num_bytes += encoded.data.len();
 
-        // normal data
-        let normal = match vertices.get_normals() {
-            Some(normal_data) => {
-                let normal = VertexBuffer::<C>::new(context)?;
-                normal.set_data(
-                    context,
-                    normal_data.as_slice(),
-                    super::buffer::Usage::Static,
-                );
+            buffers.push(VertexBufferWithLayout {
+                buffer,
+                stride: encoded.stride,
+                attributes: encoded.attributes,
+            });
+        }
 
-                Some(normal)
-            }
-            None => None,
+        let vertex_attributes = VertexAttributes {
+            buffers,
+            has_normals: vertices.get_normals().is_some(),
         };
 
-        let vertex_attributes = VertexAttributes { position, normal };
-
         // initialize vertex array data...
         let vertex_array = handle_glow_error(gl_call!(context, create_vertex_array))?;
         Self::initialize_vertex_array(context, vertex_array, &vertex_attributes);
 
         gl_call!(context, bind_vertex_array, None);
-        Ok((vertex_attributes, vertex_array))
+        Ok((vertex_attributes, vertex_array, num_bytes))
     }
 
-    /// Initializes the vertex array with the given vertex attribute data.
+    /// Initializes the vertex array with the given vertex attribute data, i.e., the attribute
+    /// pointers are configured according to the layouts of the vertex buffers.
     ///
     /// # Arguments
     /// * `context` - The GLOW context.
@@ -163,39 +301,27 @@ impl<C: HasContext> GPUMesh<C> {
     ) {
         gl_call!(context, bind_vertex_array, Some(vertex_array));
 
-        // positions
-        attributes.position.bind(context);
-        gl_call!(context, enable_vertex_attrib_array, 0);
-        gl_call!(
-            context,
-            vertex_attrib_pointer_f32,
-            0,
-            3,
-            glow::FLOAT,
-            false,
-            0,
-            0
-        );
+        for vertex_buffer in attributes.buffers.iter() {
+            vertex_buffer.buffer.bind(context);
 
-        // normals
-        match &attributes.normal {
-            Some(normals) => {
-                normals.bind(context);
-                gl_call!(context, enable_vertex_attrib_array, 1);
+            for attribute in vertex_buffer.attributes.iter() {
+                gl_call!(context, enable_vertex_attrib_array, attribute.location);
                 gl_call!(
                     context,
                     vertex_attrib_pointer_f32,
-                    1,
-                    3,
-                    glow::FLOAT,
-                    false,
-                    0,
-                    0
+                    attribute.location,
+                    attribute.num_components,
+                    attribute.data_type,
+                    attribute.normalized,
+                    vertex_buffer.stride,
+                    attribute.offset
                 );
             }
-            None => {
-                gl_call!(context, disable_vertex_attrib_array, 1);
-            }
+        }
+
+        // normals
+        if !attributes.has_normals {
+            gl_call!(context, disable_vertex_attrib_array, 1);
         }
 
         gl_call!(context, bind_vertex_array, None);
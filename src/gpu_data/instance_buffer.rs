@@ -0,0 +1,117 @@
+use anyhow::Result;
+use glow::HasContext;
+use nalgebra_glm::{Mat3, Mat4};
+
+use crate::gl_call;
+
+use super::buffer::{Buffer, Usage};
+
+type VertexBuffer<C> = Buffer<C, { glow::ARRAY_BUFFER }>;
+
+/// The first vertex attribute location used by the per-instance attributes. The locations
+/// before are used by the vertex attributes of the meshes.
+const FIRST_LOCATION: u32 = 2;
+
+/// The per-instance attributes as stored in the instance buffer.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct InstanceAttributes {
+    /// The column major transformation of the instance.
+    transform: [f32; 16],
+
+    /// The column major normal matrix of the instance transformation.
+    normal_mat: [f32; 9],
+
+    /// One if the instance is highlighted and zero otherwise.
+    highlight: f32,
+}
+
+impl InstanceAttributes {
+    /// Creates the attributes for a single instance.
+    ///
+    /// # Arguments
+    /// * `transform` - The transformation of the instance.
+    /// * `normal_mat` - The normal matrix of the instance transformation.
+    /// * `highlighted` - Flag indicating if the instance is highlighted.
+    pub fn new(transform: &Mat4, normal_mat: &Mat3, highlighted: bool) -> Self {
+        let mut attributes = Self {
+            transform: [0f32; 16],
+            normal_mat: [0f32; 9],
+            highlight: if highlighted { 1f32 } else { 0f32 },
+        };
+
+        attributes.transform.copy_from_slice(transform.as_slice());
+        attributes.normal_mat.copy_from_slice(normal_mat.as_slice());
+
+        attributes
+    }
+}
+
+/// A GPU buffer with the attributes of the instances to be drawn with instanced draw-calls.
+pub struct InstanceBuffer<C: HasContext> {
+    buffer: VertexBuffer<C>,
+}
+
+impl<C: HasContext> InstanceBuffer<C> {
+    /// Creates a new empty instance buffer.
+    ///
+    /// # Arguments
+    /// * `context` - The GLOW context used for accessing the GPU.
+    pub fn new(context: &C) -> Result<Self> {
+        let buffer = VertexBuffer::<C>::new(context)?;
+
+        Ok(Self { buffer })
+    }
+
+    /// Replaces the content of the buffer by the given instance attributes.
+    ///
+    /// # Arguments
+    /// * `context` - The GLOW context.
+    /// * `instances` - The attributes of the instances.
+    pub fn set_instances(&self, context: &C, instances: &[InstanceAttributes]) {
+        self.buffer.set_data(context, instances, Usage::Stream);
+    }
+
+    /// Points the per-instance vertex attributes of the currently bound vertex array to the
+    /// instances starting at the given index.
+    ///
+    /// # Arguments
+    /// * `context` - The GLOW context.
+    /// * `first_instance` - The index of the first instance to draw.
+    pub fn bind_attributes(&self, context: &C, first_instance: usize) {
+        let stride = std::mem::size_of::<InstanceAttributes>();
+        let base = (first_instance * stride) as i32;
+        let stride = stride as i32;
+        let float_size = std::mem::size_of::<f32>() as i32;
+
+        self.buffer.bind(context);
+
+        // the matrices occupy one attribute location per column
+        let mut location = FIRST_LOCATION;
+        let mut offset = base;
+        for (num_columns, num_rows) in [(4, 4), (3, 3), (1, 1)] {
+            for _ in 0..num_columns {
+                gl_call!(context, enable_vertex_attrib_array, location);
+                gl_call!(
+                    context,
+                    vertex_attrib_pointer_f32,
+                    location,
+                    num_rows,
+                    glow::FLOAT,
+                    false,
+                    stride,
+                    offset
+                );
+                gl_call!(context, vertex_attrib_divisor, location, 1);
+
+                location += 1;
+                offset += num_rows * float_size;
+            }
+        }
+    }
+
+    /// Deletes the buffer on the GPU.
+    pub fn cleanup(&self, context: &C) {
+        self.buffer.cleanup(context);
+    }
+}
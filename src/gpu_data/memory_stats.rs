@@ -0,0 +1,110 @@
+use std::fmt;
+use std::fmt::Display;
+
+use glow::HasContext;
+
+use super::gpu_mesh::GPUMesh;
+
+/// The number of bytes occupied by the vertex and index buffers of a set of meshes.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BufferBytes {
+    pub vertices: usize,
+    pub indices: usize,
+}
+
+impl BufferBytes {
+    /// Adds the buffers of the given mesh.
+    ///
+    /// # Arguments
+    /// * `mesh` - The mesh whose buffers are added.
+    pub fn add_mesh<C: HasContext>(&mut self, mesh: &GPUMesh<C>) {
+        self.vertices += mesh.get_num_vertex_bytes();
+        self.indices += mesh.get_num_index_bytes();
+    }
+
+    /// Returns the number of bytes of all buffers.
+    pub fn total(&self) -> usize {
+        self.vertices + self.indices
+    }
+}
+
+/// The number of bytes occupied by the buffers on the GPU per category of meshes.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MemoryStats {
+    /// The full detail meshes of the shapes.
+    pub shapes: BufferBytes,
+
+    /// The meshes of the coarser levels of detail.
+    pub lods: BufferBytes,
+
+    /// The line meshes of the feature edges of the shapes.
+    pub edges: BufferBytes,
+
+    /// The merged meshes of the small shape instances.
+    pub merged: BufferBytes,
+}
+
+impl MemoryStats {
+    /// Returns the number of bytes of all buffers.
+    pub fn total(&self) -> usize {
+        self.categories().map(|bytes| bytes.total()).sum()
+    }
+
+    /// Returns the number of bytes of all vertex buffers.
+    pub fn num_vertex_bytes(&self) -> usize {
+        self.categories().map(|bytes| bytes.vertices).sum()
+    }
+
+    /// Returns an iterator over the buffer bytes of all categories.
+    fn categories(&self) -> impl Iterator<Item = &BufferBytes> {
+        [&self.shapes, &self.lods, &self.edges, &self.merged].into_iter()
+    }
+}
+
+impl Display for BufferBytes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:.2} MiB (vertices {:.2} MiB, indices {:.2} MiB)",
+            to_mib(self.total()),
+            to_mib(self.vertices),
+            to_mib(self.indices)
+        )
+    }
+}
+
+impl Display for MemoryStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "shapes {}, LODs {}, edges {}, merged {}",
+            self.shapes, self.lods, self.edges, self.merged
+        )
+    }
+}
+
+/// Converts the given number of bytes into MiB.
+fn to_mib(num_bytes: usize) -> f64 {
+    num_bytes as f64 / (1024f64 * 1024f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_totals() {
+        let bytes = |vertices, indices| BufferBytes { vertices, indices };
+        let stats = MemoryStats {
+            shapes: bytes(1000, 200),
+            lods: bytes(300, 40),
+            edges: bytes(50, 6),
+            merged: bytes(7000, 800),
+        };
+
+        assert_eq!(stats.shapes.total(), 1200);
+        assert_eq!(stats.num_vertex_bytes(), 8350);
+        assert_eq!(stats.total(), 9396);
+        assert_eq!(MemoryStats::default().total(), 0);
+    }
+}
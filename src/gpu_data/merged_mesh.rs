@@ -0,0 +1,166 @@
+use std::{ops::Range, rc::Rc};
+
+use anyhow::Result;
+use cad_import::structure::{
+    IndexData, Material, Mesh, Normal, Point3D, PrimitiveType, Primitives, Vertices,
+};
+use glow::HasContext;
+use nalgebra_glm::{normalize, Mat3, Mat4, Vec4};
+
+use crate::geometry::{for_each_triangle, BBox};
+
+use super::{gpu_mesh::GPUMesh, vertex_format::VertexFormat};
+
+/// The maximal number of vertices of a shape for merging its meshes.
+pub const MAX_MERGED_SHAPE_VERTICES: usize = 1024;
+
+/// The maximal number of vertices of a single merged mesh.
+pub const MAX_MERGED_MESH_VERTICES: usize = 1 << 20;
+
+/// The range of indices of a merged mesh occupied by a single part of a shape instance.
+#[derive(Debug, Clone)]
+pub struct MergedRange {
+    /// The index of the shape instance within the instances of the GPU data.
+    pub instance_index: usize,
+
+    /// The range of the indices of the part.
+    pub indices: Range<usize>,
+}
+
+/// A GPU mesh combining the parts of several shape instances with the same material and
+/// primitive type. The vertices are stored in world coordinates.
+pub struct MergedMesh<C: HasContext> {
+    pub material: Rc<Material>,
+    pub mesh: GPUMesh<C>,
+
+    /// The index ranges of the merged parts ordered by their instance.
+    pub ranges: Vec<MergedRange>,
+}
+
+/// Collects the CPU data of a merged mesh.
+pub struct MergedMeshBuilder {
+    material: Rc<Material>,
+    primitive_type: PrimitiveType,
+    positions: Vec<Point3D>,
+    normals: Option<Vec<Normal>>,
+    indices: Vec<u32>,
+    ranges: Vec<MergedRange>,
+}
+
+impl MergedMeshBuilder {
+    /// Creates a new empty builder.
+    ///
+    /// # Arguments
+    /// * `material` - The material shared by all merged parts.
+    /// * `primitive_type` - The primitive type of the merged mesh.
+    /// * `has_normals` - Flag indicating if the merged parts have normals.
+    pub fn new(material: Rc<Material>, primitive_type: PrimitiveType, has_normals: bool) -> Self {
+        Self {
+            material,
+            primitive_type,
+            positions: Vec::new(),
+            normals: if has_normals { Some(Vec::new()) } else { None },
+            indices: Vec::new(),
+            ranges: Vec::new(),
+        }
+    }
+
+    /// Returns the number of vertices added so far.
+    pub fn num_vertices(&self) -> usize {
+        self.positions.len()
+    }
+
+    /// Appends the given mesh transformed into world coordinates.
+    ///
+    /// # Arguments
+    /// * `mesh` - The mesh of the part to append.
+    /// * `transform` - The transformation of the shape instance.
+    /// * `normal_mat` - The normal matrix of the transformation.
+    /// * `instance_index` - The index of the shape instance.
+    pub fn add(&mut self, mesh: &Mesh, transform: &Mat4, normal_mat: &Mat3, instance_index: usize) {
+        let vertices = mesh.get_vertices();
+        let offset = self.positions.len() as u32;
+        let first_index = self.indices.len();
+
+        self.positions
+            .extend(vertices.get_positions().iter().map(|p| {
+                let p = transform * Vec4::new(p.0[0], p.0[1], p.0[2], 1f32);
+                Point3D::new(p[0], p[1], p[2])
+            }));
+
+        if let Some(normals) = &mut self.normals {
+            if let Some(src_normals) = vertices.get_normals() {
+                normals.extend(src_normals.iter().map(|n| {
+                    let n = normalize(&(normal_mat * n.0));
+                    Normal::new(n[0], n[1], n[2])
+                }));
+            }
+        }
+
+        match self.primitive_type {
+            PrimitiveType::Triangles => {
+                // strips and fans are decomposed into single triangles
+                for_each_triangle(mesh, |t| {
+                    self.indices.extend(t.iter().map(|i| i + offset));
+                });
+            }
+            _ => match mesh.get_primitives().get_raw_index_data() {
+                IndexData::Indices(indices) => {
+                    self.indices.extend(indices.iter().map(|i| i + offset));
+                }
+                IndexData::NonIndexed(num_indices) => {
+                    self.indices
+                        .extend((0..*num_indices as u32).map(|i| i + offset));
+                }
+            },
+        }
+
+        self.ranges.push(MergedRange {
+            instance_index,
+            indices: first_index..self.indices.len(),
+        });
+    }
+
+    /// Creates the merged mesh on the GPU. The positions are never quantized, as the world
+    /// coordinates of many instances would lose too much precision.
+    ///
+    /// # Arguments
+    /// * `context` - The GLOW context used for creating the GPU mesh.
+    /// * `format` - The format of the vertex data on the GPU.
+    pub fn build<C: HasContext>(self, context: &C, format: &VertexFormat) -> Result<MergedMesh<C>> {
+        let mut vertices = Vertices::from_positions(self.positions);
+        if let Some(normals) = self.normals {
+            vertices.set_normals(normals)?;
+        }
+
+        let primitives = Primitives::new(IndexData::Indices(self.indices), self.primitive_type)?;
+        let mesh = Mesh::new(vertices, primitives)?;
+        let format = VertexFormat {
+            quantized_positions: false,
+            ..*format
+        };
+
+        Ok(MergedMesh {
+            material: self.material,
+            mesh: GPUMesh::new(context, &mesh, &format, &BBox::new())?,
+            ranges: self.ranges,
+        })
+    }
+}
+
+/// Returns the primitive type of the given mesh after merging or none if the mesh cannot be
+/// merged. Triangle strips and fans are merged as single triangles, line strips and loops are
+/// not merged at all.
+///
+/// # Arguments
+/// * `mesh` - The mesh to check.
+pub fn get_merged_primitive_type(mesh: &Mesh) -> Option<PrimitiveType> {
+    match mesh.get_primitives().get_primitive_type() {
+        PrimitiveType::Point => Some(PrimitiveType::Point),
+        PrimitiveType::Line => Some(PrimitiveType::Line),
+        PrimitiveType::Triangles | PrimitiveType::TriangleStrip | PrimitiveType::TriangleFan => {
+            Some(PrimitiveType::Triangles)
+        }
+        PrimitiveType::LineStrip | PrimitiveType::LineLoop => None,
+    }
+}
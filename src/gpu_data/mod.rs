@@ -1,5 +1,14 @@
 mod buffer;
+mod bvh;
+#[allow(clippy::module_inception)]
 mod gpu_data;
 mod gpu_mesh;
+mod instance_buffer;
+mod memory_stats;
+mod merged_mesh;
+mod scene_node;
+mod vertex_format;
 
-pub use gpu_data::GPUData;
+pub use gpu_data::{compute_shape_bbox, is_transparent, GPUData, UploadBudget};
+pub use instance_buffer::{InstanceAttributes, InstanceBuffer};
+pub use vertex_format::VertexFormat;
@@ -0,0 +1,23 @@
+use std::ops::Range;
+
+use cad_import::ID;
+
+/// A node of the scene tree, i.e., of the node hierarchy of the CAD data, together with the
+/// shape instances of its subtree.
+pub struct SceneNode {
+    /// The ID of the CAD node.
+    pub node_id: ID,
+
+    /// The label of the CAD node.
+    pub label: String,
+
+    /// The index of the parent scene node or none for the root node.
+    pub parent: Option<usize>,
+
+    /// The indices of the child scene nodes.
+    pub children: Vec<usize>,
+
+    /// The range of the instances of the node and all its descendants. The range is contiguous,
+    /// as the nodes are traversed depth-first.
+    pub instances: Range<usize>,
+}
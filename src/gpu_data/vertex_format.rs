@@ -0,0 +1,418 @@
+use cad_import::structure::{Normal, Point3D, Vertices};
+use nalgebra_glm::{scaling, translation, Mat4, Vec3};
+
+use crate::geometry::BBox;
+
+/// The vertex attribute location of the positions.
+const POSITION_LOCATION: u32 = 0;
+
+/// The vertex attribute location of the normals.
+const NORMAL_LOCATION: u32 = 1;
+
+/// The format of the vertex data of the meshes on the GPU.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct VertexFormat {
+    /// Interleaves all vertex attributes of a mesh into a single buffer.
+    pub interleaved: bool,
+
+    /// Stores the positions as normalized 16-bit integers relative to a bounding volume.
+    pub quantized_positions: bool,
+
+    /// Stores the normals octahedral encoded as two normalized 16-bit integers.
+    pub octahedral_normals: bool,
+}
+
+/// The layout of a single vertex attribute within a vertex buffer.
+#[derive(Debug, Clone, Copy)]
+pub struct AttributeLayout {
+    pub location: u32,
+    pub num_components: i32,
+    pub data_type: u32,
+    pub normalized: bool,
+
+    /// The offset of the attribute within a vertex in bytes.
+    pub offset: i32,
+}
+
+/// The encoded content of a single vertex buffer with the layout of its attributes.
+pub struct EncodedVertexBuffer {
+    pub data: Vec<u8>,
+
+    /// The number of bytes of a single vertex.
+    pub stride: i32,
+
+    pub attributes: Vec<AttributeLayout>,
+}
+
+/// Encodes the given vertices into vertex buffers of the given format, i.e., either a single
+/// interleaved buffer or one buffer for each attribute. Quantized positions are stored relative
+/// to the given volume and are mapped back by the matrix of `get_dequantization_matrix`.
+///
+/// # Arguments
+/// * `vertices` - The vertices to encode.
+/// * `format` - The format of the encoded vertices.
+/// * `volume` - The volume enclosing all positions, only used for quantized positions.
+pub fn encode_vertices(
+    vertices: &Vertices,
+    format: &VertexFormat,
+    volume: &BBox,
+) -> Vec<EncodedVertexBuffer> {
+    let mut buffers = vec![if format.quantized_positions {
+        encode_quantized_positions(vertices.get_positions(), volume)
+    } else {
+        encode_float_positions(vertices.get_positions())
+    }];
+
+    if let Some(normals) = vertices.get_normals() {
+        buffers.push(if format.octahedral_normals {
+            encode_octahedral_normals(normals)
+        } else {
+            encode_float_normals(normals)
+        });
+    }
+
+    if format.interleaved && buffers.len() > 1 {
+        vec![interleave(&buffers, vertices.len())]
+    } else {
+        buffers
+    }
+}
+
+/// Returns the matrix mapping the quantized positions, normalized to [0,1] by the GPU, back
+/// into the coordinates of the given volume.
+///
+/// # Arguments
+/// * `volume` - The volume the positions have been quantized in.
+pub fn get_dequantization_matrix(volume: &BBox) -> Mat4 {
+    if volume.is_empty() {
+        return Mat4::identity();
+    }
+
+    translation(volume.get_min()) * scaling(&get_quantization_extent(volume))
+}
+
+/// Returns the given position quantized to 16-bit integers relative to the given volume.
+///
+/// # Arguments
+/// * `p` - The position to quantize.
+/// * `volume` - The volume enclosing the position.
+pub fn quantize_position(p: &Vec3, volume: &BBox) -> [u16; 3] {
+    let extent = get_quantization_extent(volume);
+
+    [0, 1, 2].map(|axis| {
+        let t = (p[axis] - volume.min[axis]) / extent[axis];
+        (t.clamp(0f32, 1f32) * u16::MAX as f32).round() as u16
+    })
+}
+
+/// Returns the octahedral encoding of the given normal as two 16-bit integers, i.e., the
+/// normal is projected onto the octahedron and the lower half is folded over the upper half.
+///
+/// # Arguments
+/// * `n` - The normal to encode.
+pub fn encode_octahedral(n: &Vec3) -> [i16; 2] {
+    let l1 = n.x.abs() + n.y.abs() + n.z.abs();
+    if l1 <= 0f32 {
+        return [0, 0];
+    }
+
+    let (x, y) = (n.x / l1, n.y / l1);
+    let (x, y) = if n.z >= 0f32 {
+        (x, y)
+    } else {
+        (
+            (1f32 - y.abs()) * sign_not_zero(x),
+            (1f32 - x.abs()) * sign_not_zero(y),
+        )
+    };
+
+    [x, y].map(|c| (c.clamp(-1f32, 1f32) * i16::MAX as f32).round() as i16)
+}
+
+/// Returns the extent of the volume used for quantization, where empty axes are replaced by
+/// one to avoid divisions by zero.
+///
+/// # Arguments
+/// * `volume` - The volume used for quantization.
+fn get_quantization_extent(volume: &BBox) -> Vec3 {
+    volume
+        .get_size()
+        .map(|size| if size > 0f32 { size } else { 1f32 })
+}
+
+/// Returns one for non-negative values and minus one otherwise.
+#[inline]
+fn sign_not_zero(x: f32) -> f32 {
+    if x >= 0f32 {
+        1f32
+    } else {
+        -1f32
+    }
+}
+
+/// Encodes the positions as 32-bit floats.
+fn encode_float_positions(positions: &[Point3D]) -> EncodedVertexBuffer {
+    let mut data = Vec::with_capacity(positions.len() * 12);
+    for p in positions.iter() {
+        for c in p.0.iter() {
+            data.extend_from_slice(&c.to_ne_bytes());
+        }
+    }
+
+    single_attribute(data, 12, POSITION_LOCATION, 3, glow::FLOAT, false)
+}
+
+/// Encodes the positions as normalized 16-bit integers, padded to eight bytes per vertex.
+fn encode_quantized_positions(positions: &[Point3D], volume: &BBox) -> EncodedVertexBuffer {
+    let mut data = Vec::with_capacity(positions.len() * 8);
+    for p in positions.iter() {
+        for c in quantize_position(&p.0, volume).iter().chain([&0]) {
+            data.extend_from_slice(&c.to_ne_bytes());
+        }
+    }
+
+    single_attribute(data, 8, POSITION_LOCATION, 3, glow::UNSIGNED_SHORT, true)
+}
+
+/// Encodes the normals as 32-bit floats.
+fn encode_float_normals(normals: &[Normal]) -> EncodedVertexBuffer {
+    let mut data = Vec::with_capacity(normals.len() * 12);
+    for n in normals.iter() {
+        for c in n.0.iter() {
+            data.extend_from_slice(&c.to_ne_bytes());
+        }
+    }
+
+    single_attribute(data, 12, NORMAL_LOCATION, 3, glow::FLOAT, false)
+}
+
+/// Encodes the normals octahedral as two normalized 16-bit integers.
+fn encode_octahedral_normals(normals: &[Normal]) -> EncodedVertexBuffer {
+    let mut data = Vec::with_capacity(normals.len() * 4);
+    for n in normals.iter() {
+        for c in encode_octahedral(&n.0).iter() {
+            data.extend_from_slice(&c.to_ne_bytes());
+        }
+    }
+
+    single_attribute(data, 4, NORMAL_LOCATION, 2, glow::SHORT, true)
+}
+
+/// Returns a vertex buffer with a single attribute.
+fn single_attribute(
+    data: Vec<u8>,
+    stride: i32,
+    location: u32,
+    num_components: i32,
+    data_type: u32,
+    normalized: bool,
+) -> EncodedVertexBuffer {
+    EncodedVertexBuffer {
+        data,
+        stride,
+        attributes: vec![AttributeLayout {
+            location,
+            num_components,
+            data_type,
+            normalized,
+            offset: 0,
+        }],
+    }
+}
+
+/// Combines the given vertex buffers into a single buffer with the attributes of each vertex
+/// stored next to each other.
+///
+/// # Arguments
+/// * `buffers` - The vertex buffers to combine.
+/// * `num_vertices` - The number of vertices of each buffer.
+fn interleave(buffers: &[EncodedVertexBuffer], num_vertices: usize) -> EncodedVertexBuffer {
+    let stride: i32 = buffers.iter().map(|buffer| buffer.stride).sum();
+
+    let mut attributes = Vec::new();
+    let mut offset = 0;
+    for buffer in buffers.iter() {
+        attributes.extend(buffer.attributes.iter().map(|attribute| AttributeLayout {
+            offset: attribute.offset + offset,
+            ..*attribute
+        }));
+        offset += buffer.stride;
+    }
+
+    let mut data = Vec::with_capacity(num_vertices * stride as usize);
+    for vertex in 0..num_vertices {
+        for buffer in buffers.iter() {
+            let size = buffer.stride as usize;
+            data.extend_from_slice(&buffer.data[vertex * size..(vertex + 1) * size]);
+        }
+    }
+
+    EncodedVertexBuffer {
+        data,
+        stride,
+        attributes,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra_glm::{angle, distance, normalize};
+
+    use super::*;
+
+    /// Returns the position decoded from the given 16-bit integers relative to the given volume,
+    /// i.e., the inverse of `quantize_position` as done by the dequantization matrix on the GPU.
+    /// Only used for verifying the encoding, as the GPU decodes the positions itself.
+    ///
+    /// # Arguments
+    /// * `q` - The quantized position.
+    /// * `volume` - The volume the position has been quantized in.
+    fn dequantize_position(q: &[u16; 3], volume: &BBox) -> Vec3 {
+        let extent = get_quantization_extent(volume);
+
+        volume.get_min() + Vec3::from_fn(|axis, _| unpack_unorm16(q[axis]) * extent[axis])
+    }
+
+    /// Returns the normal decoded from the given octahedral encoding, i.e., the inverse of
+    /// `encode_octahedral` as done by `decodeOctahedral` in the vertex shader,
+    /// which this function has to be kept in sync with.
+    ///
+    /// # Arguments
+    /// * `e` - The octahedral encoded normal.
+    fn decode_octahedral(e: &[i16; 2]) -> Vec3 {
+        let (x, y) = (unpack_snorm16(e[0]), unpack_snorm16(e[1]));
+        let z = 1f32 - x.abs() - y.abs();
+        let n = if z >= 0f32 {
+            Vec3::new(x, y, z)
+        } else {
+            Vec3::new(
+                (1f32 - y.abs()) * sign_not_zero(x),
+                (1f32 - x.abs()) * sign_not_zero(y),
+                z,
+            )
+        };
+
+        normalize(&n)
+    }
+
+    /// Returns the given normalized unsigned 16-bit integer mapped to [0,1] like the GPU does.
+    #[inline]
+    fn unpack_unorm16(c: u16) -> f32 {
+        c as f32 / u16::MAX as f32
+    }
+
+    /// Returns the given normalized signed 16-bit integer mapped to [-1,1] like the GPU does.
+    #[inline]
+    fn unpack_snorm16(c: i16) -> f32 {
+        (c as f32 / i16::MAX as f32).max(-1f32)
+    }
+
+    /// Returns the normals of a regular grid on the unit sphere including the poles.
+    fn sphere_normals() -> Vec<Vec3> {
+        let mut normals = vec![Vec3::new(0f32, 0f32, 1f32), Vec3::new(0f32, 0f32, -1f32)];
+        for i in 1..64 {
+            let theta = std::f32::consts::PI * i as f32 / 64f32;
+            for j in 0..128 {
+                let phi = std::f32::consts::PI * j as f32 / 64f32;
+                normals.push(Vec3::new(
+                    theta.sin() * phi.cos(),
+                    theta.sin() * phi.sin(),
+                    theta.cos(),
+                ));
+            }
+        }
+
+        normals
+    }
+
+    #[test]
+    fn test_octahedral_round_trip() {
+        for n in sphere_normals() {
+            let decoded = decode_octahedral(&encode_octahedral(&n));
+            assert!(angle(&n, &decoded) < 1e-3f32, "{:?} {:?}", n, decoded);
+        }
+    }
+
+    #[test]
+    fn test_octahedral_poles() {
+        assert_eq!(encode_octahedral(&Vec3::new(0f32, 0f32, 1f32)), [0, 0]);
+        assert_eq!(decode_octahedral(&[0, 0]), Vec3::new(0f32, 0f32, 1f32));
+
+        let south = encode_octahedral(&Vec3::new(0f32, 0f32, -1f32));
+        assert_eq!(south, [i16::MAX, i16::MAX]);
+        assert_eq!(decode_octahedral(&south), Vec3::new(0f32, 0f32, -1f32));
+    }
+
+    #[test]
+    fn test_octahedral_hemisphere_fold() {
+        // normals slightly above and below the equator are encoded on both sides of the fold
+        for (x, y) in [
+            (1f32, 0f32),
+            (0f32, -1f32),
+            (-0.6f32, 0.8f32),
+            (0.6f32, -0.8f32),
+        ] {
+            for z in [1e-3f32, -1e-3f32, 0.5f32, -0.5f32] {
+                let n = normalize(&Vec3::new(x, y, z));
+                let e = encode_octahedral(&n);
+                let decoded = decode_octahedral(&e);
+
+                assert!(angle(&n, &decoded) < 1e-3f32, "{:?} {:?}", n, decoded);
+                assert_eq!(decoded.z >= 0f32, z >= 0f32, "{:?} {:?}", n, decoded);
+            }
+        }
+
+        // the equator is on the edge of the octahedron for both hemispheres
+        let e = encode_octahedral(&Vec3::new(1f32, 0f32, 0f32));
+        assert_eq!(e, [i16::MAX, 0]);
+    }
+
+    #[test]
+    fn test_quantization_round_trip() {
+        let mut volume = BBox::new();
+        volume.extend_pos(&Vec3::new(-2f32, 10f32, 0.5f32));
+        volume.extend_pos(&Vec3::new(6f32, 12f32, 0.5f32));
+
+        // half of the quantization step on each axis, where the empty z-axis has no error
+        let size = volume.get_size();
+        let max_error = size.x.max(size.y) / u16::MAX as f32 * 0.5f32 * 3f32.sqrt();
+
+        assert_eq!(
+            dequantize_position(&quantize_position(volume.get_min(), &volume), &volume),
+            *volume.get_min()
+        );
+        assert_eq!(
+            dequantize_position(&quantize_position(volume.get_max(), &volume), &volume),
+            *volume.get_max()
+        );
+
+        for i in 0..=100 {
+            let t = i as f32 / 100f32;
+            let p = Vec3::new(-2f32 + 8f32 * t, 12f32 - 2f32 * t * t, 0.5f32);
+            let decoded = dequantize_position(&quantize_position(&p, &volume), &volume);
+
+            assert!(distance(&p, &decoded) <= max_error, "{:?} {:?}", p, decoded);
+        }
+    }
+
+    #[test]
+    fn test_dequantization_matrix() {
+        let mut volume = BBox::new();
+        volume.extend_pos(&Vec3::new(-1f32, 0f32, 3f32));
+        volume.extend_pos(&Vec3::new(1f32, 4f32, 5f32));
+
+        let mat = get_dequantization_matrix(&volume);
+        for q in [
+            [0, 0, 0],
+            [u16::MAX, u16::MAX, u16::MAX],
+            [1234, 40000, 65000],
+        ] {
+            let t = Vec3::from_fn(|axis, _| unpack_unorm16(q[axis]));
+            let p = (mat * t.push(1f32)).xyz();
+
+            assert!(distance(&p, &dequantize_position(&q, &volume)) < 1e-5f32);
+        }
+
+        assert_eq!(get_dequantization_matrix(&BBox::new()), Mat4::identity());
+    }
+}
@@ -0,0 +1,132 @@
+use std::{
+    fs,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver, RecvError, TryRecvError},
+        Arc,
+    },
+    thread,
+    time::{Duration, SystemTime},
+};
+
+use anyhow::Result;
+use log::{debug, info};
+
+use super::{
+    load::{load_and_merge_cad_data, LoadSettings},
+    loaded_data::LoadedCADData,
+    lods::create_shape_lods,
+};
+
+/// The interval in which the input files are checked for modifications.
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Loads the input files on a separate thread. If requested, the input files are watched
+/// afterwards and loaded again whenever they are modified.
+pub struct BackgroundLoader {
+    receiver: Receiver<Result<LoadedCADData>>,
+    cancel: Arc<AtomicBool>,
+}
+
+impl BackgroundLoader {
+    /// Spawns the loader thread for the given settings.
+    ///
+    /// # Arguments
+    /// * `settings` - The settings defining the files to load.
+    /// * `watch` - If true, the files are loaded again whenever they are modified.
+    pub fn spawn(settings: LoadSettings, watch: bool) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        let cancel = Arc::new(AtomicBool::new(false));
+
+        let thread_cancel = cancel.clone();
+        thread::spawn(move || {
+            let cancel = thread_cancel;
+            let mut last_modified = get_modification_times(&settings.file_paths);
+
+            let result = load_scene(&settings, &cancel);
+            if cancel.load(Ordering::Relaxed) || sender.send(result).is_err() || !watch {
+                return;
+            }
+
+            info!("Watch input files for modifications");
+            while !cancel.load(Ordering::Relaxed) {
+                thread::sleep(POLL_INTERVAL);
+
+                let modified = get_modification_times(&settings.file_paths);
+                if modified == last_modified {
+                    continue;
+                }
+
+                // wait until the files are not written anymore
+                thread::sleep(POLL_INTERVAL);
+                let stable_modified = get_modification_times(&settings.file_paths);
+                if stable_modified != modified {
+                    debug!("Input files are still being written...");
+                    continue;
+                }
+                last_modified = stable_modified;
+
+                info!("Input files changed, reload...");
+                let result = load_scene(&settings, &cancel);
+                if cancel.load(Ordering::Relaxed) || sender.send(result).is_err() {
+                    break;
+                }
+            }
+
+            debug!("Stop watching input files");
+        });
+
+        Self { receiver, cancel }
+    }
+
+    /// Returns the next loading result if available.
+    pub fn try_recv(&self) -> Result<Result<LoadedCADData>, TryRecvError> {
+        self.receiver.try_recv()
+    }
+
+    /// Blocks until the next loading result is available. Fails if the loader thread has
+    /// stopped without providing any further result.
+    pub fn recv(&self) -> Result<Result<LoadedCADData>, RecvError> {
+        self.receiver.recv()
+    }
+
+    /// Requests the loader thread to stop. A file that is currently being read is finished, but
+    /// its result is discarded.
+    pub fn cancel(&self) {
+        self.cancel.store(true, Ordering::Relaxed);
+    }
+}
+
+impl Drop for BackgroundLoader {
+    fn drop(&mut self) {
+        self.cancel();
+    }
+}
+
+/// Loads the CAD data of the given settings and creates the levels of detail of its shapes.
+///
+/// # Arguments
+/// * `settings` - The settings defining the files to load.
+/// * `cancel` - Flag for cancelling the loading.
+fn load_scene(settings: &LoadSettings, cancel: &AtomicBool) -> Result<LoadedCADData> {
+    let cad_data = load_and_merge_cad_data(settings, cancel)?;
+    let shape_lods = create_shape_lods(&cad_data, settings.num_lod_levels, cancel)?;
+
+    Ok(LoadedCADData::new(cad_data, shape_lods))
+}
+
+/// Returns the modification times of the given files. Files whose modification time cannot be
+/// determined, e.g. since they are just being replaced, are represented by None.
+///
+/// # Arguments
+/// * `file_paths` - The paths of the files.
+fn get_modification_times(file_paths: &[PathBuf]) -> Vec<Option<SystemTime>> {
+    file_paths
+        .iter()
+        .map(|p| match fs::metadata(p) {
+            Ok(metadata) => metadata.modified().ok(),
+            Err(_) => None,
+        })
+        .collect()
+}
@@ -0,0 +1,304 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use anyhow::{bail, Result};
+use cad_import::{
+    loader::Manager,
+    structure::{CADData, Node},
+};
+use log::{debug, info, warn};
+use nalgebra_glm::{translation, Vec3};
+
+use super::sniffing::sniff_format;
+
+/// The settings defining which files are loaded and how they are merged.
+#[derive(Debug, Clone)]
+pub struct LoadSettings {
+    /// The paths of the files to load.
+    pub file_paths: Vec<PathBuf>,
+
+    /// The offsets for the files. Files without offset are not translated.
+    pub offsets: Vec<Vec3>,
+
+    /// The optional mime type that is forced for all files.
+    pub mime_type_override: Option<String>,
+
+    /// The number of coarser levels of detail created for each mesh.
+    pub num_lod_levels: usize,
+}
+
+/// Returns all mime types for which a loader is registered.
+///
+/// # Arguments
+/// * `manager` - The loader manager.
+fn get_supported_mime_types(manager: &Manager) -> Vec<String> {
+    let mut mime_types: Vec<String> = manager
+        .get_loader_list()
+        .iter()
+        .flat_map(|loader| loader.get_mime_types())
+        .collect();
+
+    mime_types.sort();
+    mime_types.dedup();
+
+    mime_types
+}
+
+/// Tries to find the candidate mime types for the given file. If a mime type override is
+/// given, only the override is returned. Otherwise, the mime types are derived from the file
+/// extension. The file content is only sniffed if the extension is unknown or ambiguous, where
+/// the detected format is tried first.
+///
+/// # Arguments
+/// * `manager` - The loader manager.
+/// * `input_file` - The input file whose mime types will be determined.
+/// * `mime_type_override` - The optional mime type that is forced for the input file.
+fn determine_mime_types(
+    manager: &Manager,
+    input_file: &Path,
+    mime_type_override: Option<&str>,
+) -> Result<Vec<String>> {
+    if let Some(mime_type) = mime_type_override {
+        if manager.get_loader_by_mime_type(mime_type).is_none() {
+            bail!(
+                "No loader for mime type '{}', supported mime types are {:?}",
+                mime_type,
+                get_supported_mime_types(manager)
+            );
+        }
+
+        return Ok(vec![mime_type.to_owned()]);
+    }
+
+    // determine the mime types based on the file extension
+    let mut mime_types = match input_file.extension() {
+        Some(ext) => match ext.to_str() {
+            Some(ext) => manager.get_mime_types_for_extension(ext),
+            None => Vec::new(),
+        },
+        None => Vec::new(),
+    };
+    mime_types.retain(|mime_type| manager.get_loader_by_mime_type(mime_type).is_some());
+    mime_types.dedup();
+
+    // a unique mime type for the extension doesn't need any content detection
+    if mime_types.len() == 1 {
+        return Ok(mime_types);
+    }
+
+    // the format detected by the file content either selects one of the ambiguous candidates
+    // or is the only candidate for an unknown extension
+    match sniff_format(input_file)? {
+        Some(format) => {
+            debug!("Detected {} content in {:?}", format.name, input_file);
+
+            if manager.get_loader_by_mime_type(format.mime_type).is_some() {
+                if !mime_types.is_empty() {
+                    info!(
+                        "Content of {:?} looks like {}, trying it first",
+                        input_file, format.name
+                    );
+                }
+
+                mime_types.retain(|mime_type| mime_type != format.mime_type);
+                mime_types.insert(0, format.mime_type.to_owned());
+            } else if mime_types.is_empty() {
+                bail!(
+                    "Detected {} content in {:?}, but no loader supports '{}'",
+                    format.name,
+                    input_file,
+                    format.mime_type
+                );
+            }
+        }
+        None => {
+            if mime_types.is_empty() {
+                bail!(
+                    "Cannot determine the format of {:?}, neither by extension nor by content",
+                    input_file
+                );
+            }
+        }
+    }
+
+    Ok(mime_types)
+}
+
+/// Tries to load the cad data from the given path. All candidate loaders are tried in turn and
+/// loading only fails if all of them have failed.
+///
+/// # Arguments
+/// * `file_path` - The path to load the CAD data from.
+/// * `mime_type_override` - The optional mime type that is forced for the file.
+fn load_cad_data(file_path: &Path, mime_type_override: Option<&str>) -> Result<CADData> {
+    let manager = Manager::new();
+
+    let mime_types = determine_mime_types(&manager, file_path, mime_type_override)?;
+
+    let mut failures = Vec::with_capacity(mime_types.len());
+    for mime_type in mime_types.iter() {
+        let loader = match manager.get_loader_by_mime_type(mime_type.as_str()) {
+            Some(loader) => loader,
+            None => {
+                failures.push(format!("{}: no loader available", mime_type));
+                continue;
+            }
+        };
+
+        debug!(
+            "Read {:?} as '{}' using loader '{}'",
+            file_path,
+            mime_type,
+            loader.get_name()
+        );
+        match loader.read_file(file_path, mime_type) {
+            Ok(cad_data) => return Ok(cad_data),
+            Err(err) => {
+                warn!(
+                    "Loader '{}' failed reading {:?} as '{}': {}",
+                    loader.get_name(),
+                    file_path,
+                    mime_type,
+                    err
+                );
+                failures.push(format!(
+                    "{} (loader '{}'): {}",
+                    mime_type,
+                    loader.get_name(),
+                    err
+                ));
+            }
+        }
+    }
+
+    bail!(
+        "Failed reading input file {:?}, all {} candidate(s) failed:\n  - {}",
+        file_path,
+        failures.len(),
+        failures.join("\n  - ")
+    );
+}
+
+/// Returns a copy of the given node and its whole subtree. The shapes are shared with the
+/// given node.
+///
+/// # Arguments
+/// * `node` - The node to copy.
+fn copy_node(node: &Node) -> Node {
+    let mut result = Node::new(node.get_label().to_owned());
+
+    if let Some(t) = node.get_transform() {
+        result.set_transform(t);
+    }
+
+    for shape in node.get_shapes() {
+        result.attach_shape(shape.clone());
+    }
+
+    for child in node.get_children() {
+        result.add_child(copy_node(child));
+    }
+
+    result
+}
+
+/// Loads all files of the given settings and merges them under a synthetic root node. Each file
+/// gets its own node, which is translated by the corresponding offset if given.
+///
+/// # Arguments
+/// * `settings` - The settings defining the files to load.
+/// * `cancel` - Flag for cancelling the loading. It is checked before each file.
+pub fn load_and_merge_cad_data(settings: &LoadSettings, cancel: &AtomicBool) -> Result<CADData> {
+    let file_paths = &settings.file_paths;
+    let offsets = &settings.offsets;
+    let mime_type_override = settings.mime_type_override.as_deref();
+
+    let mut root_node = Node::new("Scene".to_owned());
+
+    for (i, file_path) in file_paths.iter().enumerate() {
+        if cancel.load(Ordering::Relaxed) {
+            bail!("Loading has been cancelled");
+        }
+
+        info!("Load '{}'...", file_path.to_string_lossy());
+        let cad_data = load_cad_data(file_path, mime_type_override)?;
+        info!("Load '{}'...DONE", file_path.to_string_lossy());
+
+        // a single file without offset doesn't need a synthetic root
+        if file_paths.len() == 1 && offsets.is_empty() {
+            return Ok(cad_data);
+        }
+
+        let label = match file_path.file_name() {
+            Some(file_name) => file_name.to_string_lossy().to_string(),
+            None => file_path.to_string_lossy().to_string(),
+        };
+
+        let mut file_node = Node::new(label);
+        if let Some(offset) = offsets.get(i) {
+            file_node.set_transform(translation(offset));
+        }
+
+        file_node.add_child(copy_node(cad_data.get_root_node()));
+        root_node.add_child(file_node);
+    }
+
+    Ok(CADData::new(root_node))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Writes the given content into a file with the given name in the temporary directory.
+    fn write_temp_file(file_name: &str, content: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("cad_viewer_{}", file_name));
+        std::fs::write(&path, content).unwrap();
+
+        path
+    }
+
+    #[test]
+    fn test_known_extension_is_not_sniffed() {
+        let manager = Manager::new();
+
+        // the content is not sniffed, i.e., neither the glTF content nor a missing file matter
+        let path = write_temp_file("sniff_test.off", "{ \"asset\": {} }");
+        let mime_types = determine_mime_types(&manager, &path, None).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(mime_types, vec!["model/vnd.off".to_owned()]);
+
+        let path = Path::new("does_not_exist.off");
+        let mime_types = determine_mime_types(&manager, path, None).unwrap();
+        assert_eq!(mime_types, vec!["model/vnd.off".to_owned()]);
+    }
+
+    #[test]
+    fn test_unknown_extension_is_sniffed() {
+        let manager = Manager::new();
+
+        let content = std::fs::read_to_string("examples/cube.off").unwrap();
+        let path = write_temp_file("sniff_test.dat", &content);
+        let mime_types = determine_mime_types(&manager, &path, None);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(mime_types.unwrap(), vec!["model/vnd.off".to_owned()]);
+
+        let path = write_temp_file("sniff_test_unknown", "unknown content");
+        let mime_types = determine_mime_types(&manager, &path, None);
+        std::fs::remove_file(&path).unwrap();
+        assert!(mime_types.is_err());
+    }
+
+    #[test]
+    fn test_mime_type_override() {
+        let manager = Manager::new();
+        let path = Path::new("cube.dat");
+
+        let mime_types = determine_mime_types(&manager, path, Some("model/vnd.off")).unwrap();
+        assert_eq!(mime_types, vec!["model/vnd.off".to_owned()]);
+
+        assert!(determine_mime_types(&manager, path, Some("model/unknown")).is_err());
+    }
+}
@@ -0,0 +1,38 @@
+use std::collections::HashMap;
+
+use cad_import::{
+    structure::{CADData, Mesh},
+    ID,
+};
+
+/// CAD data that has been loaded on a loader thread and is handed over to the render thread
+/// together with the levels of detail of its shapes.
+pub struct LoadedCADData {
+    cad_data: CADData,
+    shape_lods: HashMap<ID, Vec<Vec<Mesh>>>,
+}
+
+// SAFETY: CADData is not Send as it uses Rc internally for sharing shapes, meshes and materials.
+// However, all these Rc references point into the data itself and the loader thread keeps no
+// reference into the data after handing it over. Hence, the whole data is moved at once and its
+// reference counts are only ever touched by the thread that currently owns it.
+unsafe impl Send for LoadedCADData {}
+
+impl LoadedCADData {
+    /// Wraps the given freshly loaded CAD data.
+    ///
+    /// # Arguments
+    /// * `cad_data` - The CAD data to wrap. No reference into the data must be kept.
+    /// * `shape_lods` - The levels of detail for the parts of the shapes by their shape ID.
+    pub fn new(cad_data: CADData, shape_lods: HashMap<ID, Vec<Vec<Mesh>>>) -> Self {
+        Self {
+            cad_data,
+            shape_lods,
+        }
+    }
+
+    /// Returns the wrapped CAD data and the levels of detail of its shapes.
+    pub fn into_parts(self) -> (CADData, HashMap<ID, Vec<Vec<Mesh>>>) {
+        (self.cad_data, self.shape_lods)
+    }
+}
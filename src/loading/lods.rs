@@ -0,0 +1,68 @@
+use std::{
+    collections::HashMap,
+    sync::atomic::{AtomicBool, Ordering},
+    time::Instant,
+};
+
+use anyhow::{bail, Result};
+use cad_import::{
+    structure::{CADData, Mesh, Node},
+    ID,
+};
+use log::{debug, info};
+
+use crate::geometry::create_lods;
+
+/// Creates the levels of detail for the parts of all shapes of the given CAD data. Each shape
+/// is simplified only once, regardless of how often it is instantiated. Returns the simplified
+/// meshes of each part, starting with level one, by the ID of their shape.
+///
+/// # Arguments
+/// * `cad_data` - The CAD data whose shapes are simplified.
+/// * `num_lod_levels` - The maximal number of coarser levels of detail created for each mesh.
+/// * `cancel` - Flag for cancelling the creation. It is checked before each shape.
+pub fn create_shape_lods(
+    cad_data: &CADData,
+    num_lod_levels: usize,
+    cancel: &AtomicBool,
+) -> Result<HashMap<ID, Vec<Vec<Mesh>>>> {
+    let mut shape_lods = HashMap::new();
+    if num_lod_levels == 0 {
+        return Ok(shape_lods);
+    }
+
+    info!("Create levels of detail...");
+    let start = Instant::now();
+
+    let mut pending: Vec<&Node> = vec![cad_data.get_root_node()];
+    while let Some(node) = pending.pop() {
+        for shape in node.get_shapes() {
+            if cancel.load(Ordering::Relaxed) {
+                bail!("Loading has been cancelled");
+            }
+
+            if shape_lods.contains_key(&shape.get_id()) {
+                continue;
+            }
+
+            let part_lods = shape
+                .get_parts()
+                .iter()
+                .map(|part| create_lods(part.get_mesh().as_ref(), num_lod_levels))
+                .collect::<Result<Vec<_>>>()?;
+            shape_lods.insert(shape.get_id(), part_lods);
+        }
+
+        pending.extend(node.get_children().iter());
+    }
+
+    let num_lods: usize = shape_lods.values().flatten().map(|lods| lods.len()).sum();
+    debug!(
+        "Created {} levels of detail for {} shapes",
+        num_lods,
+        shape_lods.len()
+    );
+    info!("Create levels of detail...DONE ({:?})", start.elapsed());
+
+    Ok(shape_lods)
+}
@@ -0,0 +1,9 @@
+mod background;
+mod load;
+mod loaded_data;
+mod lods;
+mod sniffing;
+
+pub use background::BackgroundLoader;
+pub use load::LoadSettings;
+pub use loaded_data::LoadedCADData;
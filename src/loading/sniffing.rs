@@ -0,0 +1,236 @@
+use std::{
+    fs::File,
+    io::{Read, Seek, SeekFrom},
+    path::Path,
+};
+
+use anyhow::{Context, Result};
+
+/// The number of bytes read from the beginning of a file for detecting its format.
+const HEADER_SIZE: usize = 1024;
+
+/// The magic number at the beginning of a binary glTF file.
+const GLB_MAGIC: &[u8] = b"glTF";
+
+/// The size of the header of a binary STL file, i.e., 80 bytes comment and 4 bytes triangle count.
+const STL_HEADER_SIZE: u64 = 84;
+
+/// The size of a single triangle record in a binary STL file.
+const STL_TRIANGLE_SIZE: u64 = 50;
+
+/// A file format detected by its content.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SniffedFormat {
+    /// The human readable name of the format.
+    pub name: &'static str,
+
+    /// The mime type of the format.
+    pub mime_type: &'static str,
+}
+
+impl SniffedFormat {
+    const OFF: Self = Self::new("OFF", "model/vnd.off");
+    const GLTF_JSON: Self = Self::new("glTF (JSON)", "model/gltf+json");
+    const GLTF_BINARY: Self = Self::new("glTF (binary)", "model/gltf-binary");
+    const PLY: Self = Self::new("PLY", "model/x-ply");
+    const STL_ASCII: Self = Self::new("STL (ASCII)", "model/stl");
+    const STL_BINARY: Self = Self::new("STL (binary)", "model/stl");
+
+    const fn new(name: &'static str, mime_type: &'static str) -> Self {
+        Self { name, mime_type }
+    }
+}
+
+/// Tries to detect the format of the given file based on its content, i.e., based on magic
+/// bytes and header data. Returns None if the format is unknown.
+///
+/// # Arguments
+/// * `file_path` - The path to the file whose format will be detected.
+pub fn sniff_format(file_path: &Path) -> Result<Option<SniffedFormat>> {
+    let mut file =
+        File::open(file_path).context(format!("Failed to open input file {:?}", file_path))?;
+
+    let file_size = file
+        .seek(SeekFrom::End(0))
+        .context(format!("Failed to determine size of {:?}", file_path))?;
+    file.seek(SeekFrom::Start(0))
+        .context(format!("Failed to read {:?}", file_path))?;
+
+    let mut header = Vec::with_capacity(HEADER_SIZE);
+    file.take(HEADER_SIZE as u64)
+        .read_to_end(&mut header)
+        .context(format!("Failed to read header of {:?}", file_path))?;
+
+    Ok(sniff_header(&header, file_size))
+}
+
+/// Tries to detect the format based on the first bytes of the file.
+///
+/// # Arguments
+/// * `header` - The first bytes of the file.
+/// * `file_size` - The total size of the file in bytes.
+fn sniff_header(header: &[u8], file_size: u64) -> Option<SniffedFormat> {
+    if header.starts_with(GLB_MAGIC) {
+        return Some(SniffedFormat::GLTF_BINARY);
+    }
+
+    // binary STL files may start with 'solid' as well, so check the size first
+    if is_binary_stl(header, file_size) {
+        return Some(SniffedFormat::STL_BINARY);
+    }
+
+    let text = String::from_utf8_lossy(header);
+    let text = text.trim_start_matches('\u{feff}').trim_start();
+
+    if text.starts_with('{') {
+        return Some(SniffedFormat::GLTF_JSON);
+    }
+
+    let first_line = text.lines().next().unwrap_or("").trim();
+
+    if first_line == "ply" {
+        return Some(SniffedFormat::PLY);
+    }
+
+    if first_line.starts_with("solid") && text.contains("facet") {
+        return Some(SniffedFormat::STL_ASCII);
+    }
+
+    if is_off_keyword(first_line) {
+        return Some(SniffedFormat::OFF);
+    }
+
+    None
+}
+
+/// Returns true if the header and the file size match the layout of a binary STL file.
+///
+/// # Arguments
+/// * `header` - The first bytes of the file.
+/// * `file_size` - The total size of the file in bytes.
+fn is_binary_stl(header: &[u8], file_size: u64) -> bool {
+    if header.len() < STL_HEADER_SIZE as usize {
+        return false;
+    }
+
+    let count_bytes: [u8; 4] = header[80..84].try_into().unwrap();
+    let num_triangles = u32::from_le_bytes(count_bytes) as u64;
+
+    num_triangles > 0 && STL_HEADER_SIZE + num_triangles * STL_TRIANGLE_SIZE == file_size
+}
+
+/// Returns true if the given first line is an OFF keyword, e.g. 'OFF', 'COFF' or 'NOFF'. The
+/// counts may follow the keyword on the same line.
+///
+/// # Arguments
+/// * `first_line` - The first line of the file.
+fn is_off_keyword(first_line: &str) -> bool {
+    let keyword = first_line.split_whitespace().next().unwrap_or("");
+
+    match keyword.strip_suffix("OFF") {
+        Some(prefix) => prefix
+            .chars()
+            .all(|c| matches!(c, 'S' | 'T' | 'C' | 'N' | '4' | 'n')),
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns the content of a binary STL file with the given header comment and number of
+    /// triangles, where all triangles are zero.
+    fn binary_stl(comment: &[u8], num_triangles: u32) -> Vec<u8> {
+        let mut content = comment.to_vec();
+        content.resize(80, b' ');
+        content.extend_from_slice(&num_triangles.to_le_bytes());
+        content.resize(
+            (STL_HEADER_SIZE + num_triangles as u64 * STL_TRIANGLE_SIZE) as usize,
+            0,
+        );
+
+        content
+    }
+
+    /// Sniffs the header of the given file content.
+    fn sniff(content: &[u8]) -> Option<SniffedFormat> {
+        let header = &content[..content.len().min(HEADER_SIZE)];
+
+        sniff_header(header, content.len() as u64)
+    }
+
+    #[test]
+    fn test_sniff_glb() {
+        let mut content = b"glTF".to_vec();
+        content.extend_from_slice(&2u32.to_le_bytes());
+        content.extend_from_slice(&12u32.to_le_bytes());
+
+        assert_eq!(sniff(&content), Some(SniffedFormat::GLTF_BINARY));
+        assert_eq!(sniff(b"gltf"), None);
+    }
+
+    #[test]
+    fn test_sniff_gltf_json() {
+        let content = b"{\n  \"asset\": { \"version\": \"2.0\" }\n}";
+        assert_eq!(sniff(content), Some(SniffedFormat::GLTF_JSON));
+
+        // leading whitespace and a byte order mark are skipped
+        let content = "\u{feff}  \r\n{ \"asset\": {} }".as_bytes();
+        assert_eq!(sniff(content), Some(SniffedFormat::GLTF_JSON));
+    }
+
+    #[test]
+    fn test_sniff_ply() {
+        let content = b"ply\nformat ascii 1.0\nelement vertex 8\nend_header\n";
+        assert_eq!(sniff(content), Some(SniffedFormat::PLY));
+
+        let content = b"ply\r\nformat binary_little_endian 1.0\r\nend_header\r\n";
+        assert_eq!(sniff(content), Some(SniffedFormat::PLY));
+
+        assert_eq!(sniff(b"plyfile\n"), None);
+    }
+
+    #[test]
+    fn test_sniff_ascii_stl() {
+        let content = b"solid cube\n  facet normal 0 0 1\n    outer loop\n";
+        assert_eq!(sniff(content), Some(SniffedFormat::STL_ASCII));
+
+        // 'solid' without any facet is not an STL file
+        assert_eq!(sniff(b"solid cube\nendsolid cube\n"), None);
+    }
+
+    #[test]
+    fn test_sniff_binary_stl() {
+        let content = binary_stl(b"binary STL", 2);
+        assert_eq!(content.len(), 184);
+        assert_eq!(sniff(&content), Some(SniffedFormat::STL_BINARY));
+
+        // the size decides, even if the header comment looks like an ASCII STL file
+        let content = binary_stl(b"solid exported facet", 3);
+        assert_eq!(sniff(&content), Some(SniffedFormat::STL_BINARY));
+
+        // the triangle count must match the file size
+        let mut content = binary_stl(b"binary STL", 2);
+        content.push(0);
+        assert_eq!(sniff(&content), None);
+
+        assert_eq!(sniff(&binary_stl(b"binary STL", 0)), None);
+    }
+
+    #[test]
+    fn test_sniff_off() {
+        assert_eq!(sniff(b"OFF\n8 6 12\n"), Some(SniffedFormat::OFF));
+        assert_eq!(sniff(b"COFF 8 6 12\n"), Some(SniffedFormat::OFF));
+        assert_eq!(sniff(b"NOFF\n"), Some(SniffedFormat::OFF));
+        assert_eq!(sniff(b"XOFF\n"), None);
+    }
+
+    #[test]
+    fn test_sniff_unknown() {
+        assert_eq!(sniff(b""), None);
+        assert_eq!(sniff(b"unknown content"), None);
+        assert_eq!(sniff(&[0u8, 1, 2, 3, 255, 254]), None);
+        assert_eq!(sniff(&[0u8; 100]), None);
+    }
+}
@@ -1,96 +1,114 @@
-use anyhow::{bail, Context, Result};
+use anyhow::{Context, Result};
 use args::Arguments;
-use cad_import::{loader::Manager, structure::CADData};
-use log::{error, info, LevelFilter};
-use std::{fs::File, path::Path, process::ExitCode};
-use viewer::Viewer;
+use gpu_data::{UploadBudget, VertexFormat};
+use loading::{BackgroundLoader, LoadSettings};
+use log::{error, info, warn};
+use nalgebra_glm::Vec3;
+use std::{process::ExitCode, time::Duration};
+use viewer::{ClipPlanes, DirectionalLight, HeadlessViewer, Lighting, Viewer};
 
-use crate::viewer::Renderer;
+use crate::viewer::{Renderer, RendererOptions};
 
 mod args;
+mod geometry;
 mod gpu_data;
+mod loading;
 mod viewer;
 
 /// Initializes the program logging
-fn initialize_logging() {
-    simple_logging::log_to(std::io::stdout(), LevelFilter::Debug);
-}
-
-/// Prints the usage of the program
-fn print_usage() {
-    println!("cad_viewer <INPUT>\n");
-    println!("INPUT: The path to the input file");
-}
-
-/// Tries to return the extension from the given file path
 ///
 /// # Arguments
-/// * `file_path` - The filepath from which the extension will be extracted.
-fn get_extension(file_path: &Path) -> Result<String> {
-    match file_path.extension() {
-        Some(ext) => {
-            let ext: String = ext.to_string_lossy().to_string();
-            Ok(ext)
-        }
-        None => {
-            bail!("Input file {:?} has no extension", file_path);
-        }
+/// * `args` - The program arguments defining the log level and the log destination.
+fn initialize_logging(args: &Arguments) -> Result<()> {
+    match &args.log_file {
+        Some(log_file) => simple_logging::log_to_file(log_file, args.log_level)
+            .context(format!("Failed to open log file {:?}", log_file))?,
+        None => simple_logging::log_to(std::io::stdout(), args.log_level),
     }
-}
 
-/// Tries to find the mime types for the given file based on the file extension.
-///
-/// # Arguments
-/// * `input_file` - The input file whose extension will be used
-fn determine_mime_types(manager: &Manager, input_file: &Path) -> Result<Vec<String>> {
-    match input_file.extension() {
-        Some(ext) => match ext.to_str() {
-            Some(ext) => Ok(manager.get_mime_types_for_extension(ext)),
-            None => {
-                bail!("Input file has invalid extension");
-            }
-        },
-        None => {
-            bail!("Input file has no extension");
-        }
-    }
+    Ok(())
 }
 
-/// Tries to load the cad data from the given path
-///
-/// # Arguments
-/// * `file_path` - The path to load the CAD data from.
-fn load_cad_data(file_path: &Path) -> Result<CADData> {
-    let manager = Manager::new();
+/// The central entry point for starting the program
+fn run_program(args: Arguments) -> Result<()> {
+    // start loading the cad data in the background
+    let settings = LoadSettings {
+        file_paths: args.input_files,
+        offsets: args.offsets,
+        mime_type_override: args.mime_type,
+        num_lod_levels: args.lod_levels,
+    };
 
-    let mime_types = determine_mime_types(&manager, file_path)?;
+    let watch = args.watch && args.snapshot.is_none();
+    if args.watch && !watch {
+        warn!("Watching the input files is not supported for snapshots");
+    }
 
-    for mime_type in mime_types.iter() {
-        match manager.get_loader_by_mime_type(mime_type.as_str()) {
-            Some(loader) => {
-                let cad_data = loader
-                    .read_file(file_path, mime_type)
-                    .context(format!("Failed reading input file {:?}", file_path))?;
+    let loader = BackgroundLoader::spawn(settings, watch);
 
-                return Ok(cad_data);
+    // the clip planes file is created on saving if it doesn't exist yet and an invalid file
+    // only loses the planes instead of aborting the viewer
+    let clip_planes = match &args.clip_planes {
+        Some(path) if path.exists() => match ClipPlanes::load(path) {
+            Ok(clip_planes) => clip_planes.get_planes().to_vec(),
+            Err(err) => {
+                error!("Failed loading the clip planes, start without: {:#}", err);
+                Vec::new()
             }
-            None => {}
-        }
-    }
+        },
+        _ => Vec::new(),
+    };
 
-    bail!("Cannot find loader for the input file {:?}", file_path);
-}
+    let options = RendererOptions {
+        background_color: args.background,
+        initial_view: args.view,
+        upload_budget: UploadBudget {
+            max_time: Duration::from_millis(args.upload_time_budget),
+            // huge budgets saturate, i.e., they are unlimited
+            max_bytes: args.upload_byte_budget.saturating_mul(1024 * 1024),
+        },
+        merge_small_meshes: args.merge_small_meshes,
+        vertex_format: VertexFormat {
+            interleaved: args.interleave_vertices,
+            quantized_positions: args.quantize_positions,
+            octahedral_normals: args.octahedral_normals,
+        },
+        lighting: Lighting {
+            headlight: if args.no_headlight {
+                None
+            } else {
+                Lighting::default().headlight
+            },
+            lights: args
+                .lights
+                .iter()
+                .map(|direction| DirectionalLight {
+                    direction: *direction,
+                    color: Vec3::new(1.0, 1.0, 1.0),
+                })
+                .collect(),
+        },
+        transparency: args.transparency,
+        display_mode: args.display_mode,
+        edge_color: args.edge_color,
+        crease_angle: args.crease_angle,
+        clip_planes,
+        clip_planes_file: args.clip_planes,
+        cap_color: args.cap_color,
+        ..RendererOptions::default()
+    };
+    let renderer = Renderer::new(loader, options);
 
-/// The central entry point for starting the program
-fn run_program(args: Arguments) -> Result<()> {
-    // load cad data
-    info!("Load '{}'...", args.input_file.to_string_lossy());
-    let cad_data = load_cad_data(&args.input_file)?;
-    info!("Load '{}'...DONE", args.input_file.to_string_lossy());
+    let (width, height) = args.size;
+    if let Some(snapshot_file) = &args.snapshot {
+        let viewer = HeadlessViewer::new(renderer, width, height)
+            .context("Failed initializing the headless viewer")?;
 
-    let renderer = Renderer::new(cad_data);
-    let viewer =
-        Viewer::new("Simple CAD Viewer", renderer).context("Failed initializing the viewer")?;
+        return viewer.render_snapshot(snapshot_file);
+    }
+
+    let viewer = Viewer::new("Simple CAD Viewer", renderer, width, height)
+        .context("Failed initializing the viewer")?;
 
     info!("Start viewer...");
     viewer.run()?;
@@ -99,16 +117,19 @@ fn run_program(args: Arguments) -> Result<()> {
 }
 
 fn main() -> ExitCode {
-    initialize_logging();
     let args = match args::Arguments::parse_args() {
         Ok(args) => args,
         Err(err) => {
-            print_usage();
-            error!("Failed to parse program arguments: {}", err);
-            return ExitCode::FAILURE;
+            // prints the usage, help or version and determines the exit code
+            err.exit();
         }
     };
 
+    if let Err(err) = initialize_logging(&args) {
+        eprintln!("Failed to initialize logging: {:#}", err);
+        return ExitCode::FAILURE;
+    }
+
     args.print_to_log();
 
     match run_program(args) {
@@ -1,10 +1,97 @@
+use std::{fmt::Display, str::FromStr};
+
+use anyhow::bail;
 use glm::mat4_to_mat3;
 use glutin::event::MouseButton;
 
-use super::{bbox::BBox, camera_data::CameraData};
+use crate::geometry::BBox;
+
+use super::camera_data::CameraData;
 
 use nalgebra_glm as glm;
 
+/// The predefined views from which the camera can look onto the scene.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CameraView {
+    Front,
+    Back,
+    Left,
+    Right,
+    Top,
+    Bottom,
+    Iso,
+}
+
+impl CameraView {
+    /// All available camera views.
+    pub const ALL: [CameraView; 7] = [
+        CameraView::Front,
+        CameraView::Back,
+        CameraView::Left,
+        CameraView::Right,
+        CameraView::Top,
+        CameraView::Bottom,
+        CameraView::Iso,
+    ];
+
+    /// Returns the name of the view.
+    pub fn name(&self) -> &'static str {
+        match self {
+            CameraView::Front => "front",
+            CameraView::Back => "back",
+            CameraView::Left => "left",
+            CameraView::Right => "right",
+            CameraView::Top => "top",
+            CameraView::Bottom => "bottom",
+            CameraView::Iso => "iso",
+        }
+    }
+
+    /// Returns the camera axis for the view, i.e., the columns are the right, up and backward
+    /// direction of the camera.
+    pub fn get_axis(&self) -> glm::Mat3 {
+        let (dir, up) = match self {
+            CameraView::Front => (glm::vec3(0.0, 0.0, 1.0), glm::vec3(0.0, 1.0, 0.0)),
+            CameraView::Back => (glm::vec3(0.0, 0.0, -1.0), glm::vec3(0.0, 1.0, 0.0)),
+            CameraView::Left => (glm::vec3(-1.0, 0.0, 0.0), glm::vec3(0.0, 1.0, 0.0)),
+            CameraView::Right => (glm::vec3(1.0, 0.0, 0.0), glm::vec3(0.0, 1.0, 0.0)),
+            CameraView::Top => (glm::vec3(0.0, 1.0, 0.0), glm::vec3(0.0, 0.0, -1.0)),
+            CameraView::Bottom => (glm::vec3(0.0, -1.0, 0.0), glm::vec3(0.0, 0.0, 1.0)),
+            CameraView::Iso => (glm::vec3(1.0, 1.0, 1.0), glm::vec3(0.0, 1.0, 0.0)),
+        };
+
+        let dir = glm::normalize(&dir);
+        let right = glm::normalize(&glm::cross(&up, &dir));
+        let up = glm::cross(&dir, &right);
+
+        glm::Mat3::from_columns(&[right, up, dir])
+    }
+}
+
+impl Display for CameraView {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for CameraView {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.to_lowercase();
+        match CameraView::ALL.iter().find(|v| v.name() == s) {
+            Some(view) => Ok(*view),
+            None => {
+                bail!(
+                    "Unknown view '{}', expected one of {:?}",
+                    s,
+                    CameraView::ALL.map(|v| v.name())
+                );
+            }
+        }
+    }
+}
+
 #[derive(Debug)]
 enum Mode {
     Nothing,
@@ -43,7 +130,7 @@ impl Camera {
             self.save_cursor[0] = x;
             self.save_cursor[1] = y;
 
-            self.saved_data = self.data.clone();
+            self.saved_data = self.data;
 
             match btn {
                 MouseButton::Right => self.mode = Mode::Zoom,
@@ -69,6 +156,13 @@ impl Camera {
         self.data.set_radius(radius.ln())
     }
 
+    /// Sets the camera to look from the given predefined view onto the current center.
+    ///
+    ///* `view` - The view to set.
+    pub fn set_view(&mut self, view: CameraView) {
+        self.data.set_axis(&view.get_axis());
+    }
+
     /// Focuses the camera on the given scene volume
     ///
     ///* `volume` - The scene volume for the camera to focus on
@@ -79,24 +173,29 @@ impl Camera {
 
         self.set_radius(box_size * 1.5);
 
-        let camera_data = &mut self.data;
-        camera_data.set_center(&center);
+        self.data.set_center(&center);
+
+        self.update_scene(volume)
+    }
 
+    /// Updates the scene volume used for determining the clipping planes without changing the
+    /// current view.
+    ///
+    ///* `volume` - The new scene volume
+    pub fn update_scene(&mut self, volume: &BBox) -> anyhow::Result<()> {
         let scene_center = volume.get_center();
         let scene_radius = glm::length(&volume.get_size()) / 2f32;
-        camera_data.set_scene(scene_center, scene_radius)?;
+        self.data.set_scene(scene_center, scene_radius)?;
 
         Ok(())
     }
 
     fn modify(&mut self, newx: f64, newy: f64) {
-        let xdrift_func = || {
-            return ((newx - self.save_cursor[0]) as f32) / (self.data.get_window_size().0 as f32);
-        };
+        let xdrift_func =
+            || ((newx - self.save_cursor[0]) as f32) / (self.data.get_window_size().0 as f32);
 
-        let ydrift_func = || {
-            return ((newy - self.save_cursor[1]) as f32) / (self.data.get_window_size().1 as f32);
-        };
+        let ydrift_func =
+            || ((newy - self.save_cursor[1]) as f32) / (self.data.get_window_size().1 as f32);
 
         match self.mode {
             Mode::Zoom => {
@@ -107,8 +206,8 @@ impl Camera {
             Mode::Move => {
                 let cam_axis = self.data.get_axis();
 
-                let xaxis = glm::column(&cam_axis, 0);
-                let yaxis = glm::column(&cam_axis, 1);
+                let xaxis = glm::column(cam_axis, 0);
+                let yaxis = glm::column(cam_axis, 1);
 
                 let factor = self.data.get_radius().exp();
 
@@ -124,8 +223,8 @@ impl Camera {
 
                 let cam_axis = self.saved_data.get_axis();
 
-                let xrot_mat = glm::rotation(-xdrift * 2.5, &glm::column(&cam_axis, 1));
-                let yrot_mat = glm::rotation(-ydrift * 2.5, &glm::column(&cam_axis, 0));
+                let xrot_mat = glm::rotation(-xdrift * 2.5, &glm::column(cam_axis, 1));
+                let yrot_mat = glm::rotation(-ydrift * 2.5, &glm::column(cam_axis, 0));
 
                 let rot_mat = mat4_to_mat3(&(yrot_mat * xrot_mat));
 
@@ -1,9 +1,14 @@
 use anyhow::bail;
 use nalgebra_glm::{
-    column, determinant, dot, inverse_transpose, mat3_to_mat4, mat4_to_mat3, normalize,
-    perspective, translation, transpose, Mat3, Mat4, Vec3, Vec4,
+    column, dot, mat3_to_mat4, normalize, perspective, translation, transpose, Mat3, Mat4, Vec3,
+    Vec4,
 };
 
+use crate::geometry::BBox;
+
+/// The vertical field of view of the camera in radians.
+const FIELD_OF_VIEW: f32 = 1.0;
+
 #[derive(Clone, Copy)]
 pub struct CameraData {
     center: Vec3,
@@ -31,22 +36,46 @@ impl CameraData {
         }
     }
 
-    /// Returns the model view matrix for the camera.
-    pub fn get_model_matrix(&self) -> Mat4 {
+    /// Returns the position of the camera in world coordinates.
+    pub fn get_position(&self) -> Vec3 {
         let dir: Vec3 = column(&self.cam_axis, 2);
 
-        // compute position of the camera
         let factor = self.radius.exp();
-        let cam_pos = self.center + dir * factor;
+        self.center + dir * factor
+    }
+
+    /// Returns the approximate height in pixels of the given volume projected onto the screen,
+    /// which is based on the diagonal of the volume and the distance to the camera.
+    ///
+    /// # Arguments
+    /// * `volume` - The volume in world coordinates.
+    pub fn get_projected_size(&self, volume: &BBox) -> f32 {
+        if volume.is_empty() {
+            return 0f32;
+        }
+
+        let diagonal = volume.get_size().norm();
+        let distance = (volume.get_center() - self.get_position()).norm();
+        if distance <= diagonal * 0.5 {
+            return f32::INFINITY;
+        }
+
+        let pixels_per_unit = self.window_size.1 as f32 / (2f32 * (FIELD_OF_VIEW * 0.5).tan());
+        diagonal / distance * pixels_per_unit
+    }
+
+    /// Returns the model view matrix for the camera.
+    pub fn get_model_matrix(&self) -> Mat4 {
+        // compute position of the camera
+        let cam_pos = self.get_position();
 
         // create rotation matrix
         let rot_mat = transpose(&self.cam_axis);
         let rot_mat = mat3_to_mat4(&rot_mat);
 
         let tmat: Mat4 = translation(&(-cam_pos));
-        let res = rot_mat * tmat;
 
-        res
+        rot_mat * tmat
     }
 
     /// Returns the projection matrix for the camera
@@ -68,7 +97,7 @@ impl CameraData {
         let far = z + self.scene_radius * 1.5;
         let near = (z - self.scene_radius).max(far * 1e-6f32);
 
-        perspective(aspect, 1.0, near, far)
+        perspective(aspect, FIELD_OF_VIEW, near, far)
     }
 
     /// Returns the combined matrix, i.e. the combination of the projection and model view matrix
@@ -76,18 +105,6 @@ impl CameraData {
         self.get_projection_matrix() * self.get_model_matrix()
     }
 
-    /// Returns the normal matrix
-    pub fn get_normal_matrix(&self) -> Mat3 {
-        let mat = mat4_to_mat3(&self.get_model_matrix());
-
-        let d: f32 = determinant(&mat);
-        if d.abs() <= 1e-9 {
-            return mat;
-        } else {
-            return inverse_transpose(mat);
-        }
-    }
-
     pub fn get_window_size(&self) -> (u32, u32) {
         self.window_size
     }
@@ -128,7 +145,14 @@ impl CameraData {
     }
 
     pub fn set_center(&mut self, center: &Vec3) {
-        self.center = center.clone();
+        self.center = *center;
+    }
+
+    /// Sets the camera axis, i.e., the columns are the right, up and backward direction.
+    ///
+    ///* `axis` - The new orthonormal camera axis.
+    pub fn set_axis(&mut self, axis: &Mat3) {
+        self.cam_axis = *axis;
     }
 
     pub fn set_rotated_cam_axis(&mut self, axis: &Mat3, rot_mat: &Mat3) {
@@ -0,0 +1,340 @@
+use std::path::Path;
+
+use anyhow::{bail, Context, Result};
+use glow::HasContext;
+use nalgebra_glm::{Mat4, Vec3};
+
+use crate::{
+    geometry::{BBox, Plane},
+    gl_call,
+};
+
+use super::{gl_call::handle_glow_error, shader::Shader};
+
+/// The maximal number of clip planes supported by the shaders.
+pub const MAX_CLIP_PLANES: usize = 4;
+
+/// Returns the coefficients of the given clip planes packed for the shader uniforms, where the
+/// unused planes are zero.
+///
+/// # Arguments
+/// * `planes` - The clip planes, where planes beyond the maximal number are ignored.
+pub fn pack_clip_planes(planes: &[Plane]) -> [f32; MAX_CLIP_PLANES * 4] {
+    let mut packed = [0f32; MAX_CLIP_PLANES * 4];
+    for (i, plane) in planes.iter().take(MAX_CLIP_PLANES).enumerate() {
+        packed[i * 4..i * 4 + 4].copy_from_slice(plane.to_vec4().as_slice());
+    }
+
+    packed
+}
+
+/// Enables the clip distances of the first planes and disables all others.
+///
+/// # Arguments
+/// * `context` - The GLOW context.
+/// * `num_planes` - The number of enabled clip planes.
+/// * `except` - The index of a plane whose clip distance stays disabled.
+pub fn enable_clip_distances<C: HasContext>(context: &C, num_planes: usize, except: Option<usize>) {
+    for i in 0..MAX_CLIP_PLANES {
+        if i < num_planes && Some(i) != except {
+            gl_call!(context, enable, glow::CLIP_DISTANCE0 + i as u32);
+        } else {
+            gl_call!(context, disable, glow::CLIP_DISTANCE0 + i as u32);
+        }
+    }
+}
+
+/// The user-defined clip planes in world coordinates, where everything behind a plane is cut
+/// away. One of the planes is active and is modified by the interactive controls.
+#[derive(Debug, Clone, Default)]
+pub struct ClipPlanes {
+    planes: Vec<Plane>,
+    active: usize,
+}
+
+impl ClipPlanes {
+    /// Creates the clip planes from the given planes, where planes beyond the maximal number
+    /// are dropped.
+    ///
+    /// # Arguments
+    /// * `planes` - The initial clip planes.
+    pub fn new(mut planes: Vec<Plane>) -> Self {
+        planes.truncate(MAX_CLIP_PLANES);
+
+        Self { planes, active: 0 }
+    }
+
+    /// Loads the clip planes from the given file, which contains one plane per line in the
+    /// form `<NX> <NY> <NZ> <DISTANCE>`. Empty lines and lines starting with '#' are skipped.
+    ///
+    /// # Arguments
+    /// * `path` - The path to the file.
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .context(format!("Failed to read clip planes from {:?}", path))?;
+
+        let mut planes = Vec::new();
+        for (line_index, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let plane: Plane =
+                line.parse()
+                    .context(format!("Line {} of {:?}", line_index + 1, path))?;
+            planes.push(plane);
+        }
+
+        if planes.len() > MAX_CLIP_PLANES {
+            bail!(
+                "Got {} clip planes in {:?}, but at most {} are supported",
+                planes.len(),
+                path,
+                MAX_CLIP_PLANES
+            );
+        }
+
+        Ok(Self::new(planes))
+    }
+
+    /// Saves the clip planes to the given file in the format read by `load`.
+    ///
+    /// # Arguments
+    /// * `path` - The path to the file.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let mut content = String::from("# <NX> <NY> <NZ> <DISTANCE>\n");
+        for plane in self.planes.iter() {
+            content.push_str(&format!("{}\n", plane));
+        }
+
+        std::fs::write(path, content).context(format!("Failed to write clip planes to {:?}", path))
+    }
+
+    /// Adds the given plane and makes it the active one. Returns false if the maximal number of
+    /// clip planes is reached.
+    ///
+    /// # Arguments
+    /// * `plane` - The plane to add.
+    pub fn add(&mut self, plane: Plane) -> bool {
+        if self.planes.len() >= MAX_CLIP_PLANES {
+            return false;
+        }
+
+        self.planes.push(plane);
+        self.active = self.planes.len() - 1;
+
+        true
+    }
+
+    /// Removes the active plane, where the previous plane becomes the active one.
+    pub fn remove_active(&mut self) {
+        if self.active < self.planes.len() {
+            self.planes.remove(self.active);
+            self.active = self.active.saturating_sub(1);
+        }
+    }
+
+    /// Makes the plane following the active one the active plane, where the last plane wraps
+    /// around.
+    pub fn select_next(&mut self) {
+        if !self.planes.is_empty() {
+            self.active = (self.active + 1) % self.planes.len();
+        }
+    }
+
+    /// Returns the index of the active plane.
+    pub fn get_active_index(&self) -> usize {
+        self.active
+    }
+
+    /// Returns the active plane or none if there are no planes.
+    pub fn get_active(&self) -> Option<&Plane> {
+        self.planes.get(self.active)
+    }
+
+    /// Flips the active plane, s.t. the other side is cut away.
+    pub fn flip_active(&mut self) {
+        if let Some(plane) = self.planes.get_mut(self.active) {
+            *plane = plane.flip();
+        }
+    }
+
+    /// Moves the active plane along its normal, where the plane is kept within the given
+    /// bounding volume.
+    ///
+    /// # Arguments
+    /// * `offset` - The offset to move the plane by.
+    /// * `bbox` - The bounding volume the plane is restricted to.
+    pub fn translate_active(&mut self, offset: f32, bbox: &BBox) {
+        if let Some(plane) = self.planes.get_mut(self.active) {
+            *plane = plane.translate(offset, bbox);
+        }
+    }
+
+    /// Returns all clip planes.
+    pub fn get_planes(&self) -> &[Plane] {
+        &self.planes
+    }
+
+    /// Returns the number of clip planes.
+    pub fn len(&self) -> usize {
+        self.planes.len()
+    }
+
+    /// Returns true if there are no clip planes.
+    pub fn is_empty(&self) -> bool {
+        self.planes.is_empty()
+    }
+}
+
+/// Caps the surfaces cut open by the clip planes with a solid color. For each plane, the
+/// surfaces clipped only by this plane are counted into the stencil buffer, s.t. an odd count
+/// marks the pixels where the plane is inside of a solid. Afterwards, a quad on the plane is
+/// drawn onto the marked pixels.
+pub struct SectionCapping<C: HasContext> {
+    program: C::Program,
+    vertex_array: C::VertexArray,
+    uniform_combined_mat: C::UniformLocation,
+    uniform_corners: C::UniformLocation,
+    uniform_clip_planes: C::UniformLocation,
+    uniform_cap_color: C::UniformLocation,
+}
+
+impl<C: HasContext> SectionCapping<C> {
+    /// Creates the shader program for drawing the caps.
+    ///
+    /// # Arguments
+    /// * `context` - The GLOW context.
+    /// * `shader_version` - The version string for the shader code.
+    pub fn new(context: &C, shader_version: &str) -> Result<Self> {
+        let shader_sources = [
+            (glow::VERTEX_SHADER, include_str!("shaders/cap.vert")),
+            (glow::FRAGMENT_SHADER, include_str!("shaders/cap.frag")),
+        ];
+        let program = Shader::<C>::create_program(context, shader_version, &shader_sources)?;
+
+        let uniform_combined_mat =
+            Shader::<C>::get_uniform_location(context, program, "combinedMat")?;
+        let uniform_corners = Shader::<C>::get_uniform_location(context, program, "corners")?;
+        let uniform_clip_planes =
+            Shader::<C>::get_uniform_location(context, program, "clipPlanes")?;
+        let uniform_cap_color = Shader::<C>::get_uniform_location(context, program, "capColor")?;
+
+        // the quad is generated from the corner uniforms without any attributes
+        let vertex_array = handle_glow_error(gl_call!(context, create_vertex_array))?;
+
+        Ok(Self {
+            program,
+            vertex_array,
+            uniform_combined_mat,
+            uniform_corners,
+            uniform_clip_planes,
+            uniform_cap_color,
+        })
+    }
+
+    /// Clears the stencil buffer and sets up the state for counting the surfaces clipped by
+    /// the given plane. The surfaces are drawn afterwards without writing any color or depth.
+    ///
+    /// # Arguments
+    /// * `context` - The GLOW context.
+    /// * `plane_index` - The index of the plane to cap.
+    pub fn begin_stencil(&self, context: &C, plane_index: usize) {
+        gl_call!(context, clear_stencil, 0);
+        gl_call!(context, clear, glow::STENCIL_BUFFER_BIT);
+
+        gl_call!(context, color_mask, false, false, false, false);
+        gl_call!(context, depth_mask, false);
+        gl_call!(context, disable, glow::DEPTH_TEST);
+
+        gl_call!(context, enable, glow::STENCIL_TEST);
+        gl_call!(context, stencil_func, glow::ALWAYS, 0, 1);
+        gl_call!(context, stencil_op, glow::KEEP, glow::KEEP, glow::INVERT);
+        gl_call!(context, stencil_mask, 1);
+
+        for i in 0..MAX_CLIP_PLANES {
+            if i == plane_index {
+                gl_call!(context, enable, glow::CLIP_DISTANCE0 + i as u32);
+            } else {
+                gl_call!(context, disable, glow::CLIP_DISTANCE0 + i as u32);
+            }
+        }
+    }
+
+    /// Draws the cap of the given plane onto the pixels marked in the stencil buffer. The cap
+    /// is clipped by all other planes and restores the default color, depth and stencil state.
+    ///
+    /// # Arguments
+    /// * `context` - The GLOW context.
+    /// * `plane_index` - The index of the plane to cap.
+    /// * `planes` - All clip planes.
+    /// * `combined_mat` - The combined projection and model view matrix.
+    /// * `volume` - The bounding volume of the scene, which is covered by the cap.
+    /// * `cap_color` - The color of the cap.
+    pub fn draw_cap(
+        &self,
+        context: &C,
+        plane_index: usize,
+        planes: &[Plane],
+        combined_mat: &Mat4,
+        volume: &BBox,
+        cap_color: &Vec3,
+    ) {
+        gl_call!(context, color_mask, true, true, true, true);
+        gl_call!(context, depth_mask, true);
+        gl_call!(context, enable, glow::DEPTH_TEST);
+        gl_call!(context, stencil_func, glow::EQUAL, 1, 1);
+        gl_call!(context, stencil_op, glow::KEEP, glow::KEEP, glow::KEEP);
+        enable_clip_distances(context, planes.len(), Some(plane_index));
+
+        let corners: Vec<f32> = planes[plane_index]
+            .get_quad(volume)
+            .iter()
+            .flat_map(|corner| corner.iter().copied())
+            .collect();
+
+        gl_call!(context, use_program, Some(self.program));
+        gl_call!(
+            context,
+            uniform_matrix_4_f32_slice,
+            Some(&self.uniform_combined_mat),
+            false,
+            combined_mat.as_slice()
+        );
+        gl_call!(
+            context,
+            uniform_3_f32_slice,
+            Some(&self.uniform_corners),
+            &corners
+        );
+        gl_call!(
+            context,
+            uniform_4_f32_slice,
+            Some(&self.uniform_clip_planes),
+            &pack_clip_planes(planes)
+        );
+        gl_call!(
+            context,
+            uniform_3_f32_slice,
+            Some(&self.uniform_cap_color),
+            cap_color.as_slice()
+        );
+
+        gl_call!(context, bind_vertex_array, Some(self.vertex_array));
+        gl_call!(context, draw_arrays, glow::TRIANGLE_STRIP, 0, 4);
+        gl_call!(context, bind_vertex_array, None);
+
+        gl_call!(context, disable, glow::STENCIL_TEST);
+        enable_clip_distances(context, planes.len(), None);
+    }
+
+    /// Frees all GPU resources.
+    ///
+    /// # Arguments
+    /// * `context` - The GLOW context.
+    pub fn cleanup(&self, context: &C) {
+        gl_call!(context, delete_vertex_array, self.vertex_array);
+        gl_call!(context, delete_program, self.program);
+    }
+}
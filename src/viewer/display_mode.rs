@@ -0,0 +1,163 @@
+use std::{fmt::Display, str::FromStr};
+
+use anyhow::bail;
+
+/// The modes for displaying the surfaces and edges of the shapes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DisplayMode {
+    /// Lit surfaces without edges.
+    Shaded,
+
+    /// The edges of all triangles without any surfaces.
+    Wireframe,
+
+    /// Lit surfaces with the feature edges drawn on top.
+    ShadedWithEdges,
+
+    /// The visible feature edges only, i.e., the surfaces are filled with the background color.
+    HiddenLine,
+}
+
+impl DisplayMode {
+    /// All available display modes in the order they are cycled through.
+    pub const ALL: [DisplayMode; 4] = [
+        DisplayMode::Shaded,
+        DisplayMode::Wireframe,
+        DisplayMode::ShadedWithEdges,
+        DisplayMode::HiddenLine,
+    ];
+
+    /// Returns the name of the display mode.
+    pub fn name(&self) -> &'static str {
+        match self {
+            DisplayMode::Shaded => "shaded",
+            DisplayMode::Wireframe => "wireframe",
+            DisplayMode::ShadedWithEdges => "edges",
+            DisplayMode::HiddenLine => "hidden-line",
+        }
+    }
+
+    /// Returns the display mode following this one, where the last mode wraps around.
+    pub fn next(&self) -> Self {
+        let index = Self::ALL.iter().position(|mode| mode == self).unwrap_or(0);
+
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+
+    /// Returns true if the feature edges are drawn in this display mode.
+    pub fn has_edges(&self) -> bool {
+        matches!(self, DisplayMode::ShadedWithEdges | DisplayMode::HiddenLine)
+    }
+
+    /// Returns true if the surfaces are filled, i.e., drawn as triangles and not as lines.
+    pub fn has_filled_surfaces(&self) -> bool {
+        !matches!(self, DisplayMode::Wireframe)
+    }
+
+    /// Returns true if the surfaces are lit with their materials, i.e., transparency is visible.
+    pub fn has_lit_surfaces(&self) -> bool {
+        matches!(self, DisplayMode::Shaded | DisplayMode::ShadedWithEdges)
+    }
+
+    /// Returns true if the filled surfaces are pushed back by a polygon offset, s.t. the
+    /// feature edges on top of them pass the depth test.
+    pub fn has_polygon_offset(&self) -> bool {
+        self.has_filled_surfaces() && self.has_edges()
+    }
+}
+
+impl Display for DisplayMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for DisplayMode {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.to_lowercase();
+        match Self::ALL.iter().find(|mode| mode.name() == s) {
+            Some(mode) => Ok(*mode),
+            None => {
+                let names: Vec<&str> = Self::ALL.iter().map(|mode| mode.name()).collect();
+                bail!(
+                    "Unknown display mode '{}', expected one of {}",
+                    s,
+                    names.join(", ")
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cycling_order() {
+        let mut mode = DisplayMode::Shaded;
+        let mut cycle = Vec::new();
+        for _ in 0..5 {
+            cycle.push(mode);
+            mode = mode.next();
+        }
+
+        assert_eq!(
+            cycle,
+            vec![
+                DisplayMode::Shaded,
+                DisplayMode::Wireframe,
+                DisplayMode::ShadedWithEdges,
+                DisplayMode::HiddenLine,
+                DisplayMode::Shaded,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_names() {
+        for mode in DisplayMode::ALL {
+            assert_eq!(mode.to_string().parse::<DisplayMode>().unwrap(), mode);
+        }
+
+        assert_eq!(
+            "Hidden-Line".parse::<DisplayMode>().unwrap(),
+            DisplayMode::HiddenLine
+        );
+        assert!("edge".parse::<DisplayMode>().is_err());
+    }
+
+    #[test]
+    fn test_polygon_offset_and_edges() {
+        let offsets: Vec<(bool, bool)> = DisplayMode::ALL
+            .iter()
+            .map(|mode| (mode.has_edges(), mode.has_polygon_offset()))
+            .collect();
+        assert_eq!(
+            offsets,
+            vec![(false, false), (false, false), (true, true), (true, true)]
+        );
+
+        // the feature edges are only drawn on top of filled surfaces pushed back by the offset
+        for mode in DisplayMode::ALL {
+            if mode.has_edges() {
+                assert!(mode.has_filled_surfaces() && mode.has_polygon_offset());
+            }
+        }
+
+        // the wireframe draws the triangle edges itself and has no feature edges
+        assert!(!DisplayMode::Wireframe.has_filled_surfaces());
+        assert!(!DisplayMode::Wireframe.has_edges());
+    }
+
+    #[test]
+    fn test_lit_surfaces() {
+        let lit: Vec<DisplayMode> = DisplayMode::ALL
+            .into_iter()
+            .filter(|mode| mode.has_lit_surfaces())
+            .collect();
+        assert_eq!(lit, vec![DisplayMode::Shaded, DisplayMode::ShadedWithEdges]);
+    }
+}
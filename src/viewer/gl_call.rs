@@ -45,6 +45,22 @@ pub fn check<C: HasContext>(context: &C, filename: &str, line: u32, column: u32)
     }
 }
 
+/// Returns an error if the previous OpenGL function calls caused any errors. In contrast to
+/// `check`, the error is not only logged, s.t. the caller can handle it.
+///
+/// # Arguments
+///
+/// * `context` - The context to check for an error.
+pub fn check_error<C: HasContext>(context: &C) -> anyhow::Result<()> {
+    let error_code = unsafe { context.get_error() };
+
+    if error_code != NO_ERROR {
+        bail!("Found OpenGL error '{}'", code_to_string(error_code));
+    }
+
+    Ok(())
+}
+
 /// Internal use only function which performs the additional steps for an OpenGL function call.
 /// Returns the passed return value t.
 ///
@@ -85,22 +101,22 @@ pub fn handle_glow_error<T>(result: Result<T, String>) -> anyhow::Result<T> {
 #[macro_export]
 macro_rules! gl_call {
     ($ctx:ident, $function:ident, $($params:tt)*) => {
-        $crate::viewer::gl_call::gl_call_helper(
-            unsafe { $ctx.$function($($params)*) },
-            $ctx,
-            file!(),
-            line!(),
-            column!(),
-        )
+        {
+            // the macro only forwards its arguments to the unsafe GLOW function
+            #[allow(clippy::macro_metavars_in_unsafe)]
+            let result = unsafe { $ctx.$function($($params)*) };
+
+            $crate::viewer::gl_call::gl_call_helper(result, $ctx, file!(), line!(), column!())
+        }
     };
 
     ($ctx:ident, $function:ident) => {
-        $crate::viewer::gl_call::gl_call_helper(
-            unsafe { $ctx.$function() },
-            $ctx,
-            file!(),
-            line!(),
-            column!(),
-        )
+        {
+            // the macro only forwards its arguments to the unsafe GLOW function
+            #[allow(clippy::macro_metavars_in_unsafe)]
+            let result = unsafe { $ctx.$function() };
+
+            $crate::viewer::gl_call::gl_call_helper(result, $ctx, file!(), line!(), column!())
+        }
     };
 }
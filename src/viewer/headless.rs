@@ -0,0 +1,362 @@
+use std::{fs::File, io::BufWriter, path::Path};
+
+use anyhow::{bail, Context as _, Result};
+use glow::{Context, HasContext, PixelPackData};
+use khronos_egl as egl;
+use log::{debug, info, warn};
+
+use crate::gl_call;
+
+use super::{
+    gl_call::handle_glow_error,
+    viewer::{ContextConfig, ViewerController},
+};
+
+/// The EGL platform enum for surfaceless rendering on Mesa, see EGL_MESA_platform_surfaceless.
+const PLATFORM_SURFACELESS_MESA: egl::Enum = 0x31DD;
+
+type EGLInstance = egl::DynamicInstance<egl::EGL1_5>;
+
+/// The EGL display together with the context created on it. Both are released when dropped,
+/// s.t. no error path leaks them.
+struct EGLSession {
+    egl: EGLInstance,
+    display: Option<egl::Display>,
+    context: Option<egl::Context>,
+}
+
+impl EGLSession {
+    /// Releases the context and terminates the display. Calling it again has no effect.
+    fn release(&mut self) -> Result<()> {
+        let display = match self.display.take() {
+            Some(display) => display,
+            None => return Ok(()),
+        };
+
+        if let Some(context) = self.context.take() {
+            self.egl
+                .make_current(display, None, None, None)
+                .context("Failed to release EGL context")?;
+            self.egl
+                .destroy_context(display, context)
+                .context("Failed to destroy EGL context")?;
+        }
+
+        self.egl
+            .terminate(display)
+            .context("Failed to terminate EGL")
+    }
+}
+
+impl Drop for EGLSession {
+    fn drop(&mut self) {
+        if let Err(err) = self.release() {
+            warn!("{:#}", err);
+        }
+    }
+}
+
+/// A viewer without any window that renders a single frame into an offscreen framebuffer.
+/// The OpenGL context is created via EGL on the surfaceless Mesa platform, i.e., neither a
+/// display nor a GPU is needed as Mesa falls back to its software rasterizer.
+pub struct HeadlessViewer<C> {
+    // the GL context is dropped before the EGL context it has been loaded from
+    gl: Context,
+    session: EGLSession,
+    controller: C,
+    width: u32,
+    height: u32,
+}
+
+impl<C: ViewerController<Context>> HeadlessViewer<C> {
+    /// Creates and returns a new headless viewer with the given frame size.
+    ///
+    /// # Arguments
+    /// * `controller` - The controller which draws the frame.
+    /// * `width` - The width of the frame in pixels.
+    /// * `height` - The height of the frame in pixels.
+    pub fn new(controller: C, width: u32, height: u32) -> Result<Self> {
+        if width == 0 || height == 0 {
+            bail!("Invalid snapshot size {}x{}", width, height);
+        }
+
+        let egl =
+            unsafe { EGLInstance::load_required() }.context("Failed to load the EGL library")?;
+
+        let display = unsafe {
+            egl.get_platform_display(
+                PLATFORM_SURFACELESS_MESA,
+                egl::DEFAULT_DISPLAY,
+                &[egl::ATTRIB_NONE],
+            )
+        }
+        .context("Failed to get the surfaceless EGL display")?;
+
+        let (major, minor) = egl
+            .initialize(display)
+            .context("Failed to initialize EGL")?;
+        debug!("EGL Version: {}.{}", major, minor);
+
+        // from here on the display is terminated on errors
+        let mut session = EGLSession {
+            egl,
+            display: Some(display),
+            context: None,
+        };
+        let egl = &session.egl;
+
+        egl.bind_api(egl::OPENGL_API)
+            .context("Failed to bind the OpenGL API")?;
+
+        let config_attributes = [
+            egl::RENDERABLE_TYPE,
+            egl::OPENGL_BIT,
+            egl::SURFACE_TYPE,
+            0,
+            egl::NONE,
+        ];
+        let config = match egl
+            .choose_first_config(display, &config_attributes)
+            .context("Failed to choose EGL config")?
+        {
+            Some(config) => config,
+            None => {
+                bail!("No EGL config with OpenGL support available");
+            }
+        };
+
+        let context_attributes = [
+            egl::CONTEXT_MAJOR_VERSION,
+            4,
+            egl::CONTEXT_MINOR_VERSION,
+            1,
+            egl::CONTEXT_OPENGL_PROFILE_MASK,
+            egl::CONTEXT_OPENGL_CORE_PROFILE_BIT,
+            egl::NONE,
+        ];
+        let egl_context = egl
+            .create_context(display, config, None, &context_attributes)
+            .context("Failed to create EGL context")?;
+        session.context = Some(egl_context);
+        let egl = &session.egl;
+
+        egl.make_current(display, None, None, Some(egl_context))
+            .context("Failed to make EGL context current")?;
+
+        let gl = unsafe {
+            Context::from_loader_function(|s| match egl.get_proc_address(s) {
+                Some(p) => p as *const _,
+                None => std::ptr::null(),
+            })
+        };
+
+        let gl_ref = &gl;
+        info!(
+            "Headless OpenGL: {} ({})",
+            gl_call!(gl_ref, get_parameter_string, glow::VERSION),
+            gl_call!(gl_ref, get_parameter_string, glow::RENDERER)
+        );
+
+        Ok(Self {
+            gl,
+            session,
+            controller,
+            width,
+            height,
+        })
+    }
+
+    /// Renders a single frame and writes it as PNG file to the given path. All GL and EGL
+    /// resources are released, even if rendering fails.
+    ///
+    /// # Arguments
+    /// * `output_file` - The path of the PNG file to write.
+    pub fn render_snapshot(mut self, output_file: &Path) -> Result<()> {
+        let (framebuffer, renderbuffers) =
+            Self::create_framebuffer(&self.gl, self.width, self.height)?;
+
+        let pixels = self.render_frame(framebuffer);
+
+        let gl = &self.gl;
+        self.controller.cleanup(gl);
+        Self::delete_framebuffer(gl, framebuffer, &renderbuffers);
+
+        Self::write_png(output_file, self.width, self.height, &pixels?)?;
+        info!("Wrote snapshot to {:?}", output_file);
+
+        self.session.release()
+    }
+
+    /// Initializes the controller with the given framebuffer, draws a single frame into it
+    /// and returns the read back pixels.
+    ///
+    /// # Arguments
+    /// * `framebuffer` - The bound offscreen framebuffer to draw into.
+    fn render_frame(&mut self, framebuffer: glow::Framebuffer) -> Result<Vec<u8>> {
+        let gl = &self.gl;
+        let (width, height) = (self.width, self.height);
+
+        let context_config = ContextConfig {
+            shader_version: "#version 410".to_owned(),
+            width,
+            height,
+            scale_factor: 1f64,
+            framebuffer: Some(framebuffer),
+        };
+        self.controller.initialize(gl, context_config)?;
+        self.controller.resize(gl, width, height);
+
+        // wait until the data has been loaded and uploaded
+        self.controller.wait_for_data(gl);
+
+        if self.controller.has_failed() {
+            bail!("Failed to render snapshot due to previous errors");
+        }
+
+        info!("Render snapshot {}x{}...", width, height);
+        self.controller.draw(gl);
+        gl_call!(gl, finish);
+
+        Ok(Self::read_pixels(gl, width, height))
+    }
+
+    /// Creates and binds a framebuffer with a color and depth attachment of the given size.
+    ///
+    /// # Arguments
+    /// * `gl` - The GLOW context.
+    /// * `width` - The width of the framebuffer in pixels.
+    /// * `height` - The height of the framebuffer in pixels.
+    fn create_framebuffer(
+        gl: &Context,
+        width: u32,
+        height: u32,
+    ) -> Result<(glow::Framebuffer, Vec<glow::Renderbuffer>)> {
+        let framebuffer = handle_glow_error(gl_call!(gl, create_framebuffer))?;
+        gl_call!(gl, bind_framebuffer, glow::FRAMEBUFFER, Some(framebuffer));
+
+        let attachments = [
+            (glow::RGBA8, glow::COLOR_ATTACHMENT0),
+            (glow::DEPTH24_STENCIL8, glow::DEPTH_STENCIL_ATTACHMENT),
+        ];
+
+        let mut renderbuffers = Vec::with_capacity(attachments.len());
+        for (format, attachment) in attachments {
+            let renderbuffer = match handle_glow_error(gl_call!(gl, create_renderbuffer)) {
+                Ok(renderbuffer) => renderbuffer,
+                Err(err) => {
+                    Self::delete_framebuffer(gl, framebuffer, &renderbuffers);
+                    return Err(err);
+                }
+            };
+            renderbuffers.push(renderbuffer);
+
+            gl_call!(
+                gl,
+                bind_renderbuffer,
+                glow::RENDERBUFFER,
+                Some(renderbuffer)
+            );
+            gl_call!(
+                gl,
+                renderbuffer_storage,
+                glow::RENDERBUFFER,
+                format,
+                width as i32,
+                height as i32
+            );
+            gl_call!(
+                gl,
+                framebuffer_renderbuffer,
+                glow::FRAMEBUFFER,
+                attachment,
+                glow::RENDERBUFFER,
+                Some(renderbuffer)
+            );
+        }
+        gl_call!(gl, bind_renderbuffer, glow::RENDERBUFFER, None);
+
+        let status = gl_call!(gl, check_framebuffer_status, glow::FRAMEBUFFER);
+        if status != glow::FRAMEBUFFER_COMPLETE {
+            Self::delete_framebuffer(gl, framebuffer, &renderbuffers);
+            bail!("Offscreen framebuffer is incomplete (status={:#x})", status);
+        }
+
+        Ok((framebuffer, renderbuffers))
+    }
+
+    /// Unbinds and deletes the given framebuffer together with its renderbuffers.
+    ///
+    /// # Arguments
+    /// * `gl` - The GLOW context.
+    /// * `framebuffer` - The framebuffer to delete.
+    /// * `renderbuffers` - The renderbuffers attached to the framebuffer.
+    fn delete_framebuffer(
+        gl: &Context,
+        framebuffer: glow::Framebuffer,
+        renderbuffers: &[glow::Renderbuffer],
+    ) {
+        gl_call!(gl, bind_framebuffer, glow::FRAMEBUFFER, None);
+        gl_call!(gl, delete_framebuffer, framebuffer);
+        for renderbuffer in renderbuffers {
+            gl_call!(gl, delete_renderbuffer, *renderbuffer);
+        }
+    }
+
+    /// Reads back the RGBA pixels of the currently bound framebuffer. The rows are returned
+    /// top to bottom.
+    ///
+    /// # Arguments
+    /// * `gl` - The GLOW context.
+    /// * `width` - The width of the framebuffer in pixels.
+    /// * `height` - The height of the framebuffer in pixels.
+    fn read_pixels(gl: &Context, width: u32, height: u32) -> Vec<u8> {
+        let row_size = width as usize * 4;
+        let mut pixels = vec![0u8; row_size * height as usize];
+
+        gl_call!(gl, pixel_store_i32, glow::PACK_ALIGNMENT, 1);
+        gl_call!(
+            gl,
+            read_pixels,
+            0,
+            0,
+            width as i32,
+            height as i32,
+            glow::RGBA,
+            glow::UNSIGNED_BYTE,
+            PixelPackData::Slice(&mut pixels)
+        );
+
+        // OpenGL returns the rows bottom to top
+        let mut flipped = Vec::with_capacity(pixels.len());
+        for row in pixels.chunks_exact(row_size).rev() {
+            flipped.extend_from_slice(row);
+        }
+
+        flipped
+    }
+
+    /// Writes the given RGBA pixels as PNG file.
+    ///
+    /// # Arguments
+    /// * `output_file` - The path of the PNG file to write.
+    /// * `width` - The width of the image in pixels.
+    /// * `height` - The height of the image in pixels.
+    /// * `pixels` - The RGBA pixels, rows ordered top to bottom.
+    fn write_png(output_file: &Path, width: u32, height: u32, pixels: &[u8]) -> Result<()> {
+        let file = File::create(output_file)
+            .context(format!("Failed to create snapshot file {:?}", output_file))?;
+
+        let mut encoder = png::Encoder::new(BufWriter::new(file), width, height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+
+        let mut writer = encoder
+            .write_header()
+            .context("Failed to write PNG header")?;
+        writer
+            .write_image_data(pixels)
+            .context("Failed to write PNG data")?;
+
+        Ok(())
+    }
+}
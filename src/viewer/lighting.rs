@@ -0,0 +1,301 @@
+use cad_import::structure::{Material, PhongMaterialData};
+#[cfg(test)]
+use nalgebra_glm::dot;
+use nalgebra_glm::{mat4_to_mat3, normalize, Mat4, Vec3};
+
+/// The maximal number of lights supported by the shader.
+pub const MAX_LIGHTS: usize = 8;
+
+/// The factor mapping the shininess of a material in [0,1] to the exponent of the specular term.
+const SHININESS_SCALE: f32 = 128.0;
+
+/// A light infinitely far away, which illuminates the scene from a single direction.
+#[derive(Debug, Clone, Copy)]
+pub struct DirectionalLight {
+    /// The direction in which the light travels.
+    pub direction: Vec3,
+
+    /// The color of the light multiplied by its intensity.
+    pub color: Vec3,
+}
+
+/// The lights illuminating the scene.
+#[derive(Debug, Clone)]
+pub struct Lighting {
+    /// The color of the headlight, i.e., a directional light looking along the view direction
+    /// of the camera, or none if the headlight is disabled.
+    pub headlight: Option<Vec3>,
+
+    /// Additional directional lights with their directions in world coordinates.
+    pub lights: Vec<DirectionalLight>,
+}
+
+impl Default for Lighting {
+    fn default() -> Self {
+        Self {
+            headlight: Some(Vec3::new(1.0, 1.0, 1.0)),
+            lights: Vec::new(),
+        }
+    }
+}
+
+impl Lighting {
+    /// Returns the lights with their directions in view coordinates. Lights beyond the number
+    /// of lights supported by the shader are dropped.
+    ///
+    /// # Arguments
+    /// * `model_view_matrix` - The matrix transforming world coordinates to view coordinates.
+    pub fn get_view_lights(&self, model_view_matrix: &Mat4) -> Vec<DirectionalLight> {
+        let rotation = mat4_to_mat3(model_view_matrix);
+
+        let headlight = self.headlight.map(|color| DirectionalLight {
+            direction: Vec3::new(0.0, 0.0, -1.0),
+            color,
+        });
+
+        headlight
+            .into_iter()
+            .chain(self.lights.iter().map(|light| DirectionalLight {
+                direction: normalize(&(rotation * light.direction)),
+                color: light.color,
+            }))
+            .take(MAX_LIGHTS)
+            .collect()
+    }
+}
+
+/// The parameters of the Blinn-Phong lighting equation derived from a material.
+#[derive(Debug, Clone, Copy)]
+pub struct PhongParameters {
+    pub diffuse: Vec3,
+    pub specular: Vec3,
+    pub emissive: Vec3,
+
+    /// The fraction of the diffuse color reflected as ambient light, added once for all lights.
+    pub ambient_intensity: f32,
+
+    /// The exponent of the specular term.
+    pub shininess: f32,
+
+    /// The opacity of the surface, where zero is completely transparent.
+    pub opacity: f32,
+}
+
+impl PhongParameters {
+    /// Returns the lighting parameters of the given material. Missing materials are shaded with
+    /// the default Phong material.
+    ///
+    /// # Arguments
+    /// * `material` - The material to derive the parameters from.
+    pub fn from_material(material: &Material) -> Self {
+        let default_material = PhongMaterialData::default();
+        let p = match material {
+            Material::PhongMaterial(p) => p,
+            Material::None => &default_material,
+        };
+
+        Self {
+            diffuse: p.diffuse_color.0,
+            specular: p.specular_color.0,
+            emissive: p.emissive_color.0,
+            ambient_intensity: p.ambient_intensity,
+            shininess: (p.shininess * SHININESS_SCALE).max(1.0),
+            opacity: 1.0 - p.transparency.clamp(0.0, 1.0),
+        }
+    }
+
+    /// Evaluates the lighting equation on the CPU, the reference for `shade` in the fragment
+    /// shader, which both have to be kept in sync. The lighting is two-sided, i.e., normals
+    /// facing away from the viewer are flipped.
+    ///
+    /// # Arguments
+    /// * `normal` - The normalized surface normal in view coordinates.
+    /// * `view_dir` - The normalized direction from the surface to the viewer.
+    /// * `lights` - The lights in view coordinates.
+    #[cfg(test)]
+    pub fn shade(&self, normal: &Vec3, view_dir: &Vec3, lights: &[DirectionalLight]) -> Vec3 {
+        let normal = if dot(normal, view_dir) < 0.0 {
+            -normal
+        } else {
+            *normal
+        };
+
+        let mut color = self.emissive + self.diffuse * self.ambient_intensity;
+        for light in lights.iter() {
+            let l = -light.direction;
+            let h = normalize(&(l + view_dir));
+
+            let diffuse = dot(&normal, &l).max(0.0);
+            let specular = if diffuse > 0.0 {
+                dot(&normal, &h).max(0.0).powf(self.shininess)
+            } else {
+                0.0
+            };
+
+            let reflected = self.diffuse * diffuse + self.specular * specular;
+            color += light.color.component_mul(&reflected);
+        }
+
+        color
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: Vec3, expected: Vec3) {
+        assert!(
+            (actual - expected).abs().max() < 1e-5,
+            "{:?} != {:?}",
+            actual,
+            expected
+        );
+    }
+
+    fn parameters() -> PhongParameters {
+        PhongParameters {
+            diffuse: Vec3::new(0.5, 0.25, 1.0),
+            specular: Vec3::new(1.0, 1.0, 1.0),
+            emissive: Vec3::new(0.1, 0.0, 0.0),
+            ambient_intensity: 0.2,
+            shininess: 2.0,
+            opacity: 1.0,
+        }
+    }
+
+    fn headlight(color: Vec3) -> DirectionalLight {
+        DirectionalLight {
+            direction: Vec3::new(0.0, 0.0, -1.0),
+            color,
+        }
+    }
+
+    #[test]
+    fn test_shade_without_lights() {
+        // emissive plus ambient
+        let color = parameters().shade(&Vec3::z(), &Vec3::z(), &[]);
+        assert_close(color, Vec3::new(0.2, 0.05, 0.2));
+    }
+
+    #[test]
+    fn test_shade_headlight() {
+        // the normal faces the light, i.e., full diffuse and specular
+        let lights = [headlight(Vec3::new(1.0, 1.0, 1.0))];
+        let color = parameters().shade(&Vec3::z(), &Vec3::z(), &lights);
+        assert_close(color, Vec3::new(1.7, 1.3, 2.2));
+
+        // a normal facing away from the viewer is flipped
+        let flipped = parameters().shade(&-Vec3::z(), &Vec3::z(), &lights);
+        assert_close(flipped, color);
+    }
+
+    #[test]
+    fn test_shade_oblique_light() {
+        // light at 60 degrees: diffuse cos(60) = 0.5, the half vector is at 30 degrees
+        let lights = [DirectionalLight {
+            direction: -Vec3::new(3f32.sqrt() * 0.5, 0.0, 0.5),
+            color: Vec3::new(1.0, 1.0, 1.0),
+        }];
+        let color = parameters().shade(&Vec3::z(), &Vec3::z(), &lights);
+
+        let specular = (30f32.to_radians().cos()).powf(2.0);
+        assert_close(
+            color,
+            Vec3::new(0.2 + 0.25, 0.05 + 0.125, 0.2 + 0.5).add_scalar(specular),
+        );
+
+        // the light behind the surface has neither diffuse nor specular contribution
+        let lights = [DirectionalLight {
+            direction: Vec3::z(),
+            color: Vec3::new(1.0, 1.0, 1.0),
+        }];
+        let color = parameters().shade(&Vec3::z(), &Vec3::z(), &lights);
+        assert_close(color, Vec3::new(0.2, 0.05, 0.2));
+    }
+
+    #[test]
+    fn test_ambient_is_added_once() {
+        let mut p = parameters();
+        p.specular = Vec3::zeros();
+
+        let light = headlight(Vec3::new(0.5, 0.5, 0.5));
+        let one = p.shade(&Vec3::z(), &Vec3::z(), &[light]);
+        let two = p.shade(&Vec3::z(), &Vec3::z(), &[light, light]);
+
+        // only the diffuse term of the second light is added
+        assert_close(two - one, p.diffuse * 0.5);
+        assert_close(one, p.emissive + p.diffuse * (0.2 + 0.5));
+    }
+
+    #[test]
+    fn test_view_lights_are_rotated() {
+        let lighting = Lighting {
+            headlight: None,
+            lights: vec![DirectionalLight {
+                direction: Vec3::new(2.0, 0.0, 0.0),
+                color: Vec3::new(0.5, 0.5, 0.5),
+            }],
+        };
+
+        // rotating the world by 90 degrees around y maps x to -z, the translation is ignored
+        let model_view = nalgebra_glm::translation(&Vec3::new(1.0, 2.0, 3.0))
+            * nalgebra_glm::rotation(90f32.to_radians(), &Vec3::y());
+        let lights = lighting.get_view_lights(&model_view);
+
+        assert_eq!(lights.len(), 1);
+        assert_close(lights[0].direction, Vec3::new(0.0, 0.0, -1.0));
+        assert_close(lights[0].color, Vec3::new(0.5, 0.5, 0.5));
+    }
+
+    #[test]
+    fn test_view_headlight() {
+        let mut lighting = Lighting {
+            headlight: Some(Vec3::new(1.0, 0.5, 0.25)),
+            lights: vec![headlight(Vec3::new(0.5, 0.5, 0.5))],
+        };
+
+        // the headlight looks along the view direction independent of the camera
+        let model_view = nalgebra_glm::rotation(45f32.to_radians(), &Vec3::x());
+        let lights = lighting.get_view_lights(&model_view);
+        assert_eq!(lights.len(), 2);
+        assert_close(lights[0].direction, Vec3::new(0.0, 0.0, -1.0));
+        assert_close(lights[0].color, Vec3::new(1.0, 0.5, 0.25));
+        assert_close(
+            lights[1].direction,
+            model_view.transform_vector(&-Vec3::z()),
+        );
+
+        lighting.headlight = None;
+        let lights = lighting.get_view_lights(&model_view);
+        assert_eq!(lights.len(), 1);
+        assert_close(lights[0].color, Vec3::new(0.5, 0.5, 0.5));
+    }
+
+    #[test]
+    fn test_view_lights_are_truncated() {
+        let lighting = Lighting {
+            headlight: Some(Vec3::new(1.0, 1.0, 1.0)),
+            lights: (0..10)
+                .map(|i| headlight(Vec3::new(i as f32, 0.0, 0.0)))
+                .collect(),
+        };
+
+        // the headlight comes first and only the first additional lights are kept
+        let lights = lighting.get_view_lights(&Mat4::identity());
+        assert_eq!(lights.len(), MAX_LIGHTS);
+        assert_close(lights[0].color, Vec3::new(1.0, 1.0, 1.0));
+        for (i, light) in lights[1..].iter().enumerate() {
+            assert_close(light.color, Vec3::new(i as f32, 0.0, 0.0));
+        }
+
+        let lighting = Lighting {
+            headlight: None,
+            ..lighting
+        };
+        assert_eq!(
+            lighting.get_view_lights(&Mat4::identity()).len(),
+            MAX_LIGHTS
+        );
+    }
+}
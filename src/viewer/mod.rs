@@ -1,11 +1,28 @@
-mod bbox;
 mod camera;
 mod camera_data;
+mod clipping;
+mod display_mode;
+mod headless;
+mod lighting;
+mod options;
+mod overlay;
+mod picking;
+mod render_queue;
 mod renderer;
+mod selection;
 mod shader;
+mod transparency;
+#[allow(clippy::module_inception)]
 mod viewer;
 
 pub mod gl_call;
 
+pub use camera::CameraView;
+pub use clipping::ClipPlanes;
+pub use display_mode::DisplayMode;
+pub use headless::HeadlessViewer;
+pub use lighting::{DirectionalLight, Lighting};
+pub use options::RendererOptions;
 pub use renderer::Renderer;
+pub use transparency::TransparencyMode;
 pub use viewer::Viewer;
@@ -0,0 +1,80 @@
+use std::path::PathBuf;
+
+use nalgebra_glm::Vec3;
+
+use crate::{
+    geometry::{Plane, DEFAULT_CREASE_ANGLE},
+    gpu_data::{UploadBudget, VertexFormat},
+};
+
+use super::{
+    camera::CameraView, display_mode::DisplayMode, lighting::Lighting,
+    transparency::TransparencyMode,
+};
+
+/// The options to configure the renderer.
+#[derive(Debug, Clone)]
+pub struct RendererOptions {
+    /// The color used for clearing the background.
+    pub background_color: Vec3,
+
+    /// The view from which the camera initially looks onto the scene.
+    pub initial_view: CameraView,
+
+    /// The budget for uploading data to the GPU per frame.
+    pub upload_budget: UploadBudget,
+
+    /// The color used for highlighting the selected shape instances.
+    pub highlight_color: Vec3,
+
+    /// Merges the meshes of small shape instances into combined GPU meshes after the upload.
+    pub merge_small_meshes: bool,
+
+    /// The format of the vertex data of the meshes on the GPU.
+    pub vertex_format: VertexFormat,
+
+    /// The lights illuminating the scene.
+    pub lighting: Lighting,
+
+    /// The blending of the transparent parts with the scene.
+    pub transparency: TransparencyMode,
+
+    /// The initial mode for displaying the surfaces and edges.
+    pub display_mode: DisplayMode,
+
+    /// The color of the feature edges.
+    pub edge_color: Vec3,
+
+    /// The angle in degrees between adjacent triangles above which their edge is a feature edge.
+    pub crease_angle: f32,
+
+    /// The initial clip planes in world coordinates.
+    pub clip_planes: Vec<Plane>,
+
+    /// The file the clip planes are saved to or none for the default file.
+    pub clip_planes_file: Option<PathBuf>,
+
+    /// The color of the caps of the surfaces cut open by the clip planes.
+    pub cap_color: Vec3,
+}
+
+impl Default for RendererOptions {
+    fn default() -> Self {
+        Self {
+            background_color: Vec3::new(0.2, 0.2, 1.0),
+            initial_view: CameraView::Front,
+            upload_budget: UploadBudget::default(),
+            highlight_color: Vec3::new(1.0, 0.6, 0.0),
+            merge_small_meshes: false,
+            vertex_format: VertexFormat::default(),
+            lighting: Lighting::default(),
+            transparency: TransparencyMode::Sorted,
+            display_mode: DisplayMode::Shaded,
+            edge_color: Vec3::new(0.0, 0.0, 0.0),
+            crease_angle: DEFAULT_CREASE_ANGLE,
+            clip_planes: Vec::new(),
+            clip_planes_file: None,
+            cap_color: Vec3::new(0.8, 0.2, 0.2),
+        }
+    }
+}
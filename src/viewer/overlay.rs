@@ -0,0 +1,162 @@
+use std::{sync::Arc, time::Instant};
+
+use anyhow::{bail, Result};
+use egui::{Event, Modifiers, PointerButton, Pos2, RawInput, Rect, Vec2};
+use glow::{Context, HasContext};
+use glutin::event::{ElementState, MouseButton, MouseScrollDelta, WindowEvent};
+
+use crate::gl_call;
+
+/// The number of points scrolled per line of a mouse wheel.
+const SCROLL_LINE_HEIGHT: f32 = 24.0;
+
+/// An immediate mode GUI drawn on top of the frame of the viewer controller.
+pub struct Overlay {
+    egui_context: egui::Context,
+    painter: egui_glow::Painter,
+    raw_input: RawInput,
+    pointer_pos: Pos2,
+    start_time: Instant,
+}
+
+impl Overlay {
+    /// Creates a new overlay for the given OpenGL context.
+    ///
+    /// # Arguments
+    /// * `gl` - The GLOW context the overlay is drawn with.
+    pub fn new(gl: Arc<Context>) -> Result<Self> {
+        let painter = match egui_glow::Painter::new(gl, "", None) {
+            Ok(painter) => painter,
+            Err(err) => {
+                bail!("Failed to create the overlay painter due to {}", err);
+            }
+        };
+
+        Ok(Self {
+            egui_context: egui::Context::default(),
+            painter,
+            raw_input: RawInput::default(),
+            pointer_pos: Pos2::ZERO,
+            start_time: Instant::now(),
+        })
+    }
+
+    /// Passes the given window event to the GUI. Returns true if the event has been consumed by
+    /// the GUI, i.e., it must not be passed to the viewer controller.
+    ///
+    /// # Arguments
+    /// * `event` - The window event.
+    /// * `scale_factor` - The ratio between physical and logical pixels.
+    pub fn handle_event(&mut self, event: &WindowEvent, scale_factor: f64) -> bool {
+        match event {
+            WindowEvent::CursorMoved { position, .. } => {
+                let position = position.to_logical::<f32>(scale_factor);
+                self.pointer_pos = Pos2::new(position.x, position.y);
+                self.raw_input
+                    .events
+                    .push(Event::PointerMoved(self.pointer_pos));
+
+                false
+            }
+            WindowEvent::CursorLeft { .. } => {
+                self.raw_input.events.push(Event::PointerGone);
+
+                false
+            }
+            WindowEvent::MouseInput { state, button, .. } => {
+                let button = match button {
+                    MouseButton::Left => PointerButton::Primary,
+                    MouseButton::Right => PointerButton::Secondary,
+                    MouseButton::Middle => PointerButton::Middle,
+                    MouseButton::Other(_) => return false,
+                };
+
+                let pressed = *state == ElementState::Pressed;
+                self.raw_input.events.push(Event::PointerButton {
+                    pos: self.pointer_pos,
+                    button,
+                    pressed,
+                    modifiers: self.raw_input.modifiers,
+                });
+
+                // releases are always passed on, s.t. the controller can finish its interaction
+                pressed && self.egui_context.is_pointer_over_area()
+            }
+            WindowEvent::MouseWheel { delta, .. } => {
+                let delta = match delta {
+                    MouseScrollDelta::LineDelta(x, y) => Vec2::new(*x, *y) * SCROLL_LINE_HEIGHT,
+                    MouseScrollDelta::PixelDelta(delta) => {
+                        let delta = delta.to_logical::<f32>(scale_factor);
+                        Vec2::new(delta.x, delta.y)
+                    }
+                };
+                self.raw_input.events.push(Event::Scroll(delta));
+
+                self.egui_context.is_pointer_over_area()
+            }
+            WindowEvent::ModifiersChanged(modifiers) => {
+                let mac_cmd = cfg!(target_os = "macos") && modifiers.logo();
+                self.raw_input.modifiers = Modifiers {
+                    alt: modifiers.alt(),
+                    ctrl: modifiers.ctrl(),
+                    shift: modifiers.shift(),
+                    mac_cmd,
+                    command: mac_cmd || (!cfg!(target_os = "macos") && modifiers.ctrl()),
+                };
+
+                false
+            }
+            _ => false,
+        }
+    }
+
+    /// Runs the given UI function and draws the resulting GUI on top of the current frame.
+    ///
+    /// # Arguments
+    /// * `width` - The width of the frame in pixels.
+    /// * `height` - The height of the frame in pixels.
+    /// * `scale_factor` - The ratio between physical and logical pixels.
+    /// * `build_ui` - The function which adds the widgets to the GUI.
+    pub fn draw<F: FnOnce(&egui::Context)>(
+        &mut self,
+        width: u32,
+        height: u32,
+        scale_factor: f64,
+        build_ui: F,
+    ) {
+        let pixels_per_point = scale_factor as f32;
+
+        // the modifiers are a state and must be kept for the next frame
+        let mut raw_input = std::mem::take(&mut self.raw_input);
+        self.raw_input.modifiers = raw_input.modifiers;
+
+        raw_input.screen_rect = Some(Rect::from_min_size(
+            Pos2::ZERO,
+            Vec2::new(width as f32, height as f32) / pixels_per_point,
+        ));
+        raw_input.pixels_per_point = Some(pixels_per_point);
+        raw_input.max_texture_side = Some(self.painter.max_texture_side());
+        raw_input.time = Some(self.start_time.elapsed().as_secs_f64());
+
+        let output = self.egui_context.run(raw_input, build_ui);
+        let primitives = self.egui_context.tessellate(output.shapes);
+
+        self.painter.paint_and_update_textures(
+            [width, height],
+            pixels_per_point,
+            &primitives,
+            &output.textures_delta,
+        );
+
+        // restore the state changed by the painter, which is expected by the controller
+        let gl = self.painter.gl().as_ref();
+        gl_call!(gl, disable, glow::SCISSOR_TEST);
+        gl_call!(gl, disable, glow::BLEND);
+        gl_call!(gl, enable, glow::DEPTH_TEST);
+    }
+
+    /// Deletes all GPU resources of the overlay.
+    pub fn destroy(&mut self) {
+        self.painter.destroy();
+    }
+}
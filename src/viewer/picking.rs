@@ -0,0 +1,129 @@
+use cad_import::ID;
+use glow::HasContext;
+use nalgebra_glm::{determinant, inverse, Vec3};
+
+use crate::{
+    geometry::{Plane, Ray},
+    gpu_data::GPUData,
+};
+
+/// The tolerance for detecting non-invertible instance transformations.
+const SINGULAR_EPSILON: f32 = 1e-12;
+
+/// The result of picking the scene with a ray.
+#[derive(Debug, Clone, Copy)]
+pub struct PickResult {
+    /// The index of the hit shape instance within the instances of the GPU data.
+    pub instance_index: usize,
+
+    /// The ID of the node the hit shape instance is attached to.
+    pub node_id: ID,
+
+    /// The ID of the hit shape.
+    pub shape_id: ID,
+
+    /// The index of the hit part within the parts of the shape.
+    pub part_index: usize,
+
+    /// The hit point in world coordinates.
+    pub position: Vec3,
+}
+
+/// Returns the closest hit of the given world space ray with the visible shape instances of
+/// the GPU data. The intersection is computed on the CPU meshes the GPU meshes have been created from.
+/// Hits cut away by any of the clip planes are skipped, s.t. only the visible surfaces are
+/// picked.
+///
+/// # Arguments
+/// * `gpu_data` - The GPU data whose shape instances are tested.
+/// * `ray` - The ray in world coordinates.
+/// * `clip_planes` - The enabled clip planes in world coordinates.
+pub fn pick<C: HasContext>(
+    gpu_data: &GPUData<C>,
+    ray: &Ray,
+    clip_planes: &[Plane],
+) -> Option<PickResult> {
+    let mut closest: Option<(f32, PickResult)> = None;
+
+    gpu_data.query_ray(ray, |instance_index| {
+        let instance = &gpu_data.get_instances()[instance_index];
+        if !instance.visible || determinant(&instance.transform).abs() <= SINGULAR_EPSILON {
+            return None;
+        }
+
+        // the ray parameters of the local ray are the same as for the world ray
+        let local_ray = ray.transform(&inverse(&instance.transform));
+        let shape = &gpu_data.get_shapes()[instance.shape_index];
+
+        let mut instance_hit: Option<f32> = None;
+        for (part_index, part) in shape.parts.iter().enumerate() {
+            let hit =
+                local_ray.intersect_mesh(&part.cpu_mesh, |t| !is_clipped(&ray.at(t), clip_planes));
+            let t = match hit {
+                Some(t) => t,
+                None => continue,
+            };
+
+            if instance_hit.is_none_or(|instance_t| t < instance_t) {
+                instance_hit = Some(t);
+            }
+
+            if closest.is_none_or(|(closest_t, _)| t < closest_t) {
+                closest = Some((
+                    t,
+                    PickResult {
+                        instance_index,
+                        node_id: instance.node_id,
+                        shape_id: shape.shape_id,
+                        part_index,
+                        position: ray.at(t),
+                    },
+                ));
+            }
+        }
+
+        instance_hit
+    });
+
+    closest.map(|(_, result)| result)
+}
+
+/// Returns true if the given point is cut away by any of the clip planes, i.e., it is behind
+/// one of the planes.
+///
+/// # Arguments
+/// * `p` - The point in world coordinates.
+/// * `clip_planes` - The enabled clip planes in world coordinates.
+fn is_clipped(p: &Vec3, clip_planes: &[Plane]) -> bool {
+    clip_planes
+        .iter()
+        .any(|plane| plane.signed_distance(p) < 0f32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_clipped() {
+        let p = Vec3::new(1.0, 2.0, 3.0);
+        assert!(!is_clipped(&p, &[]));
+
+        let x = Plane::new(Vec3::new(1.0, 0.0, 0.0), 0.5);
+        let z = Plane::new(Vec3::new(0.0, 0.0, 1.0), 2.0);
+        assert!(!is_clipped(&p, &[x, z]));
+
+        // behind a single plane is enough to be cut away
+        assert!(is_clipped(&p, &[x, z.flip()]));
+        assert!(is_clipped(
+            &p,
+            &[Plane::new(Vec3::new(0.0, 1.0, 0.0), 2.5), x]
+        ));
+
+        // points on the plane are kept like by the clip distances of the shaders
+        assert!(!is_clipped(
+            &p,
+            &[Plane::new(Vec3::new(0.0, 1.0, 0.0), 2.0)]
+        ));
+    }
+}
@@ -0,0 +1,162 @@
+use cad_import::structure::Material;
+
+/// The shader state required by a draw item. The order of the fields defines the sort order of
+/// the render queue, i.e., the most expensive state changes come first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct DrawState {
+    /// The material of the drawn part, identified by its address.
+    pub material: *const Material,
+
+    /// Flag indicating if the drawn part has normals.
+    pub normals_enabled: bool,
+
+    /// The OpenGL primitive type of the drawn part.
+    pub primitive_type: u32,
+}
+
+/// A single draw-call of the render queue.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum DrawItem {
+    /// Draws a part of a shape for a range of instances of the instance buffer.
+    Instanced {
+        /// The index of the shape within the shapes of the GPU data.
+        shape_index: usize,
+
+        /// The index of the part within the parts of the shape.
+        part_index: usize,
+
+        /// The level of detail of the part.
+        lod: usize,
+
+        /// The index of the first instance within the instance buffer.
+        first_instance: usize,
+
+        /// The number of instances to draw.
+        num_instances: usize,
+    },
+
+    /// Draws a range of indices of a merged mesh once.
+    Merged {
+        /// The index of the merged mesh within the merged meshes of the GPU data.
+        merged_mesh_index: usize,
+
+        /// The index of the first index to draw.
+        first_index: usize,
+
+        /// The number of indices to draw.
+        num_indices: usize,
+
+        /// The index of the instance within the instance buffer used for drawing the range.
+        instance: usize,
+    },
+
+    /// Draws the feature edges of a shape for a range of instances of the instance buffer.
+    Edges {
+        /// The index of the shape within the shapes of the GPU data.
+        shape_index: usize,
+
+        /// The index of the first instance within the instance buffer.
+        first_instance: usize,
+
+        /// The number of instances to draw.
+        num_instances: usize,
+    },
+}
+
+/// The draw items of a frame. The opaque draw items are sorted by the shader state they
+/// require, the transparent ones by their view depth.
+#[derive(Debug, Default)]
+pub struct RenderQueue {
+    items: Vec<(DrawState, DrawItem)>,
+
+    /// The transparent draw items together with their distance to the camera.
+    transparent_items: Vec<(f32, DrawState, DrawItem)>,
+
+    /// The draw items of the feature edges, drawn after all surfaces.
+    edge_items: Vec<(DrawState, DrawItem)>,
+}
+
+impl RenderQueue {
+    /// Creates a new empty render queue.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the given draw item to the queue.
+    ///
+    /// # Arguments
+    /// * `state` - The shader state required by the draw item.
+    /// * `item` - The draw item.
+    pub fn push(&mut self, state: DrawState, item: DrawItem) {
+        self.items.push((state, item));
+    }
+
+    /// Adds the given transparent draw item to the queue.
+    ///
+    /// # Arguments
+    /// * `depth` - The distance of the drawn instance to the camera along the view direction.
+    /// * `state` - The shader state required by the draw item.
+    /// * `item` - The draw item.
+    pub fn push_transparent(&mut self, depth: f32, state: DrawState, item: DrawItem) {
+        self.transparent_items.push((depth, state, item));
+    }
+
+    /// Adds the given draw item of feature edges to the queue.
+    ///
+    /// # Arguments
+    /// * `state` - The shader state required by the draw item.
+    /// * `item` - The draw item.
+    pub fn push_edges(&mut self, state: DrawState, item: DrawItem) {
+        self.edge_items.push((state, item));
+    }
+
+    /// Sorts the opaque draw items by their shader state, s.t. draw items with the same state
+    /// are drawn consecutively. The transparent draw items are sorted back-to-front.
+    pub fn sort(&mut self) {
+        self.items.sort_unstable();
+        self.transparent_items
+            .sort_unstable_by(|(d0, s0, i0), (d1, s1, i1)| {
+                d1.total_cmp(d0).then_with(|| (s0, i0).cmp(&(s1, i1)))
+            });
+    }
+
+    /// Returns an iterator over the opaque draw items together with their shader state.
+    pub fn iter(&self) -> impl Iterator<Item = (&DrawState, &DrawItem)> {
+        self.items.iter().map(|(state, item)| (state, item))
+    }
+
+    /// Returns an iterator over the transparent draw items together with their shader state.
+    pub fn iter_transparent(&self) -> impl Iterator<Item = (&DrawState, &DrawItem)> {
+        self.transparent_items
+            .iter()
+            .map(|(_, state, item)| (state, item))
+    }
+
+    /// Returns an iterator over the draw items of the feature edges together with their shader
+    /// state.
+    pub fn iter_edges(&self) -> impl Iterator<Item = (&DrawState, &DrawItem)> {
+        self.edge_items.iter().map(|(state, item)| (state, item))
+    }
+
+    /// Returns true if the queue contains any transparent draw items.
+    pub fn has_transparent(&self) -> bool {
+        !self.transparent_items.is_empty()
+    }
+}
+
+/// The statistics of a single drawn frame.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FrameStats {
+    /// The number of drawn instances.
+    pub num_instances: usize,
+
+    /// The number of issued draw-calls.
+    pub num_draw_calls: usize,
+
+    /// The number of uniform uploads due to changes of the shader state.
+    pub num_state_changes: usize,
+
+    /// The number of draw-calls for capping the clipped surfaces, i.e., the surfaces drawn
+    /// again into the stencil buffer for each clip plane, which are not part of the other counts.
+    pub num_capping_draw_calls: usize,
+}
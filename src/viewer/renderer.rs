@@ -1,101 +1,988 @@
-use crate::{gl_call, gpu_data::GPUData};
+use std::{
+    collections::HashMap,
+    ops::Range,
+    path::PathBuf,
+    rc::Rc,
+    sync::mpsc::TryRecvError,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    geometry::{compute_normal_matrix, BBox, Frustum, Plane, Ray},
+    gl_call,
+    gpu_data::{
+        compute_shape_bbox, is_transparent, GPUData, InstanceAttributes, InstanceBuffer,
+        UploadBudget,
+    },
+    loading::{BackgroundLoader, LoadedCADData},
+};
 
 use super::{
-    bbox::BBox,
     camera::Camera,
+    clipping::{enable_clip_distances, ClipPlanes, SectionCapping, MAX_CLIP_PLANES},
+    display_mode::DisplayMode,
+    options::RendererOptions,
+    picking::{self, PickResult},
+    render_queue::{DrawItem, DrawState, FrameStats, RenderQueue},
+    selection::{SelectedNode, Selection},
     shader::Shader,
+    transparency::{TransparencyMode, WeightedBlendedOIT},
     viewer::{ContextConfig, ViewerController},
 };
 
-use cad_import::structure::{CADData, Node};
+use cad_import::{
+    structure::{CADData, Node},
+    ID,
+};
+use egui::collapsing_header::CollapsingState;
 use glow::HasContext;
 
-use glutin::event::{MouseButton, VirtualKeyCode};
+use glutin::event::{ModifiersState, MouseButton, VirtualKeyCode};
 use log::{debug, error, info, trace, warn};
-use nalgebra_glm::{determinant, inverse, mat4_to_mat3, transpose, vec4_to_vec3, Mat3, Mat4, Vec4};
+use nalgebra_glm::{Mat3, Mat4, Vec4};
+
+/// The maximal distance in logical pixels the cursor may move between pressing and releasing
+/// a mouse button to be still considered a click.
+const CLICK_TOLERANCE: f64 = 3.0;
+
+/// The projected size in pixels below which instances are drawn with coarser levels of detail.
+const LOD_FULL_DETAIL_SIZE: f32 = 128.0;
+
+/// The number of steps for moving a clip plane with the keyboard through the whole scene.
+const CLIP_PLANE_STEPS: f32 = 50.0;
+
+/// The file the clip planes are saved to if no file is given in the options.
+const DEFAULT_CLIP_PLANES_FILE: &str = "clip_planes.txt";
 
 pub struct Renderer<C: HasContext> {
     shader: Option<Shader<C>>,
+    instance_buffer: Option<InstanceBuffer<C>>,
+    oit: Option<WeightedBlendedOIT<C>>,
+    capping: Option<SectionCapping<C>>,
+    framebuffer: Option<C::Framebuffer>,
     shader_version: String,
-    cad_data: CADData,
+    cad_data: Option<CADData>,
+    options: RendererOptions,
     scene_volume: BBox,
     camera: Camera,
     gpu_data: GPUData<C>,
+    loader: Option<BackgroundLoader>,
+    loading_failed: bool,
+    start_time: Instant,
+    upload_start_time: Instant,
     width: u32,
     height: u32,
+    scale_factor: f64,
+    click_start: Option<[f64; 2]>,
+    modifiers: ModifiersState,
+    selection: Selection,
+    show_scene_tree: bool,
+    show_frame_stats: bool,
+    frame_stats: FrameStats,
+    display_mode: DisplayMode,
+    clip_planes: ClipPlanes,
+    clip_plane_drag: Option<f64>,
 }
 
 impl<C: HasContext> Renderer<C> {
-    pub fn new(cad_data: CADData) -> Self {
+    /// Creates a new renderer which renders the CAD data provided by the given loader. Until
+    /// the CAD data has been loaded, a loading indicator is shown.
+    ///
+    /// # Arguments
+    /// * `loader` - The loader providing the CAD data to render.
+    /// * `options` - The options to configure the renderer.
+    pub fn new(loader: BackgroundLoader, options: RendererOptions) -> Self {
         let gpu_data = GPUData::new();
-        let mut scene_volume = BBox::new();
-        Self::compute_bbox(
-            cad_data.get_root_node(),
-            Mat4::identity(),
-            &mut scene_volume,
-        );
 
         let mut camera = Camera::new();
-        camera.focus(&scene_volume).unwrap();
+        camera.set_view(options.initial_view);
+        let display_mode = options.display_mode;
+        let clip_planes = ClipPlanes::new(options.clip_planes.clone());
 
         Self {
             shader: None,
+            instance_buffer: None,
+            oit: None,
+            capping: None,
+            framebuffer: None,
             shader_version: String::new(),
-            cad_data,
-            scene_volume,
+            cad_data: None,
+            options,
+            scene_volume: BBox::new(),
             camera,
             gpu_data,
+            loader: Some(loader),
+            loading_failed: false,
+            start_time: Instant::now(),
+            upload_start_time: Instant::now(),
             width: 0,
             height: 0,
+            scale_factor: 1f64,
+            click_start: None,
+            modifiers: ModifiersState::empty(),
+            selection: Selection::new(),
+            show_scene_tree: true,
+            show_frame_stats: false,
+            frame_stats: FrameStats::default(),
+            display_mode,
+            clip_planes,
+            clip_plane_drag: None,
         }
     }
 
-    fn compute_normal_matrix(m: &Mat4) -> Mat3 {
-        let m = mat4_to_mat3(m);
+    /// Checks the loader for new CAD data and replaces the GPU data if there is any.
+    ///
+    /// # Arguments
+    /// * `context` - The GLOW context.
+    fn poll_loader(&mut self, context: &C) {
+        let loader = match &self.loader {
+            Some(loader) => loader,
+            None => return,
+        };
+
+        // only the most recent result is of interest
+        let mut latest = None;
+        loop {
+            match loader.try_recv() {
+                Ok(result) => latest = Some(result),
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    self.loader = None;
+                    break;
+                }
+            }
+        }
+
+        match latest {
+            Some(Ok(loaded)) => self.replace_cad_data(context, loaded),
+            Some(Err(err)) => {
+                if self.cad_data.is_none() {
+                    error!("Failed loading the CAD data due to {:#}", err);
+                    self.loading_failed = true;
+                } else {
+                    error!("Failed reloading, keep previous CAD data: {:#}", err);
+                }
+            }
+            None => {
+                if self.loader.is_none() && self.cad_data.is_none() && !self.loading_failed {
+                    error!("Loader stopped without providing any CAD data");
+                    self.loading_failed = true;
+                }
+            }
+        }
+    }
+
+    /// Replaces the rendered CAD data by the given CAD data. The old GPU data is freed. The
+    /// camera is only focused onto the new data if there was no data before.
+    ///
+    /// # Arguments
+    /// * `context` - The GLOW context.
+    /// * `loaded` - The new CAD data together with the levels of detail of its shapes.
+    fn replace_cad_data(&mut self, context: &C, loaded: LoadedCADData) {
+        let (cad_data, shape_lods) = loaded.into_parts();
+
+        info!("Transfer CPU data to GPU...");
+        self.gpu_data.cleanup(context);
+        self.upload_start_time = Instant::now();
+
+        // the instance indices of the selection refer to the old data
+        self.clear_selection();
+
+        // the local volumes of the shapes are computed once and reused for the GPU shapes
+        let mut shape_bboxes = HashMap::new();
+        let mut scene_volume = BBox::new();
+        Self::compute_bbox(
+            cad_data.get_root_node(),
+            Mat4::identity(),
+            &mut shape_bboxes,
+            &mut scene_volume,
+        );
+        self.scene_volume = scene_volume;
 
-        let d: f32 = determinant(&m);
-        if d.abs() <= 1e-9 {
-            m
+        let camera_result = if self.cad_data.is_none() {
+            self.camera.focus(&self.scene_volume)
         } else {
-            transpose(&inverse(&m))
+            self.camera.update_scene(&self.scene_volume)
+        };
+        if let Err(err) = camera_result {
+            error!("Failed to update camera for the scene due to {}", err);
+        }
+
+        self.gpu_data.begin_upload(
+            &cad_data,
+            shape_bboxes,
+            shape_lods,
+            self.options.vertex_format,
+            self.options.crease_angle,
+        );
+        self.cad_data = Some(cad_data);
+    }
+
+    /// Continues the upload of the CAD data to the GPU within the given budget.
+    ///
+    /// # Arguments
+    /// * `context` - The GLOW context.
+    /// * `budget` - The budget for this step of the upload.
+    fn continue_upload(&mut self, context: &C, budget: &UploadBudget) {
+        let cad_data = match &self.cad_data {
+            Some(cad_data) => cad_data,
+            None => return,
+        };
+
+        if !self.gpu_data.is_uploading() {
+            return;
+        }
+
+        match self.gpu_data.continue_upload(context, cad_data, budget) {
+            Ok(true) => {
+                if self.options.merge_small_meshes {
+                    match self.gpu_data.merge_small_meshes(context) {
+                        Ok(num_merged) => info!("Merged {} small shape instances", num_merged),
+                        Err(err) => error!("Failed to merge small meshes due to {}", err),
+                    }
+                }
+
+                let memory_stats = self.gpu_data.get_memory_stats();
+                info!(
+                    "Transfer CPU data to GPU...DONE ({} shapes, {} instances, {:.1} MiB in {:?})",
+                    self.gpu_data.get_shapes().len(),
+                    self.gpu_data.get_instances().len(),
+                    memory_stats.total() as f64 / (1024f64 * 1024f64),
+                    self.upload_start_time.elapsed()
+                );
+                info!(
+                    "Vertex data: {:.2} MiB, {:.2} MiB as 32-bit floats",
+                    memory_stats.num_vertex_bytes() as f64 / (1024f64 * 1024f64),
+                    self.gpu_data.get_num_uncompressed_vertex_bytes() as f64 / (1024f64 * 1024f64)
+                );
+                info!("GPU memory: {}", memory_stats);
+            }
+            Ok(false) => {}
+            Err(err) => {
+                error!("Failed to transfer CAD data to GPU due to {}", err);
+            }
         }
     }
 
-    /// Computes the bounding volume for the given node and all its children recursively.
+    /// Returns the closest shape instance under the given cursor position if there is any.
+    ///
+    /// # Arguments
+    /// * `x` - The x coordinate of the cursor in logical coordinates.
+    /// * `y` - The y coordinate of the cursor in logical coordinates.
+    pub fn pick(&mut self, x: f64, y: f64) -> Option<PickResult> {
+        if self.width == 0 || self.height == 0 {
+            return None;
+        }
+
+        let x = (x * self.scale_factor) as f32 / self.width as f32;
+        let y = (y * self.scale_factor) as f32 / self.height as f32;
+
+        self.camera.update_window_size(self.width, self.height);
+        let camera_data = self.camera.get_data();
+        let combined_mat = camera_data.get_projection_matrix() * camera_data.get_model_matrix();
+
+        let ray = Ray::from_ndc(&combined_mat, x * 2f32 - 1f32, 1f32 - y * 2f32);
+
+        picking::pick(&self.gpu_data, &ray, self.clip_planes.get_planes())
+    }
+
+    /// Returns the statistics of the last drawn frame.
+    pub fn get_frame_stats(&self) -> FrameStats {
+        self.frame_stats
+    }
+
+    /// Returns the selected shape instances together with the nodes they are attached to.
+    pub fn get_selected_nodes(&self) -> Vec<SelectedNode> {
+        let instances = self.gpu_data.get_instances();
+        let nodes = self.gpu_data.get_nodes();
+
+        self.selection
+            .iter()
+            .map(|instance_index| {
+                let instance = &instances[instance_index];
+
+                SelectedNode {
+                    instance_index,
+                    node_id: instance.node_id,
+                    label: nodes[instance.node_index].label.clone(),
+                }
+            })
+            .collect()
+    }
+
+    /// Updates the selection by picking the scene at the given cursor position. With the
+    /// control key pressed, the selection state of the hit instance is toggled. Otherwise, the
+    /// selection is replaced by the hit instance.
+    ///
+    /// # Arguments
+    /// * `x` - The x coordinate of the cursor in logical coordinates.
+    /// * `y` - The y coordinate of the cursor in logical coordinates.
+    fn click(&mut self, x: f64, y: f64) {
+        let result = self.pick(x, y);
+        match &result {
+            Some(result) => info!(
+                "Picked instance {} of node ID {}, shape ID {}, part {} at ({}, {}, {})",
+                result.instance_index,
+                result.node_id,
+                result.shape_id,
+                result.part_index,
+                result.position[0],
+                result.position[1],
+                result.position[2]
+            ),
+            None => info!("Picked nothing"),
+        }
+
+        let toggle = self.modifiers.ctrl();
+        match (result, toggle) {
+            (Some(result), true) => self.selection.toggle(result.instance_index),
+            (Some(result), false) => self.selection.select(result.instance_index),
+            (None, true) => return,
+            (None, false) => {
+                self.clear_selection();
+                return;
+            }
+        }
+
+        self.log_selection();
+    }
+
+    /// Clears the selection and logs the change if anything was selected.
+    fn clear_selection(&mut self) {
+        if !self.selection.is_empty() {
+            self.selection.clear();
+            self.log_selection();
+        }
+    }
+
+    /// Logs the currently selected nodes.
+    fn log_selection(&self) {
+        info!("Selection: {} instance(s)", self.selection.len());
+        for selected in self.get_selected_nodes() {
+            info!(
+                "  Node '{}' (ID {}), instance {}",
+                selected.label, selected.node_id, selected.instance_index
+            );
+        }
+    }
+
+    /// Hides all instances except for the selected ones.
+    fn isolate_selection(&mut self) {
+        if self.selection.is_empty() {
+            warn!("Cannot isolate, nothing is selected");
+            return;
+        }
+
+        info!("Isolate {} selected instance(s)", self.selection.len());
+        let num_instances = self.gpu_data.get_instances().len();
+        self.gpu_data.set_visible(0..num_instances, false);
+        for instance_index in self.selection.iter() {
+            self.gpu_data
+                .set_visible(instance_index..instance_index + 1, true);
+        }
+    }
+
+    /// Hides the selected instances and clears the selection.
+    fn hide_selection(&mut self) {
+        if self.selection.is_empty() {
+            warn!("Cannot hide, nothing is selected");
+            return;
+        }
+
+        info!("Hide {} selected instance(s)", self.selection.len());
+        for instance_index in self.selection.iter() {
+            self.gpu_data
+                .set_visible(instance_index..instance_index + 1, false);
+        }
+
+        self.clear_selection();
+    }
+
+    /// Shows all instances.
+    fn unhide_all(&mut self) {
+        info!("Unhide all");
+        let num_instances = self.gpu_data.get_instances().len();
+        self.gpu_data.set_visible(0..num_instances, true);
+    }
+
+    /// Focuses the camera onto the selected instances or onto all visible instances if nothing
+    /// is selected. The clipping range still covers the whole scene.
+    fn focus_selection(&mut self) {
+        let instances: Vec<usize> = if self.selection.is_empty() {
+            info!("Focus on visible instances");
+            self.gpu_data
+                .get_instances()
+                .iter()
+                .enumerate()
+                .filter(|(_, instance)| instance.visible)
+                .map(|(instance_index, _)| instance_index)
+                .collect()
+        } else {
+            info!("Focus on {} selected instance(s)", self.selection.len());
+            self.selection.iter().collect()
+        };
+
+        let volume = self.compute_instances_bbox(instances);
+        if volume.is_empty() {
+            warn!("Cannot focus, the instances have no extent");
+            return;
+        }
+
+        let camera_result = self
+            .camera
+            .focus(&volume)
+            .and_then(|_| self.camera.update_scene(&self.scene_volume));
+        if let Err(err) = camera_result {
+            error!("Failed to focus on instances due to {}", err);
+        }
+    }
+
+    /// Adds a clip plane through the center of the scene, which is orthogonal to the next
+    /// coordinate axis, i.e., the x-axis for the first plane, the y-axis for the second one and
+    /// so on.
+    fn add_clip_plane(&mut self) {
+        if self.scene_volume.is_empty() {
+            warn!("Cannot add a clip plane, the scene has no extent");
+            return;
+        }
+
+        let plane = Plane::from_bbox_axis(&self.scene_volume, self.clip_planes.len());
+        if self.clip_planes.add(plane) {
+            self.log_active_clip_plane();
+        } else {
+            warn!(
+                "Cannot add a clip plane, at most {} are supported",
+                MAX_CLIP_PLANES
+            );
+        }
+    }
+
+    /// Removes the active clip plane.
+    fn remove_clip_plane(&mut self) {
+        if self.clip_planes.is_empty() {
+            warn!("Cannot remove a clip plane, there are none");
+            return;
+        }
+
+        info!("Remove clip plane {}", self.clip_planes.get_active_index());
+        self.clip_planes.remove_active();
+    }
+
+    /// Moves the active clip plane along its normal by the given fraction of the extent of the
+    /// scene along the normal.
+    ///
+    /// # Arguments
+    /// * `fraction` - The fraction of the extent to move the plane by.
+    fn move_clip_plane(&mut self, fraction: f32) {
+        let (min, max) = match self.clip_planes.get_active() {
+            Some(plane) => plane.get_distance_range(&self.scene_volume),
+            None => return,
+        };
+
+        self.clip_planes
+            .translate_active(fraction * (max - min), &self.scene_volume);
+        if let Some(plane) = self.clip_planes.get_active() {
+            debug!("Move clip plane to distance {}", plane.distance);
+        }
+    }
+
+    /// Logs the active clip plane.
+    fn log_active_clip_plane(&self) {
+        if let Some(plane) = self.clip_planes.get_active() {
+            info!(
+                "Clip plane {} of {}: normal ({}, {}, {}), distance {}",
+                self.clip_planes.get_active_index(),
+                self.clip_planes.len(),
+                plane.normal[0],
+                plane.normal[1],
+                plane.normal[2],
+                plane.distance
+            );
+        }
+    }
+
+    /// Saves the clip planes to the file given in the options or to the default file.
+    fn save_clip_planes(&self) {
+        let path = self
+            .options
+            .clip_planes_file
+            .clone()
+            .unwrap_or_else(|| PathBuf::from(DEFAULT_CLIP_PLANES_FILE));
+
+        match self.clip_planes.save(&path) {
+            Ok(()) => info!(
+                "Saved {} clip plane(s) to {}",
+                self.clip_planes.len(),
+                path.to_string_lossy()
+            ),
+            Err(err) => error!("Failed to save the clip planes due to {:#}", err),
+        }
+    }
+
+    /// Computes the bounding volume of the given instances in world coordinates.
+    ///
+    /// # Arguments
+    /// * `instances` - The indices of the instances.
+    fn compute_instances_bbox<I: IntoIterator<Item = usize>>(&self, instances: I) -> BBox {
+        let mut bbox = BBox::new();
+
+        for instance_index in instances {
+            bbox.extend_bbox(&self.gpu_data.get_instances()[instance_index].bbox);
+        }
+
+        bbox
+    }
+
+    /// Adds the given scene node and its expandable subtree to the scene tree panel. Each node
+    /// has a checkbox for toggling the visibility of its subtree.
+    ///
+    /// # Arguments
+    /// * `ui` - The UI to add the node to.
+    /// * `index` - The index of the scene node.
+    fn add_scene_node_ui(&mut self, ui: &mut egui::Ui, index: usize) {
+        let node = &self.gpu_data.get_nodes()[index];
+        let instances = node.instances.clone();
+        let children = node.children.clone();
+        let label = if node.label.is_empty() {
+            format!("Node {}", node.node_id)
+        } else {
+            node.label.clone()
+        };
+
+        let mut visible = self.gpu_data.is_any_visible(instances.clone());
+        let mut changed = false;
+
+        if children.is_empty() {
+            changed = ui.checkbox(&mut visible, label).changed();
+        } else {
+            let id = ui.make_persistent_id(("scene_node", node.node_id));
+            CollapsingState::load_with_default_open(ui.ctx(), id, node.parent.is_none())
+                .show_header(ui, |ui| {
+                    changed = ui.checkbox(&mut visible, label).changed();
+                })
+                .body(|ui| {
+                    for child in children {
+                        self.add_scene_node_ui(ui, child);
+                    }
+                });
+        }
+
+        if changed {
+            self.gpu_data.set_visible(instances, visible);
+        }
+    }
+
+    /// Returns the level of detail for an instance with the given projected size in pixels. The
+    /// full detail is used down to `LOD_FULL_DETAIL_SIZE` pixels and each coarser level halves the
+    /// size.
+    ///
+    /// # Arguments
+    /// * `projected_size` - The size of the instance on the screen in pixels.
+    /// * `max_lod` - The coarsest available level of detail.
+    fn select_lod(projected_size: f32, max_lod: usize) -> usize {
+        let mut lod = 0;
+        let mut size = LOD_FULL_DETAIL_SIZE;
+        while projected_size < size && lod < max_lod {
+            lod += 1;
+            size *= 0.5;
+        }
+
+        lod
+    }
+
+    /// Creates the render queue for drawing the given shape instances. The attributes of the
+    /// instances referenced by the draw items are appended to the given list. For sorted
+    /// transparency, the transparent parts are drawn for each instance on its own.
+    ///
+    /// # Arguments
+    /// * `drawn_instances` - The indices of the instances to draw in ascending order.
+    /// * `attributes` - The list of instance attributes to be uploaded to the instance buffer.
+    fn build_render_queue(
+        &self,
+        drawn_instances: &[usize],
+        attributes: &mut Vec<InstanceAttributes>,
+    ) -> RenderQueue {
+        let instances = self.gpu_data.get_instances();
+        let mut queue = RenderQueue::new();
+
+        // group the individually drawn instances by their shape and level of detail, s.t. each
+        // shape is drawn at once per level
+        let camera_data = self.camera.get_data();
+        let shapes = self.gpu_data.get_shapes();
+        let mut individual_instances: Vec<(usize, usize, usize)> = drawn_instances
+            .iter()
+            .copied()
+            .filter(|instance_index| !instances[*instance_index].merged)
+            .map(|instance_index| {
+                let instance = &instances[instance_index];
+                let max_lod = shapes[instance.shape_index]
+                    .parts
+                    .iter()
+                    .map(|part| part.lods.len())
+                    .max()
+                    .unwrap_or(0);
+
+                let size = camera_data.get_projected_size(&instance.bbox);
+                let lod = Self::select_lod(size, max_lod);
+
+                (instance.shape_index, lod, instance_index)
+            })
+            .collect();
+        individual_instances.sort_unstable();
+
+        let mut groups: Vec<(usize, usize, usize)> = Vec::new();
+        for &(shape_index, lod, instance_index) in individual_instances.iter() {
+            // the positions stored on the GPU are mapped into the local shape coordinates first
+            let instance = &instances[instance_index];
+            attributes.push(InstanceAttributes::new(
+                &(instance.transform * shapes[shape_index].position_transform),
+                &compute_normal_matrix(&instance.transform),
+                self.selection.contains(instance_index),
+            ));
+
+            match groups.last_mut() {
+                Some((group_shape_index, group_lod, num_instances))
+                    if *group_shape_index == shape_index && *group_lod == lod =>
+                {
+                    *num_instances += 1;
+                }
+                _ => groups.push((shape_index, lod, 1)),
+            }
+        }
+
+        // transparency is only visible for shaded surfaces
+        let blend_transparent = self.display_mode.has_lit_surfaces();
+        let edge_state = DrawState {
+            material: std::ptr::null(),
+            normals_enabled: false,
+            primitive_type: glow::LINES,
+        };
+
+        let model_view_matrix = camera_data.get_model_matrix();
+        let mut first_instance = 0;
+        for (shape_index, lod, num_instances) in groups {
+            let shape = &shapes[shape_index];
+            for (part_index, part) in shape.parts.iter().enumerate() {
+                let mesh = match part.get_lod_mesh(lod) {
+                    Some(mesh) => mesh,
+                    None => continue,
+                };
+                let state = DrawState {
+                    material: Rc::as_ptr(&part.material),
+                    normals_enabled: mesh.has_normals(),
+                    primitive_type: mesh.get_primitive_type(),
+                };
+
+                let item = |first_instance: usize, num_instances: usize| DrawItem::Instanced {
+                    shape_index,
+                    part_index,
+                    lod,
+                    first_instance,
+                    num_instances,
+                };
+
+                if !blend_transparent || !is_transparent(&part.material) {
+                    queue.push(state, item(first_instance, num_instances));
+                } else if self.options.transparency == TransparencyMode::WeightedBlended {
+                    queue.push_transparent(0f32, state, item(first_instance, num_instances));
+                } else {
+                    // the depth of an instance is the view depth of the center of its volume
+                    let group =
+                        &individual_instances[first_instance..first_instance + num_instances];
+                    for (k, (_, _, instance_index)) in group.iter().enumerate() {
+                        let c = instances[*instance_index].bbox.get_center();
+                        let p = model_view_matrix * Vec4::new(c[0], c[1], c[2], 1f32);
+                        queue.push_transparent(-p[2], state, item(first_instance + k, 1));
+                    }
+                }
+            }
+
+            if self.display_mode.has_edges() && shape.edges.is_some() {
+                queue.push_edges(
+                    edge_state,
+                    DrawItem::Edges {
+                        shape_index,
+                        first_instance,
+                        num_instances,
+                    },
+                );
+            }
+
+            first_instance += num_instances;
+        }
+
+        // the edges of the merged instances are drawn instanced grouped by their shape
+        if self.display_mode.has_edges() {
+            let mut merged_instances: Vec<(usize, usize)> = drawn_instances
+                .iter()
+                .map(|instance_index| (instances[*instance_index].shape_index, *instance_index))
+                .filter(|(shape_index, instance_index)| {
+                    instances[*instance_index].merged && shapes[*shape_index].edges.is_some()
+                })
+                .collect();
+            merged_instances.sort_unstable();
+
+            for group in merged_instances.chunk_by(|a, b| a.0 == b.0) {
+                let first_instance = attributes.len();
+                for &(shape_index, instance_index) in group {
+                    let instance = &instances[instance_index];
+                    attributes.push(InstanceAttributes::new(
+                        &(instance.transform * shapes[shape_index].position_transform),
+                        &compute_normal_matrix(&instance.transform),
+                        self.selection.contains(instance_index),
+                    ));
+                }
+
+                queue.push_edges(
+                    edge_state,
+                    DrawItem::Edges {
+                        shape_index: group[0].0,
+                        first_instance,
+                        num_instances: group.len(),
+                    },
+                );
+            }
+        }
+
+        let merged_meshes = self.gpu_data.get_merged_meshes();
+        if merged_meshes.is_empty() {
+            return queue;
+        }
+
+        // the merged meshes are drawn with an identity instance, either highlighted or not
+        let identity_instance = attributes.len();
+        for highlighted in [false, true] {
+            attributes.push(InstanceAttributes::new(
+                &Mat4::identity(),
+                &Mat3::identity(),
+                highlighted,
+            ));
+        }
+
+        let mut is_drawn = vec![false; instances.len()];
+        for &instance_index in drawn_instances.iter() {
+            is_drawn[instance_index] = instances[instance_index].merged;
+        }
+
+        for (merged_mesh_index, merged_mesh) in merged_meshes.iter().enumerate() {
+            let state = DrawState {
+                material: Rc::as_ptr(&merged_mesh.material),
+                normals_enabled: merged_mesh.mesh.has_normals(),
+                primitive_type: merged_mesh.mesh.get_primitive_type(),
+            };
+
+            let mut push = |indices: Range<usize>, instance: usize| {
+                queue.push(
+                    state,
+                    DrawItem::Merged {
+                        merged_mesh_index,
+                        first_index: indices.start,
+                        num_indices: indices.len(),
+                        instance,
+                    },
+                );
+            };
+
+            // consecutive ranges with the same highlighting are drawn at once
+            let mut pending: Option<(Range<usize>, usize)> = None;
+            for range in merged_mesh.ranges.iter() {
+                if !is_drawn[range.instance_index] {
+                    continue;
+                }
+
+                let instance = if self.selection.contains(range.instance_index) {
+                    identity_instance + 1
+                } else {
+                    identity_instance
+                };
+
+                match &mut pending {
+                    Some((indices, pending_instance))
+                        if indices.end == range.indices.start && *pending_instance == instance =>
+                    {
+                        indices.end = range.indices.end;
+                    }
+                    _ => {
+                        if let Some((indices, pending_instance)) =
+                            pending.replace((range.indices.clone(), instance))
+                        {
+                            push(indices, pending_instance);
+                        }
+                    }
+                }
+            }
+
+            if let Some((indices, instance)) = pending {
+                push(indices, instance);
+            }
+        }
+
+        queue
+    }
+
+    /// Draws the given draw items. The uniforms are only uploaded if the shader state changes.
+    ///
+    /// # Arguments
+    /// * `context` - The GLOW context.
+    /// * `shader` - The bound shader.
+    /// * `instance_buffer` - The instance buffer with the attributes of the drawn instances.
+    /// * `gpu_data` - The GPU data referenced by the draw items.
+    /// * `items` - The draw items together with their shader state.
+    /// * `stats` - The statistics of the frame, updated by the issued draw-calls.
+    fn draw_items<'a, I: Iterator<Item = (&'a DrawState, &'a DrawItem)>>(
+        context: &C,
+        shader: &Shader<C>,
+        instance_buffer: &InstanceBuffer<C>,
+        gpu_data: &GPUData<C>,
+        items: I,
+        stats: &mut FrameStats,
+    ) {
+        let shapes = gpu_data.get_shapes();
+        let merged_meshes = gpu_data.get_merged_meshes();
+        let mut current_state: Option<DrawState> = None;
+        for (state, item) in items {
+            let (material, mesh) = match *item {
+                DrawItem::Instanced {
+                    shape_index,
+                    part_index,
+                    lod,
+                    ..
+                } => {
+                    let part = &shapes[shape_index].parts[part_index];
+                    match part.get_lod_mesh(lod) {
+                        Some(mesh) => (Some(&part.material), mesh),
+                        None => continue,
+                    }
+                }
+                DrawItem::Merged {
+                    merged_mesh_index, ..
+                } => {
+                    let merged_mesh = &merged_meshes[merged_mesh_index];
+                    (Some(&merged_mesh.material), &merged_mesh.mesh)
+                }
+                DrawItem::Edges { shape_index, .. } => match &shapes[shape_index].edges {
+                    Some(edges) => (None, edges),
+                    None => continue,
+                },
+            };
+
+            // the edges are drawn with a solid color and don't need any material
+            if let Some(material) = material {
+                if current_state.is_none_or(|current| current.material != state.material) {
+                    shader.set_material(context, material);
+                    stats.num_state_changes += 1;
+                }
+            }
+
+            if current_state.is_none_or(|current| current.normals_enabled != state.normals_enabled)
+            {
+                shader.set_attributes(context, state.normals_enabled);
+                stats.num_state_changes += 1;
+            }
+
+            current_state = Some(*state);
+
+            match *item {
+                DrawItem::Instanced {
+                    first_instance,
+                    num_instances,
+                    ..
+                }
+                | DrawItem::Edges {
+                    first_instance,
+                    num_instances,
+                    ..
+                } => {
+                    mesh.draw_instanced(context, instance_buffer, first_instance, num_instances);
+                }
+                DrawItem::Merged {
+                    first_index,
+                    num_indices,
+                    instance,
+                    ..
+                } => {
+                    mesh.draw_range_instanced(
+                        context,
+                        instance_buffer,
+                        instance,
+                        1,
+                        first_index..first_index + num_indices,
+                    );
+                }
+            }
+
+            stats.num_draw_calls += 1;
+        }
+    }
+
+    /// Draws an animated progress bar while the CAD data is loading.
+    ///
+    /// # Arguments
+    /// * `context` - The GLOW context.
+    fn draw_loading_indicator(&self, context: &C) {
+        let bar_width = (self.width / 3) as i32;
+        let bar_height = (self.height / 64).max(4) as i32;
+        let x = (self.width as i32 - bar_width) / 2;
+        let y = (self.height as i32 - bar_height) / 2;
+
+        // the moving block bounces between both ends of the bar
+        let t = self.start_time.elapsed().as_secs_f32() * 0.75;
+        let t = 1f32 - (t.fract() * 2f32 - 1f32).abs();
+        let block_width = bar_width / 4;
+        let block_x = x + ((bar_width - block_width) as f32 * t) as i32;
+
+        gl_call!(context, enable, glow::SCISSOR_TEST);
+
+        gl_call!(context, scissor, x, y, bar_width, bar_height);
+        gl_call!(context, clear_color, 0.1, 0.1, 0.1, 1.0);
+        gl_call!(context, clear, glow::COLOR_BUFFER_BIT);
+
+        gl_call!(context, scissor, block_x, y, block_width, bar_height);
+        gl_call!(context, clear_color, 0.9, 0.9, 0.9, 1.0);
+        gl_call!(context, clear, glow::COLOR_BUFFER_BIT);
+
+        gl_call!(context, disable, glow::SCISSOR_TEST);
+    }
+
+    /// Computes the bounding volume for the given node and all its children recursively. The
+    /// volume is based on the transformed local volumes of the shapes, which are computed only
+    /// once per shape.
     ///
     /// # Arguments
     /// * `node` - The node which defines the subtree for which the bounding volume will be computed.
     /// * `transform` - The transformation to be applied
+    /// * `shape_bboxes` - The local bounding volumes of the shapes, updated for new shapes.
     /// * `bbox` - Mutable reference for the bounding volume to be updated.
-    fn compute_bbox(node: &Node, transform: Mat4, bbox: &mut BBox) {
+    fn compute_bbox(
+        node: &Node,
+        transform: Mat4,
+        shape_bboxes: &mut HashMap<ID, BBox>,
+        bbox: &mut BBox,
+    ) {
         // update transformation
         let transform = match node.get_transform() {
             Some(t) => transform * t,
             None => transform,
         };
 
-        // compute bounding volume for all parts
+        // extend the bounding volume by the transformed volumes of all shapes
         for shape in node.get_shapes() {
-            for part in shape.get_parts() {
-                let mesh = part.get_mesh();
-                let positions = mesh.get_vertices().get_positions();
+            let shape_bbox = shape_bboxes
+                .entry(shape.get_id())
+                .or_insert_with(|| compute_shape_bbox(shape));
 
-                for p in positions.iter() {
-                    let p = vec4_to_vec3(&(transform * Vec4::new(p.0.x, p.0.y, p.0.z, 1f32)));
-                    bbox.extend_pos(&p);
-                }
-            }
+            bbox.extend_bbox(&shape_bbox.transform(&transform));
         }
 
         // iterate over all children and update the global
         for child in node.get_children() {
-            Self::compute_bbox(child, transform, bbox);
+            Self::compute_bbox(child, transform, shape_bboxes, bbox);
         }
     }
 }
 
 impl<C: HasContext> ViewerController<C> for Renderer<C> {
-    fn initialize(&mut self, context: &C, context_config: ContextConfig) -> anyhow::Result<()> {
+    fn initialize(&mut self, context: &C, context_config: ContextConfig<C>) -> anyhow::Result<()> {
         info!("Initialize Renderer...");
 
         gl_call!(context, enable, glow::DEPTH_TEST);
@@ -103,18 +990,27 @@ impl<C: HasContext> ViewerController<C> for Renderer<C> {
         self.shader_version = context_config.shader_version;
         self.width = context_config.width;
         self.height = context_config.height;
+        self.scale_factor = context_config.scale_factor;
+        self.framebuffer = context_config.framebuffer;
 
         info!("Shader Version: {}", self.shader_version);
         self.shader = Some(Shader::new(context, &self.shader_version)?);
+        self.instance_buffer = Some(InstanceBuffer::new(context)?);
+        self.capping = Some(SectionCapping::new(context, &self.shader_version)?);
 
-        info!("Transfer CPU data to GPU...");
-        self.gpu_data.add_cad_data(context, &self.cad_data)?;
+        if self.options.transparency == TransparencyMode::WeightedBlended {
+            self.oit = Some(WeightedBlendedOIT::new(context, &self.shader_version)?);
+        }
 
         Ok(())
     }
 
     fn draw(&mut self, context: &C) {
         trace!("Draw");
+        self.poll_loader(context);
+        let upload_budget = self.options.upload_budget;
+        self.continue_upload(context, &upload_budget);
+
         gl_call!(
             context,
             viewport,
@@ -123,19 +1019,35 @@ impl<C: HasContext> ViewerController<C> for Renderer<C> {
             self.width as i32,
             self.height as i32
         );
-        gl_call!(context, clear_color, 0.2, 0.2, 1.0, 1.0);
+        let background = &self.options.background_color;
+        gl_call!(
+            context,
+            clear_color,
+            background[0],
+            background[1],
+            background[2],
+            1.0
+        );
         gl_call!(
             context,
             clear,
             glow::COLOR_BUFFER_BIT | glow::DEPTH_BUFFER_BIT
         );
 
-        let shader = match &self.shader {
-            Some(shader) => {
+        if self.cad_data.is_none() {
+            if !self.loading_failed {
+                self.draw_loading_indicator(context);
+            }
+
+            return;
+        }
+
+        let (shader, instance_buffer) = match (&self.shader, &self.instance_buffer) {
+            (Some(shader), Some(instance_buffer)) => {
                 shader.bind(context);
-                shader
+                (shader, instance_buffer)
             }
-            None => {
+            _ => {
                 warn!("Draw aborted -> Shader not ready");
                 return;
             }
@@ -143,42 +1055,258 @@ impl<C: HasContext> ViewerController<C> for Renderer<C> {
 
         self.camera.update_window_size(self.width, self.height);
         let model_view_matrix = self.camera.get_data().get_model_matrix();
-        let projection_matrix = self.camera.get_data().get_projection_matrix();
+        let combined_mat = self.camera.get_data().get_combined_matrix();
+        let normal_mat = compute_normal_matrix(&model_view_matrix);
+        let frustum = Frustum::from_matrix(&combined_mat);
 
-        let combined_mat = projection_matrix * model_view_matrix;
+        shader.set_matrices(context, &model_view_matrix, &combined_mat, &normal_mat);
+        shader.set_highlight_color(context, &self.options.highlight_color);
+        shader.set_normal_encoding(context, self.options.vertex_format.octahedral_normals);
+        shader.set_lights(
+            context,
+            &self.options.lighting.get_view_lights(&model_view_matrix),
+        );
+        shader.set_clip_planes(context, self.clip_planes.get_planes());
+        enable_clip_distances(context, self.clip_planes.len(), None);
 
-        for instance in self.gpu_data.get_instances() {
-            let normal_mat = Self::compute_normal_matrix(&(model_view_matrix * instance.transform));
-            let final_combined_mat = combined_mat * instance.transform;
+        let instances = self.gpu_data.get_instances();
+        let drawn_instances: Vec<usize> = self
+            .gpu_data
+            .query_frustum(&frustum)
+            .into_iter()
+            .filter(|instance_index| instances[*instance_index].visible)
+            .collect();
 
-            shader.set_matrices(
-                context,
-                &model_view_matrix,
-                &final_combined_mat,
-                &normal_mat,
-            );
+        let mut attributes = Vec::with_capacity(drawn_instances.len() + 2);
+        let mut queue = self.build_render_queue(&drawn_instances, &mut attributes);
+        queue.sort();
+        instance_buffer.set_instances(context, &attributes);
+
+        let mut stats = FrameStats {
+            num_instances: drawn_instances.len(),
+            ..FrameStats::default()
+        };
+
+        // the surfaces are pushed back, s.t. the edges on top of them pass the depth test
+        if !self.display_mode.has_filled_surfaces() {
+            gl_call!(context, polygon_mode, glow::FRONT_AND_BACK, glow::LINE);
+        }
+        if self.display_mode.has_polygon_offset() {
+            gl_call!(context, enable, glow::POLYGON_OFFSET_FILL);
+            gl_call!(context, polygon_offset, 1.0, 1.0);
+        }
 
-            let shape = &self.gpu_data.get_shapes()[instance.shape_index];
+        let surface_color = match self.display_mode {
+            DisplayMode::HiddenLine => Some(&self.options.background_color),
+            _ => None,
+        };
+        shader.set_solid_color(context, surface_color);
 
-            for part in shape.parts.iter() {
-                shader.set_material(context, &part.material);
+        Self::draw_items(
+            context,
+            shader,
+            instance_buffer,
+            &self.gpu_data,
+            queue.iter(),
+            &mut stats,
+        );
 
-                let normals_enabled = part.mesh.has_normals();
-                shader.set_attributes(context, normals_enabled);
+        // the surfaces cut open by the clip planes are capped before blending the transparent
+        // parts, where the caps are only visible for filled surfaces
+        if let Some(capping) = &self.capping {
+            if self.display_mode.has_filled_surfaces() {
+                let planes = self.clip_planes.get_planes();
+                let mut capping_stats = FrameStats::default();
+                for plane_index in 0..planes.len() {
+                    capping.begin_stencil(context, plane_index);
+                    shader.bind(context);
+                    Self::draw_items(
+                        context,
+                        shader,
+                        instance_buffer,
+                        &self.gpu_data,
+                        queue.iter().filter(|(state, _)| {
+                            matches!(
+                                state.primitive_type,
+                                glow::TRIANGLES | glow::TRIANGLE_STRIP | glow::TRIANGLE_FAN
+                            )
+                        }),
+                        &mut capping_stats,
+                    );
+                    capping.draw_cap(
+                        context,
+                        plane_index,
+                        planes,
+                        &combined_mat,
+                        &self.scene_volume,
+                        &self.options.cap_color,
+                    );
 
-                part.mesh.draw(context);
+                    // the cap is drawn as a single quad
+                    capping_stats.num_draw_calls += 1;
+                }
+
+                stats.num_capping_draw_calls = capping_stats.num_draw_calls;
+                shader.bind(context);
             }
         }
 
+        if queue.has_transparent() {
+            let mut oit = None;
+            if self.options.transparency == TransparencyMode::WeightedBlended {
+                if let Some(weighted_blended) = &mut self.oit {
+                    match weighted_blended.begin(context, self.framebuffer, self.width, self.height)
+                    {
+                        Ok(()) => oit = Some(&*weighted_blended),
+                        Err(err) => {
+                            error!(
+                                "Failed to set up order independent transparency, fall back to \
+                                 blending without sorting: {}",
+                                err
+                            );
+                            gl_call!(
+                                context,
+                                bind_framebuffer,
+                                glow::FRAMEBUFFER,
+                                self.framebuffer
+                            );
+                        }
+                    }
+                }
+            }
+
+            match oit {
+                Some(oit) => {
+                    // the opaque parts and the caps occlude the transparent fragments by the
+                    // depth copied from the frame
+                    oit.begin_accumulation(context);
+                    shader.set_weighted_blended(context, true);
+                    Self::draw_items(
+                        context,
+                        shader,
+                        instance_buffer,
+                        &self.gpu_data,
+                        queue.iter_transparent(),
+                        &mut stats,
+                    );
+                    shader.set_weighted_blended(context, false);
+
+                    // the full screen triangle of the composition is not clipped
+                    enable_clip_distances(context, 0, None);
+                    oit.composite(context, self.framebuffer);
+                    enable_clip_distances(context, self.clip_planes.len(), None);
+                }
+                None => {
+                    gl_call!(context, enable, glow::BLEND);
+                    gl_call!(
+                        context,
+                        blend_func,
+                        glow::SRC_ALPHA,
+                        glow::ONE_MINUS_SRC_ALPHA
+                    );
+                    gl_call!(context, depth_mask, false);
+                    Self::draw_items(
+                        context,
+                        shader,
+                        instance_buffer,
+                        &self.gpu_data,
+                        queue.iter_transparent(),
+                        &mut stats,
+                    );
+                    gl_call!(context, depth_mask, true);
+                    gl_call!(context, disable, glow::BLEND);
+                }
+            }
+        }
+
+        gl_call!(context, polygon_mode, glow::FRONT_AND_BACK, glow::FILL);
+        gl_call!(context, disable, glow::POLYGON_OFFSET_FILL);
+
+        if self.display_mode.has_edges() {
+            // the composition of the transparent parts binds its own shader program
+            shader.bind(context);
+            shader.set_solid_color(context, Some(&self.options.edge_color));
+            Self::draw_items(
+                context,
+                shader,
+                instance_buffer,
+                &self.gpu_data,
+                queue.iter_edges(),
+                &mut stats,
+            );
+        }
+
+        trace!(
+            "Drawn {} of {} instances with {} draw-calls, {} state changes and {} capping \
+             draw-calls",
+            stats.num_instances,
+            instances.len(),
+            stats.num_draw_calls,
+            stats.num_state_changes,
+            stats.num_capping_draw_calls
+        );
+        self.frame_stats = stats;
+
+        enable_clip_distances(context, 0, None);
         gl_call!(context, use_program, None);
     }
 
     fn cleanup(&mut self, context: &C) {
         info!("Clean up...");
-        match &mut self.shader {
-            Some(s) => s.cleanup(context),
-            _ => {}
+        if let Some(loader) = self.loader.take() {
+            debug!("Cancel loader");
+            loader.cancel();
+        }
+
+        if let Some(s) = &mut self.shader {
+            s.cleanup(context)
+        }
+
+        if let Some(instance_buffer) = self.instance_buffer.take() {
+            instance_buffer.cleanup(context);
+        }
+
+        if let Some(mut oit) = self.oit.take() {
+            oit.cleanup(context);
         }
+
+        if let Some(capping) = self.capping.take() {
+            capping.cleanup(context);
+        }
+
+        self.gpu_data.cleanup(context);
+    }
+
+    fn wait_for_data(&mut self, context: &C) {
+        if self.cad_data.is_none() && !self.loading_failed {
+            if let Some(loader) = &self.loader {
+                match loader.recv() {
+                    Ok(Ok(loaded)) => self.replace_cad_data(context, loaded),
+                    Ok(Err(err)) => {
+                        error!("Failed loading the CAD data due to {:#}", err);
+                        self.loading_failed = true;
+                    }
+                    Err(_) => {
+                        error!("Loader stopped without providing any CAD data");
+                        self.loader = None;
+                        self.loading_failed = true;
+                    }
+                }
+            }
+        }
+
+        // the whole data is uploaded at once, since no frames are drawn in the meantime
+        let unlimited_budget = UploadBudget {
+            max_time: Duration::MAX,
+            max_bytes: usize::MAX,
+        };
+        while self.gpu_data.is_uploading() {
+            self.continue_upload(context, &unlimited_budget);
+        }
+    }
+
+    fn has_failed(&self) -> bool {
+        self.loading_failed
     }
 
     fn resize(&mut self, _context: &C, width: u32, height: u32) {
@@ -189,25 +1317,126 @@ impl<C: HasContext> ViewerController<C> for Renderer<C> {
     }
 
     fn cursor_move(&mut self, x: f64, y: f64) {
+        // dragging upwards moves the active clip plane along its normal
+        if let Some(last_y) = self.clip_plane_drag {
+            let fraction = ((last_y - y) * self.scale_factor) as f32 / self.height.max(1) as f32;
+            self.move_clip_plane(fraction);
+            self.clip_plane_drag = Some(y);
+            return;
+        }
+
         self.camera.update_mouse_motion(x, y);
     }
 
     fn keyboard_event(&mut self, virtual_key: VirtualKeyCode, pressed: bool) {
         match (virtual_key, pressed) {
+            (VirtualKeyCode::Escape, true) => self.clear_selection(),
+            (VirtualKeyCode::I, true) => self.isolate_selection(),
+            (VirtualKeyCode::H, true) => self.hide_selection(),
+            (VirtualKeyCode::U, true) => self.unhide_all(),
+            (VirtualKeyCode::F, true) => self.focus_selection(),
+            (VirtualKeyCode::T, true) => {
+                self.show_scene_tree = !self.show_scene_tree;
+            }
+            (VirtualKeyCode::S, true) => {
+                self.show_frame_stats = !self.show_frame_stats;
+            }
+            (VirtualKeyCode::D, true) => {
+                self.display_mode = self.display_mode.next();
+                info!("Display mode: {}", self.display_mode);
+            }
+            (VirtualKeyCode::C, true) => self.add_clip_plane(),
+            (VirtualKeyCode::Delete, true) => self.remove_clip_plane(),
+            (VirtualKeyCode::N, true) => {
+                self.clip_planes.select_next();
+                self.log_active_clip_plane();
+            }
+            (VirtualKeyCode::X, true) => {
+                self.clip_planes.flip_active();
+                self.log_active_clip_plane();
+            }
+            (VirtualKeyCode::Up, true) => self.move_clip_plane(1f32 / CLIP_PLANE_STEPS),
+            (VirtualKeyCode::Down, true) => self.move_clip_plane(-1f32 / CLIP_PLANE_STEPS),
+            (VirtualKeyCode::P, true) => self.save_clip_planes(),
             (VirtualKeyCode::A, true) => {
                 info!("Show all");
-                match self.camera.focus(&self.scene_volume) {
-                    Err(err) => {
-                        error!("Failed to focus on scene due to {}", err);
-                    }
-                    _ => {}
+                if let Err(err) = self.camera.focus(&self.scene_volume) {
+                    error!("Failed to focus on scene due to {}", err);
                 }
             }
             _ => {}
         }
     }
 
+    fn build_ui(&mut self, gui: &egui::Context) {
+        if self.show_frame_stats {
+            let stats = self.get_frame_stats();
+            egui::Window::new("Statistics")
+                .default_pos([self.width as f32 / self.scale_factor as f32 - 208.0, 8.0])
+                .show(gui, |ui| {
+                    ui.label(format!(
+                        "Instances: {} of {}",
+                        stats.num_instances,
+                        self.gpu_data.get_instances().len()
+                    ));
+                    ui.label(format!("Draw-calls: {}", stats.num_draw_calls));
+                    ui.label(format!("State changes: {}", stats.num_state_changes));
+                    if stats.num_capping_draw_calls > 0 {
+                        ui.label(format!(
+                            "Capping draw-calls: {}",
+                            stats.num_capping_draw_calls
+                        ));
+                    }
+                    ui.label(format!(
+                        "GPU memory: {:.1} MiB",
+                        self.gpu_data.get_memory_stats().total() as f64 / (1024f64 * 1024f64)
+                    ));
+                });
+        }
+
+        if !self.show_scene_tree || self.gpu_data.get_nodes().is_empty() {
+            return;
+        }
+
+        egui::Window::new("Scene")
+            .default_pos([8.0, 8.0])
+            .default_height(self.height as f32 / 2f32)
+            .show(gui, |ui| {
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    self.add_scene_node_ui(ui, 0);
+                });
+            });
+    }
+
+    fn modifiers_changed(&mut self, modifiers: ModifiersState) {
+        self.modifiers = modifiers;
+    }
+
     fn mouse_button(&mut self, x: f64, y: f64, button: MouseButton, pressed: bool) {
+        // dragging with the alt key pressed moves the active clip plane instead of the camera
+        if button == MouseButton::Left {
+            if pressed && self.modifiers.alt() && !self.clip_planes.is_empty() {
+                self.clip_plane_drag = Some(y);
+                return;
+            } else if !pressed && self.clip_plane_drag.take().is_some() {
+                self.log_active_clip_plane();
+                return;
+            }
+        }
+
         self.camera.update_mouse_button(x, y, button, pressed);
+
+        if button != MouseButton::Left {
+            return;
+        }
+
+        // a click is a press and release of the left button without dragging in between
+        if pressed {
+            self.click_start = Some([x, y]);
+        } else if let Some([start_x, start_y]) = self.click_start.take() {
+            if (x - start_x).hypot(y - start_y) <= CLICK_TOLERANCE {
+                self.click(x, y);
+            }
+        }
     }
 }
@@ -0,0 +1,78 @@
+use std::collections::BTreeSet;
+
+use cad_import::ID;
+
+/// The set of selected shape instances, identified by their index within the instances of the
+/// GPU data.
+#[derive(Debug, Clone, Default)]
+pub struct Selection {
+    instances: BTreeSet<usize>,
+}
+
+impl Selection {
+    /// Creates a new empty selection.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Replaces the selection by the given instance.
+    ///
+    /// # Arguments
+    /// * `instance_index` - The index of the instance to select.
+    pub fn select(&mut self, instance_index: usize) {
+        self.instances.clear();
+        self.instances.insert(instance_index);
+    }
+
+    /// Adds the given instance to the selection if it is not selected and removes it otherwise.
+    ///
+    /// # Arguments
+    /// * `instance_index` - The index of the instance to toggle.
+    pub fn toggle(&mut self, instance_index: usize) {
+        if !self.instances.remove(&instance_index) {
+            self.instances.insert(instance_index);
+        }
+    }
+
+    /// Removes all instances from the selection.
+    pub fn clear(&mut self) {
+        self.instances.clear();
+    }
+
+    /// Returns true if the given instance is selected.
+    ///
+    /// # Arguments
+    /// * `instance_index` - The index of the instance to check.
+    #[inline]
+    pub fn contains(&self, instance_index: usize) -> bool {
+        self.instances.contains(&instance_index)
+    }
+
+    /// Returns true if no instance is selected.
+    pub fn is_empty(&self) -> bool {
+        self.instances.is_empty()
+    }
+
+    /// Returns the number of selected instances.
+    pub fn len(&self) -> usize {
+        self.instances.len()
+    }
+
+    /// Returns an iterator over the indices of the selected instances in ascending order.
+    pub fn iter(&self) -> impl Iterator<Item = usize> + '_ {
+        self.instances.iter().copied()
+    }
+}
+
+/// A selected shape instance together with the node it is attached to.
+#[derive(Debug, Clone)]
+pub struct SelectedNode {
+    /// The index of the selected instance within the instances of the GPU data.
+    pub instance_index: usize,
+
+    /// The ID of the node the instance is attached to.
+    pub node_id: ID,
+
+    /// The label of the node the instance is attached to.
+    pub label: String,
+}
@@ -2,9 +2,17 @@ use anyhow::bail;
 use cad_import::structure::Material;
 use glow::HasContext;
 use log::debug;
-use nalgebra_glm::{Mat3, Mat4};
+use nalgebra_glm::{Mat3, Mat4, Vec3};
 
-use crate::gl_call;
+use crate::{geometry::Plane, gl_call};
+
+use super::{
+    clipping::pack_clip_planes,
+    lighting::{DirectionalLight, PhongParameters, MAX_LIGHTS},
+};
+
+/// The weight used for blending the highlight color with the diffuse color.
+const HIGHLIGHT_WEIGHT: f32 = 0.6;
 
 pub struct Shader<C: HasContext> {
     program: Option<C::Program>,
@@ -12,7 +20,21 @@ pub struct Shader<C: HasContext> {
     uniform_model_mat: C::UniformLocation,
     uniform_normal_mat: C::UniformLocation,
     uniform_diffuse_color: C::UniformLocation,
+    uniform_specular_color: C::UniformLocation,
+    uniform_emissive_color: C::UniformLocation,
+    uniform_ambient_intensity: C::UniformLocation,
+    uniform_shininess: C::UniformLocation,
+    uniform_opacity: C::UniformLocation,
+    uniform_num_lights: C::UniformLocation,
+    uniform_light_directions: C::UniformLocation,
+    uniform_light_colors: C::UniformLocation,
     uniform_normals_enabled: C::UniformLocation,
+    uniform_highlight_color: C::UniformLocation,
+    uniform_solid_color_enabled: C::UniformLocation,
+    uniform_solid_color: C::UniformLocation,
+    uniform_octahedral_normals: C::UniformLocation,
+    uniform_weighted_blended: C::UniformLocation,
+    uniform_clip_planes: C::UniformLocation,
 }
 
 impl<C: HasContext> Shader<C> {
@@ -22,6 +44,75 @@ impl<C: HasContext> Shader<C> {
     /// * `context` - The OpenGL context used for creating and compiling the shader
     /// * `shader_version` - The version string for the shader code.
     pub fn new(context: &C, shader_version: &str) -> anyhow::Result<Self> {
+        let shader_sources = [
+            (glow::VERTEX_SHADER, include_str!("shaders/shader.vert")),
+            (glow::FRAGMENT_SHADER, include_str!("shaders/shader.frag")),
+        ];
+        let program = Self::create_program(context, shader_version, &shader_sources)?;
+
+        // find uniform shader variables
+        let uniform_combined_mat = Self::get_uniform_location(context, program, "combinedMat")?;
+        let uniform_model_mat = Self::get_uniform_location(context, program, "modelMat")?;
+        let uniform_normal_mat = Self::get_uniform_location(context, program, "normalMat")?;
+        let uniform_diffuse_color = Self::get_uniform_location(context, program, "diffuseColor")?;
+        let uniform_specular_color = Self::get_uniform_location(context, program, "specularColor")?;
+        let uniform_emissive_color = Self::get_uniform_location(context, program, "emissiveColor")?;
+        let uniform_ambient_intensity =
+            Self::get_uniform_location(context, program, "ambientIntensity")?;
+        let uniform_shininess = Self::get_uniform_location(context, program, "shininess")?;
+        let uniform_opacity = Self::get_uniform_location(context, program, "opacity")?;
+        let uniform_num_lights = Self::get_uniform_location(context, program, "numLights")?;
+        let uniform_light_directions =
+            Self::get_uniform_location(context, program, "lightDirections")?;
+        let uniform_light_colors = Self::get_uniform_location(context, program, "lightColors")?;
+        let uniform_normals_enabled =
+            Self::get_uniform_location(context, program, "normalsEnabled")?;
+        let uniform_highlight_color =
+            Self::get_uniform_location(context, program, "highlightColor")?;
+        let uniform_solid_color_enabled =
+            Self::get_uniform_location(context, program, "solidColorEnabled")?;
+        let uniform_solid_color = Self::get_uniform_location(context, program, "solidColor")?;
+        let uniform_octahedral_normals =
+            Self::get_uniform_location(context, program, "octahedralNormals")?;
+        let uniform_weighted_blended =
+            Self::get_uniform_location(context, program, "weightedBlended")?;
+        let uniform_clip_planes = Self::get_uniform_location(context, program, "clipPlanes")?;
+
+        Ok(Shader {
+            program: Some(program),
+            uniform_combined_mat,
+            uniform_model_mat,
+            uniform_normal_mat,
+            uniform_diffuse_color,
+            uniform_specular_color,
+            uniform_emissive_color,
+            uniform_ambient_intensity,
+            uniform_shininess,
+            uniform_opacity,
+            uniform_num_lights,
+            uniform_light_directions,
+            uniform_light_colors,
+            uniform_normals_enabled,
+            uniform_highlight_color,
+            uniform_solid_color_enabled,
+            uniform_solid_color,
+            uniform_octahedral_normals,
+            uniform_weighted_blended,
+            uniform_clip_planes,
+        })
+    }
+
+    /// Creates a shader program from the given shader sources.
+    ///
+    /// # Arguments
+    /// * `context` - The OpenGL context used for creating and compiling the shaders.
+    /// * `shader_version` - The version string prepended to the shader sources.
+    /// * `shader_sources` - The types and sources of the shaders of the program.
+    pub fn create_program(
+        context: &C,
+        shader_version: &str,
+        shader_sources: &[(u32, &str)],
+    ) -> anyhow::Result<C::Program> {
         debug!("Create shader program...");
         let program: C::Program = match gl_call!(context, create_program) {
             Ok(program) => program,
@@ -31,11 +122,6 @@ impl<C: HasContext> Shader<C> {
         };
 
         debug!("Compile shader source...");
-        let shader_sources = [
-            (glow::VERTEX_SHADER, include_str!("shaders/shader.vert")),
-            (glow::FRAGMENT_SHADER, include_str!("shaders/shader.frag")),
-        ];
-
         let mut shaders = Vec::with_capacity(shader_sources.len());
         for (shader_type, shader_source) in shader_sources.iter() {
             let shader_id = match gl_call!(context, create_shader, *shader_type) {
@@ -85,26 +171,11 @@ impl<C: HasContext> Shader<C> {
             gl_call!(context, delete_shader, shader);
         }
 
-        // find uniform shader variables
-        let uniform_combined_mat = Self::get_uniform_location(context, program, "combinedMat")?;
-        let uniform_model_mat = Self::get_uniform_location(context, program, "modelMat")?;
-        let uniform_normal_mat = Self::get_uniform_location(context, program, "normalMat")?;
-        let uniform_diffuse_color = Self::get_uniform_location(context, program, "diffuseColor")?;
-        let uniform_normals_enabled =
-            Self::get_uniform_location(context, program, "normalsEnabled")?;
-
-        Ok(Shader {
-            program: Some(program),
-            uniform_combined_mat,
-            uniform_model_mat,
-            uniform_normal_mat,
-            uniform_diffuse_color,
-            uniform_normals_enabled,
-        })
+        Ok(program)
     }
 
     /// Tries to find the specified uniform variable.
-    fn get_uniform_location(
+    pub fn get_uniform_location(
         context: &C,
         program: C::Program,
         name: &str,
@@ -117,13 +188,14 @@ impl<C: HasContext> Shader<C> {
         }
     }
 
-    /// Sets the matrices for the shader uniform variables.
+    /// Sets the matrices of the camera for the shader uniform variables. The transformations of
+    /// the instances are provided by the per-instance vertex attributes.
     ///
     /// # Arguments
     /// * `context` - The GLOW context.
-    /// * `model_mat` - The multiplied projection and model view matrix.
+    /// * `model_mat` - The model view matrix.
     /// * `combined_mat` - The multiplied projection and model view matrix.
-    /// * `normal_mat` - The normal matrix.
+    /// * `normal_mat` - The normal matrix of the model view matrix.
     pub fn set_matrices(
         &self,
         context: &C,
@@ -156,31 +228,126 @@ impl<C: HasContext> Shader<C> {
         );
     }
 
-    /// Sets uniform variable for the given material.
+    /// Sets the uniform variables for all components of the given material.
     ///
     /// # Arguments
     /// * `context` - The GLOW context.
     /// * `material` - The material data to set.
     pub fn set_material(&self, context: &C, material: &Material) {
-        match material {
-            Material::PhongMaterial(p) => {
-                gl_call!(
-                    context,
-                    uniform_3_f32_slice,
-                    Some(&self.uniform_diffuse_color),
-                    p.diffuse_color.0.as_slice()
-                );
-            }
-            Material::None => {
-                gl_call!(
-                    context,
-                    uniform_3_f32,
-                    Some(&self.uniform_diffuse_color),
-                    0.8f32,
-                    0.8f32,
-                    0.8f32
-                );
-            }
+        let p = PhongParameters::from_material(material);
+
+        for (location, color) in [
+            (&self.uniform_diffuse_color, &p.diffuse),
+            (&self.uniform_specular_color, &p.specular),
+            (&self.uniform_emissive_color, &p.emissive),
+        ] {
+            gl_call!(
+                context,
+                uniform_3_f32_slice,
+                Some(location),
+                color.as_slice()
+            );
+        }
+
+        gl_call!(
+            context,
+            uniform_1_f32,
+            Some(&self.uniform_ambient_intensity),
+            p.ambient_intensity
+        );
+        gl_call!(
+            context,
+            uniform_1_f32,
+            Some(&self.uniform_shininess),
+            p.shininess
+        );
+        gl_call!(
+            context,
+            uniform_1_f32,
+            Some(&self.uniform_opacity),
+            p.opacity
+        );
+    }
+
+    /// Sets the lights illuminating the scene. Lights beyond the maximal number of lights are
+    /// ignored.
+    ///
+    /// # Arguments
+    /// * `context` - The GLOW context.
+    /// * `lights` - The directional lights in view coordinates.
+    pub fn set_lights(&self, context: &C, lights: &[DirectionalLight]) {
+        let lights = &lights[..lights.len().min(MAX_LIGHTS)];
+
+        let mut directions = Vec::with_capacity(lights.len() * 3);
+        let mut colors = Vec::with_capacity(lights.len() * 3);
+        for light in lights.iter() {
+            directions.extend_from_slice(light.direction.as_slice());
+            colors.extend_from_slice(light.color.as_slice());
+        }
+
+        gl_call!(
+            context,
+            uniform_1_i32,
+            Some(&self.uniform_num_lights),
+            lights.len() as i32
+        );
+
+        if !lights.is_empty() {
+            gl_call!(
+                context,
+                uniform_3_f32_slice,
+                Some(&self.uniform_light_directions),
+                &directions
+            );
+            gl_call!(
+                context,
+                uniform_3_f32_slice,
+                Some(&self.uniform_light_colors),
+                &colors
+            );
+        }
+    }
+
+    /// Sets the color blended with the material color of the highlighted instances.
+    ///
+    /// # Arguments
+    /// * `context` - The GLOW context.
+    /// * `highlight_color` - The highlight color.
+    pub fn set_highlight_color(&self, context: &C, highlight_color: &Vec3) {
+        gl_call!(
+            context,
+            uniform_4_f32,
+            Some(&self.uniform_highlight_color),
+            highlight_color[0],
+            highlight_color[1],
+            highlight_color[2],
+            HIGHLIGHT_WEIGHT
+        );
+    }
+
+    /// Sets the color used for drawing without lighting, e.g., for lines, or disables the
+    /// solid color.
+    ///
+    /// # Arguments
+    /// * `context` - The GLOW context.
+    /// * `solid_color` - The solid color or none for lit surfaces.
+    pub fn set_solid_color(&self, context: &C, solid_color: Option<&Vec3>) {
+        gl_call!(
+            context,
+            uniform_1_i32,
+            Some(&self.uniform_solid_color_enabled),
+            if solid_color.is_some() { 1 } else { 0 }
+        );
+
+        if let Some(color) = solid_color {
+            gl_call!(
+                context,
+                uniform_3_f32,
+                Some(&self.uniform_solid_color),
+                color[0],
+                color[1],
+                color[2]
+            );
         }
     }
 
@@ -198,6 +365,50 @@ impl<C: HasContext> Shader<C> {
         );
     }
 
+    /// Sets how the normals of the vertex data are encoded.
+    ///
+    /// # Arguments
+    /// * `context` - The GLOW context.
+    /// * `octahedral_normals` - This flag indicates if the normals are octahedral encoded.
+    pub fn set_normal_encoding(&self, context: &C, octahedral_normals: bool) {
+        gl_call!(
+            context,
+            uniform_1_i32,
+            Some(&self.uniform_octahedral_normals),
+            if octahedral_normals { 1 } else { 0 }
+        );
+    }
+
+    /// Sets if the fragments are written into the targets of the weighted blended order
+    /// independent transparency instead of a single color.
+    ///
+    /// # Arguments
+    /// * `context` - The GLOW context.
+    /// * `weighted_blended` - This flag indicates if the weighted blended targets are written.
+    pub fn set_weighted_blended(&self, context: &C, weighted_blended: bool) {
+        gl_call!(
+            context,
+            uniform_1_i32,
+            Some(&self.uniform_weighted_blended),
+            if weighted_blended { 1 } else { 0 }
+        );
+    }
+
+    /// Sets the clip planes in world coordinates. The clip distances of the planes are only
+    /// applied if they are enabled in the context.
+    ///
+    /// # Arguments
+    /// * `context` - The GLOW context.
+    /// * `planes` - The clip planes, where planes beyond the maximal number are ignored.
+    pub fn set_clip_planes(&self, context: &C, planes: &[Plane]) {
+        gl_call!(
+            context,
+            uniform_4_f32_slice,
+            Some(&self.uniform_clip_planes),
+            &pack_clip_planes(planes)
+        );
+    }
+
     /// Binds the shader program to the given context.
     pub fn bind(&self, context: &C) {
         gl_call!(context, use_program, self.program);
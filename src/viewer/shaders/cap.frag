@@ -0,0 +1,20 @@
+
+//------------------------------------------
+// UNIFORMS
+//------------------------------------------
+
+uniform vec3 capColor;
+
+//------------------------------------------
+// OUTPUT
+//------------------------------------------
+
+layout(location = 0) out vec4 outColor;
+
+//------------------------------------------
+// MAIN
+//------------------------------------------
+
+void main() {
+    outColor = vec4(capColor, 1.0);
+}
@@ -0,0 +1,34 @@
+
+//------------------------------------------
+// UNIFORMS
+//------------------------------------------
+
+uniform mat4 combinedMat;
+
+// the corners of the cap quad in world coordinates
+uniform vec3 corners[4];
+
+// the clip planes in world coordinates
+#define MAX_CLIP_PLANES 4
+uniform vec4 clipPlanes[MAX_CLIP_PLANES];
+
+//------------------------------------------
+// OUTPUT
+//------------------------------------------
+
+out float gl_ClipDistance[MAX_CLIP_PLANES];
+
+//------------------------------------------
+// MAIN
+//------------------------------------------
+
+void main() {
+    // the quad is drawn as triangle strip without any attributes
+    vec4 pos = vec4(corners[gl_VertexID], 1.0);
+
+    for(int i = 0; i < MAX_CLIP_PLANES; ++i) {
+        gl_ClipDistance[i] = dot(clipPlanes[i], pos);
+    }
+
+    gl_Position = combinedMat * pos;
+}
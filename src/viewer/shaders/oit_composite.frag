@@ -0,0 +1,35 @@
+
+//------------------------------------------
+// UNIFORMS
+//------------------------------------------
+
+// the weighted sum of the premultiplied colors and their alpha values
+uniform sampler2D accumulation;
+
+// the product of the transparencies of all fragments
+uniform sampler2D revealage;
+
+//------------------------------------------
+// OUTPUT
+//------------------------------------------
+
+out vec4 outColor;
+
+//------------------------------------------
+// MAIN
+//------------------------------------------
+
+void main() {
+    ivec2 coord = ivec2(gl_FragCoord.xy);
+    float reveal = texelFetch(revealage, coord, 0).r;
+
+    // skip pixels without any transparent fragments
+    if (reveal >= 1.0) {
+        discard;
+    }
+
+    vec4 accum = texelFetch(accumulation, coord, 0);
+    vec3 color = accum.rgb / clamp(accum.a, 1e-4, 5e4);
+
+    outColor = vec4(color, 1.0 - reveal);
+}
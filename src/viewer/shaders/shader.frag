@@ -5,19 +5,41 @@
 
 in vec3 varNormal;
 in vec3 varPos;
+flat in float varHighlight;
 
 //------------------------------------------
 // UNIFORMS
 //------------------------------------------
 
 uniform vec3 diffuseColor;
+uniform vec3 specularColor;
+uniform vec3 emissiveColor;
+uniform float ambientIntensity;
+uniform float shininess;
+uniform float opacity;
+
 uniform int normalsEnabled;
+uniform vec4 highlightColor;
+
+// draws unlit with a single color, e.g., the edges
+uniform int solidColorEnabled;
+uniform vec3 solidColor;
+
+// writes the targets of the weighted blended order independent transparency
+uniform int weightedBlended;
+
+// directional lights in view coordinates
+#define MAX_LIGHTS 8
+uniform int numLights;
+uniform vec3 lightDirections[MAX_LIGHTS];
+uniform vec3 lightColors[MAX_LIGHTS];
 
 //------------------------------------------
 // OUTPUT
 //------------------------------------------
 
-out vec4 outColor;
+layout(location = 0) out vec4 outColor;
+layout(location = 1) out vec4 outRevealage;
 
 //------------------------------------------
 // CONSTANTS
@@ -29,7 +51,31 @@ vec3 flatNormal(vec3 pos) {
     return normalize(cross(fdx, fdy));
 }
 
+// evaluates the Blinn-Phong lighting equation for all lights, where the ambient term is added
+// once independent of the number of lights
+vec3 shade(vec3 normal, vec3 viewDir, vec3 diffuse) {
+    vec3 color = emissiveColor + diffuse * ambientIntensity;
+
+    for(int i = 0; i < numLights; ++i) {
+        vec3 l = -lightDirections[i];
+        vec3 h = normalize(l + viewDir);
+
+        float d = max(dot(normal, l), 0.0);
+        float s = d > 0.0 ? pow(max(dot(normal, h), 0.0), shininess) : 0.0;
+
+        color += lightColors[i] * (diffuse * d + specularColor * s);
+    }
+
+    return color;
+}
+
 void main() {
+    if(solidColorEnabled == 1) {
+        outColor = vec4(mix(solidColor, highlightColor.rgb, highlightColor.a * varHighlight), 1.0);
+        outRevealage = vec4(0.0);
+        return;
+    }
+
     vec3 normal;
 
     if(normalsEnabled == 1) {
@@ -38,7 +84,27 @@ void main() {
         normal = flatNormal(varPos);
     }
 
-    float f = abs(normal.z) * 0.75 + 0.25;
+    // two-sided lighting, i.e., normals facing away from the viewer are flipped
+    vec3 viewDir = normalize(-varPos);
+    if(dot(normal, viewDir) < 0.0) {
+        normal = -normal;
+    }
+
+    // the alpha channel of the highlight color defines the blend weight
+    vec3 diffuse = mix(diffuseColor, highlightColor.rgb, highlightColor.a * varHighlight);
 
-    outColor = vec4(f * diffuseColor, 1.0);
+    vec3 color = shade(normal, viewDir, diffuse);
+
+    if(weightedBlended == 1) {
+        // the weight prefers close and opaque fragments
+        float a = min(1.0, opacity * 10.0) + 0.01;
+        float d = 1.0 - gl_FragCoord.z * 0.9;
+        float w = clamp(a * a * a * 1e8 * d * d * d, 1e-2, 3e3);
+
+        outColor = vec4(color * opacity, opacity) * w;
+        outRevealage = vec4(opacity);
+    } else {
+        outColor = vec4(color, opacity);
+        outRevealage = vec4(0.0);
+    }
 }
\ No newline at end of file
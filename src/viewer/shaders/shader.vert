@@ -1,4 +1,3 @@
-
 //------------------------------------------
 // INPUTS
 //------------------------------------------
@@ -6,6 +5,11 @@
 layout(location = 0) in vec3 inPosition;
 layout(location = 1) in vec3 inNormal;
 
+// per-instance attributes
+layout(location = 2) in mat4 inInstanceMat;
+layout(location = 6) in mat3 inInstanceNormalMat;
+layout(location = 9) in float inHighlight;
+
 //------------------------------------------
 // UNIFORMS
 //------------------------------------------
@@ -13,6 +17,11 @@ layout(location = 1) in vec3 inNormal;
 uniform mat4 combinedMat;
 uniform mat4 modelMat;
 uniform mat3 normalMat;
+uniform int octahedralNormals;
+
+// the clip planes in world coordinates, where only the enabled clip distances are applied
+#define MAX_CLIP_PLANES 4
+uniform vec4 clipPlanes[MAX_CLIP_PLANES];
 
 //------------------------------------------
 // OUTPUT
@@ -20,22 +29,46 @@ uniform mat3 normalMat;
 
 out vec3 varNormal;
 out vec3 varPos;
+flat out float varHighlight;
+out float gl_ClipDistance[MAX_CLIP_PLANES];
 
 //------------------------------------------
 // CONSTANTS
 //------------------------------------------
 
+// decodes the normal from the folded octahedron, must be kept in sync with `encode_octahedral`
+// and its reference decoder `decode_octahedral` in the tests of src/gpu_data/vertex_format.rs
+vec3 decodeOctahedral(vec2 e) {
+    vec3 n = vec3(e, 1.0 - abs(e.x) - abs(e.y));
+    if (n.z < 0.0) {
+        vec2 s = vec2(n.x >= 0.0 ? 1.0 : -1.0, n.y >= 0.0 ? 1.0 : -1.0);
+        n.xy = (1.0 - abs(n.yx)) * s;
+    }
+
+    return normalize(n);
+}
+
 void main() {
     // transform normal
-    vec3 normal = inNormal;
+    vec3 normal = octahedralNormals == 1 ? decodeOctahedral(inNormal.xy) : inNormal;
+    normal = inInstanceNormalMat * normal;
     varNormal = normalMat * normal;
 
+    // apply instance transformation
+    vec4 pos = inInstanceMat * vec4(inPosition, 1.0);
+
+    for(int i = 0; i < MAX_CLIP_PLANES; ++i) {
+        gl_ClipDistance[i] = dot(clipPlanes[i], pos);
+    }
+
     // apply model view matrix
-    varPos = vec3(modelMat * vec4(inPosition, 1.0));
+    varPos = vec3(modelMat * pos);
 
     // project 
-    vec4 ppos = combinedMat * vec4(inPosition, 1.0);
+    vec4 ppos = combinedMat * pos;
 
     // transform vertex position
     gl_Position = ppos;
+
+    varHighlight = inHighlight;
 }
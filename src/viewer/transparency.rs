@@ -0,0 +1,431 @@
+use std::{fmt::Display, str::FromStr};
+
+use anyhow::{bail, Context, Result};
+use glow::HasContext;
+
+use crate::gl_call;
+
+use super::{
+    gl_call::{check_error, handle_glow_error},
+    shader::Shader,
+};
+
+/// The methods for blending the transparent parts with the scene.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransparencyMode {
+    /// Draws the transparent instances back-to-front sorted by their view depth.
+    Sorted,
+
+    /// Accumulates the transparent fragments order independent with depth based weights.
+    WeightedBlended,
+}
+
+impl TransparencyMode {
+    /// All available transparency modes.
+    pub const ALL: [TransparencyMode; 2] =
+        [TransparencyMode::Sorted, TransparencyMode::WeightedBlended];
+
+    /// Returns the name of the transparency mode.
+    pub fn name(&self) -> &'static str {
+        match self {
+            TransparencyMode::Sorted => "sorted",
+            TransparencyMode::WeightedBlended => "oit",
+        }
+    }
+}
+
+impl Display for TransparencyMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for TransparencyMode {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.to_lowercase();
+        match Self::ALL.iter().find(|mode| mode.name() == s) {
+            Some(mode) => Ok(*mode),
+            None => {
+                let names: Vec<&str> = Self::ALL.iter().map(|mode| mode.name()).collect();
+                bail!(
+                    "Unknown transparency mode '{}', expected one of {}",
+                    s,
+                    names.join(", ")
+                );
+            }
+        }
+    }
+}
+
+/// The offscreen render targets of the weighted blended order independent transparency.
+struct Targets<C: HasContext> {
+    framebuffer: C::Framebuffer,
+    accumulation: C::Texture,
+    revealage: C::Texture,
+    depth: C::Renderbuffer,
+    width: u32,
+    height: u32,
+}
+
+/// Weighted blended order independent transparency, i.e., the transparent fragments are
+/// accumulated into offscreen targets weighted by their depth and opacity and are composited
+/// onto the scene afterwards.
+pub struct WeightedBlendedOIT<C: HasContext> {
+    program: C::Program,
+    vertex_array: C::VertexArray,
+    uniform_accumulation: C::UniformLocation,
+    uniform_revealage: C::UniformLocation,
+    targets: Option<Targets<C>>,
+}
+
+impl<C: HasContext> WeightedBlendedOIT<C> {
+    /// Creates the composite shader program. The render targets are created on first use.
+    ///
+    /// # Arguments
+    /// * `context` - The GLOW context.
+    /// * `shader_version` - The version string for the shader code.
+    pub fn new(context: &C, shader_version: &str) -> Result<Self> {
+        let shader_sources = [
+            (
+                glow::VERTEX_SHADER,
+                include_str!("shaders/oit_composite.vert"),
+            ),
+            (
+                glow::FRAGMENT_SHADER,
+                include_str!("shaders/oit_composite.frag"),
+            ),
+        ];
+        let program = Shader::<C>::create_program(context, shader_version, &shader_sources)?;
+
+        let uniform_accumulation =
+            Shader::<C>::get_uniform_location(context, program, "accumulation")?;
+        let uniform_revealage = Shader::<C>::get_uniform_location(context, program, "revealage")?;
+
+        // the full screen triangle is generated from the vertex IDs without any attributes
+        let vertex_array = handle_glow_error(gl_call!(context, create_vertex_array))?;
+
+        Ok(Self {
+            program,
+            vertex_array,
+            uniform_accumulation,
+            uniform_revealage,
+            targets: None,
+        })
+    }
+
+    /// Binds and clears the render targets for the given frame size and copies the depth
+    /// buffer of the given framebuffer, s.t. everything drawn so far occludes the transparent
+    /// fragments without drawing it again. The render targets are recreated if the size has
+    /// changed.
+    ///
+    /// # Arguments
+    /// * `context` - The GLOW context.
+    /// * `framebuffer` - The framebuffer with the depth of the frame, where none is the default.
+    /// * `width` - The width of the frame in pixels.
+    /// * `height` - The height of the frame in pixels.
+    pub fn begin(
+        &mut self,
+        context: &C,
+        framebuffer: Option<C::Framebuffer>,
+        width: u32,
+        height: u32,
+    ) -> Result<()> {
+        let is_outdated = self
+            .targets
+            .as_ref()
+            .is_none_or(|targets| targets.width != width || targets.height != height);
+        if is_outdated {
+            if let Some(targets) = self.targets.take() {
+                Self::delete_targets(context, targets);
+            }
+
+            self.targets = Some(Self::create_targets(context, width, height)?);
+        }
+
+        let targets = match &self.targets {
+            Some(targets) => targets,
+            None => return Ok(()),
+        };
+
+        // the window and the offscreen framebuffer have a 24-bit depth and 8-bit stencil buffer
+        // like the render targets, otherwise the blit fails, which is checked directly instead
+        // of by `gl_call`, which would only log the error
+        gl_call!(
+            context,
+            bind_framebuffer,
+            glow::READ_FRAMEBUFFER,
+            framebuffer
+        );
+        gl_call!(
+            context,
+            bind_framebuffer,
+            glow::DRAW_FRAMEBUFFER,
+            Some(targets.framebuffer)
+        );
+        unsafe {
+            context.blit_framebuffer(
+                0,
+                0,
+                width as i32,
+                height as i32,
+                0,
+                0,
+                width as i32,
+                height as i32,
+                glow::DEPTH_BUFFER_BIT,
+                glow::NEAREST,
+            );
+        }
+        check_error(context).context("Failed to copy the depth buffer of the frame")?;
+        gl_call!(
+            context,
+            bind_framebuffer,
+            glow::FRAMEBUFFER,
+            Some(targets.framebuffer)
+        );
+
+        gl_call!(
+            context,
+            draw_buffers,
+            &[glow::COLOR_ATTACHMENT0, glow::COLOR_ATTACHMENT1]
+        );
+        gl_call!(
+            context,
+            clear_buffer_f32_slice,
+            glow::COLOR,
+            0,
+            &[0.0, 0.0, 0.0, 0.0]
+        );
+        gl_call!(
+            context,
+            clear_buffer_f32_slice,
+            glow::COLOR,
+            1,
+            &[1.0, 1.0, 1.0, 1.0]
+        );
+
+        Ok(())
+    }
+
+    /// Sets up the blending for accumulating the transparent fragments. The depth buffer of
+    /// the opaque parts is only tested, but not written.
+    ///
+    /// # Arguments
+    /// * `context` - The GLOW context.
+    pub fn begin_accumulation(&self, context: &C) {
+        gl_call!(context, depth_mask, false);
+        gl_call!(context, enable, glow::BLEND);
+        gl_call!(context, blend_func_draw_buffer, 0, glow::ONE, glow::ONE);
+        gl_call!(
+            context,
+            blend_func_draw_buffer,
+            1,
+            glow::ZERO,
+            glow::ONE_MINUS_SRC_COLOR
+        );
+    }
+
+    /// Blends the accumulated transparent fragments over the given framebuffer and restores
+    /// the default depth and blend state.
+    ///
+    /// # Arguments
+    /// * `context` - The GLOW context.
+    /// * `framebuffer` - The framebuffer to composite into, where none is the default one.
+    pub fn composite(&self, context: &C, framebuffer: Option<C::Framebuffer>) {
+        let targets = match &self.targets {
+            Some(targets) => targets,
+            None => return,
+        };
+
+        gl_call!(context, bind_framebuffer, glow::FRAMEBUFFER, framebuffer);
+        gl_call!(context, disable, glow::DEPTH_TEST);
+        gl_call!(
+            context,
+            blend_func,
+            glow::SRC_ALPHA,
+            glow::ONE_MINUS_SRC_ALPHA
+        );
+
+        gl_call!(context, use_program, Some(self.program));
+        for (unit, texture, uniform) in [
+            (0, targets.accumulation, &self.uniform_accumulation),
+            (1, targets.revealage, &self.uniform_revealage),
+        ] {
+            gl_call!(context, active_texture, glow::TEXTURE0 + unit as u32);
+            gl_call!(context, bind_texture, glow::TEXTURE_2D, Some(texture));
+            gl_call!(context, uniform_1_i32, Some(uniform), unit);
+        }
+
+        gl_call!(context, bind_vertex_array, Some(self.vertex_array));
+        gl_call!(context, draw_arrays, glow::TRIANGLES, 0, 3);
+        gl_call!(context, bind_vertex_array, None);
+
+        for unit in [1, 0] {
+            gl_call!(context, active_texture, glow::TEXTURE0 + unit);
+            gl_call!(context, bind_texture, glow::TEXTURE_2D, None);
+        }
+
+        gl_call!(context, disable, glow::BLEND);
+        gl_call!(context, depth_mask, true);
+        gl_call!(context, enable, glow::DEPTH_TEST);
+    }
+
+    /// Frees all GPU resources.
+    ///
+    /// # Arguments
+    /// * `context` - The GLOW context.
+    pub fn cleanup(&mut self, context: &C) {
+        if let Some(targets) = self.targets.take() {
+            Self::delete_targets(context, targets);
+        }
+
+        gl_call!(context, delete_vertex_array, self.vertex_array);
+        gl_call!(context, delete_program, self.program);
+    }
+
+    /// Creates and binds the framebuffer with the accumulation and revealage targets and a depth
+    /// buffer of the given size. The depth buffer has the format of the rendered frames, s.t.
+    /// their depth can be copied.
+    ///
+    /// # Arguments
+    /// * `context` - The GLOW context.
+    /// * `width` - The width of the targets in pixels.
+    /// * `height` - The height of the targets in pixels.
+    fn create_targets(context: &C, width: u32, height: u32) -> Result<Targets<C>> {
+        let framebuffer = handle_glow_error(gl_call!(context, create_framebuffer))?;
+        gl_call!(
+            context,
+            bind_framebuffer,
+            glow::FRAMEBUFFER,
+            Some(framebuffer)
+        );
+
+        let accumulation = Self::create_texture(
+            context,
+            width,
+            height,
+            glow::RGBA16F,
+            glow::RGBA,
+            glow::COLOR_ATTACHMENT0,
+        )?;
+        let revealage = Self::create_texture(
+            context,
+            width,
+            height,
+            glow::R16F,
+            glow::RED,
+            glow::COLOR_ATTACHMENT1,
+        )?;
+
+        let depth = handle_glow_error(gl_call!(context, create_renderbuffer))?;
+        gl_call!(context, bind_renderbuffer, glow::RENDERBUFFER, Some(depth));
+        gl_call!(
+            context,
+            renderbuffer_storage,
+            glow::RENDERBUFFER,
+            glow::DEPTH24_STENCIL8,
+            width as i32,
+            height as i32
+        );
+        gl_call!(context, bind_renderbuffer, glow::RENDERBUFFER, None);
+        gl_call!(
+            context,
+            framebuffer_renderbuffer,
+            glow::FRAMEBUFFER,
+            glow::DEPTH_STENCIL_ATTACHMENT,
+            glow::RENDERBUFFER,
+            Some(depth)
+        );
+
+        let targets = Targets {
+            framebuffer,
+            accumulation,
+            revealage,
+            depth,
+            width,
+            height,
+        };
+
+        let status = gl_call!(context, check_framebuffer_status, glow::FRAMEBUFFER);
+        if status != glow::FRAMEBUFFER_COMPLETE {
+            Self::delete_targets(context, targets);
+            bail!(
+                "Transparency framebuffer is incomplete, status 0x{:X}",
+                status
+            );
+        }
+
+        Ok(targets)
+    }
+
+    /// Creates a floating point texture of the given size and attaches it to the bound
+    /// framebuffer.
+    ///
+    /// # Arguments
+    /// * `context` - The GLOW context.
+    /// * `width` - The width of the texture in pixels.
+    /// * `height` - The height of the texture in pixels.
+    /// * `internal_format` - The internal format of the texture.
+    /// * `format` - The format of the channels of the texture.
+    /// * `attachment` - The attachment point within the framebuffer.
+    fn create_texture(
+        context: &C,
+        width: u32,
+        height: u32,
+        internal_format: u32,
+        format: u32,
+        attachment: u32,
+    ) -> Result<C::Texture> {
+        let texture = handle_glow_error(gl_call!(context, create_texture))?;
+        gl_call!(context, bind_texture, glow::TEXTURE_2D, Some(texture));
+        gl_call!(
+            context,
+            tex_image_2d,
+            glow::TEXTURE_2D,
+            0,
+            internal_format as i32,
+            width as i32,
+            height as i32,
+            0,
+            format,
+            glow::FLOAT,
+            None
+        );
+        for parameter in [glow::TEXTURE_MIN_FILTER, glow::TEXTURE_MAG_FILTER] {
+            gl_call!(
+                context,
+                tex_parameter_i32,
+                glow::TEXTURE_2D,
+                parameter,
+                glow::NEAREST as i32
+            );
+        }
+        gl_call!(context, bind_texture, glow::TEXTURE_2D, None);
+
+        gl_call!(
+            context,
+            framebuffer_texture_2d,
+            glow::FRAMEBUFFER,
+            attachment,
+            glow::TEXTURE_2D,
+            Some(texture),
+            0
+        );
+
+        Ok(texture)
+    }
+
+    /// Deletes the given render targets.
+    ///
+    /// # Arguments
+    /// * `context` - The GLOW context.
+    /// * `targets` - The render targets to delete.
+    fn delete_targets(context: &C, targets: Targets<C>) {
+        gl_call!(context, delete_framebuffer, targets.framebuffer);
+        gl_call!(context, delete_texture, targets.accumulation);
+        gl_call!(context, delete_texture, targets.revealage);
+        gl_call!(context, delete_renderbuffer, targets.depth);
+    }
+}
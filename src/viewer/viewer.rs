@@ -1,15 +1,20 @@
+use std::sync::Arc;
+
 use anyhow::Result;
 use glow::{Context, HasContext};
 use glutin::{
     dpi::LogicalPosition,
-    event::{ElementState, Event, MouseButton, VirtualKeyCode, WindowEvent},
+    event::{ElementState, Event, ModifiersState, MouseButton, VirtualKeyCode, WindowEvent},
     event_loop::{ControlFlow, EventLoop},
     window::Window,
     ContextBuilder, ContextWrapper, PossiblyCurrent,
 };
+use log::error;
+
+use super::overlay::Overlay;
 
 /// The configuration of the context.
-pub struct ContextConfig {
+pub struct ContextConfig<C: HasContext> {
     /// The shader version
     pub shader_version: String,
 
@@ -18,12 +23,18 @@ pub struct ContextConfig {
 
     /// The initial height of the context
     pub height: u32,
+
+    /// The ratio between physical and logical pixels
+    pub scale_factor: f64,
+
+    /// The framebuffer the frames are drawn into, where none is the default framebuffer
+    pub framebuffer: Option<C::Framebuffer>,
 }
 
 /// The trait for the viewer controller
 pub trait ViewerController<C: HasContext> {
     /// Initialize call to allocate all OpenGL resource
-    fn initialize(&mut self, context: &C, config: ContextConfig) -> Result<()>;
+    fn initialize(&mut self, context: &C, config: ContextConfig<C>) -> Result<()>;
 
     /// Draws a single frame
     fn draw(&mut self, context: &C);
@@ -34,6 +45,12 @@ pub trait ViewerController<C: HasContext> {
     /// Final cleanup call to remove all GL resources.
     fn cleanup(&mut self, context: &C);
 
+    /// Blocks until the data to display has been loaded and transferred to the GPU.
+    fn wait_for_data(&mut self, context: &C);
+
+    /// Returns true if the controller failed irrecoverably and the viewer should be closed.
+    fn has_failed(&self) -> bool;
+
     /// Callback for logical cursor position
     ///
     ///* `x` - The x coordinate of the cursor in logical coordinates
@@ -55,6 +72,20 @@ pub trait ViewerController<C: HasContext> {
     /// * `virtual_key` - The key pressed or released.
     /// * `pressed` - Determines if the key was pressed or released.
     fn keyboard_event(&mut self, virtual_key: VirtualKeyCode, pressed: bool);
+
+    /// Is called when the state of the modifier keys has changed.
+    ///
+    /// # Arguments
+    ///
+    /// * `modifiers` - The new state of the modifier keys.
+    fn modifiers_changed(&mut self, modifiers: ModifiersState);
+
+    /// Adds the widgets of the overlay GUI for the current frame.
+    ///
+    /// # Arguments
+    ///
+    /// * `gui` - The GUI context to add the widgets to.
+    fn build_ui(&mut self, gui: &egui::Context);
 }
 
 /// The 3D viewer component
@@ -64,17 +95,20 @@ where
 {
     event_loop: EventLoop<()>,
     window: ContextWrapper<PossiblyCurrent, Window>,
-    gl: Context,
+    gl: Arc<Context>,
     controller: C,
-    context_config: ContextConfig,
+    context_config: ContextConfig<Context>,
 }
 
 impl<C: ViewerController<Context>> Viewer<C> {
     /// Creates and returns a new viewer with the given title.
-    pub fn new(title: &str, controller: C) -> Result<Self> {
-        let width: u32 = 1024;
-        let height: u32 = 768;
-
+    ///
+    /// # Arguments
+    /// * `title` - The title of the window.
+    /// * `controller` - The controller which handles the events and draws the frames.
+    /// * `width` - The initial logical width of the window.
+    /// * `height` - The initial logical height of the window.
+    pub fn new(title: &str, controller: C, width: u32, height: u32) -> Result<Self> {
         let (gl, shader_version, window, event_loop) = unsafe {
             let event_loop = EventLoop::new();
             let window_builder = glutin::window::WindowBuilder::new()
@@ -82,16 +116,19 @@ impl<C: ViewerController<Context>> Viewer<C> {
                 .with_inner_size(glutin::dpi::LogicalSize::new(width as f32, height as f32));
             let window = ContextBuilder::new()
                 .with_vsync(true)
+                .with_depth_buffer(24)
+                .with_stencil_buffer(8)
                 .build_windowed(window_builder, &event_loop)
                 .unwrap()
                 .make_current()
                 .unwrap();
             let gl =
                 glow::Context::from_loader_function(|s| window.get_proc_address(s) as *const _);
-            (gl, "#version 410", window, event_loop)
+            (Arc::new(gl), "#version 410", window, event_loop)
         };
 
         let physical_size = window.window().inner_size();
+        let scale_factor = window.window().scale_factor();
 
         let viewer = Viewer {
             event_loop,
@@ -102,6 +139,8 @@ impl<C: ViewerController<Context>> Viewer<C> {
                 shader_version: shader_version.to_owned(),
                 width: physical_size.width,
                 height: physical_size.height,
+                scale_factor,
+                framebuffer: None,
             },
         };
 
@@ -118,40 +157,50 @@ impl<C: ViewerController<Context>> Viewer<C> {
         let context_config = viewer.context_config;
         let mut controller = viewer.controller;
 
-        let scale_factor = window.window().scale_factor();
+        let scale_factor = context_config.scale_factor;
         let mut cursor_pos: [f64; 2] = [0.0, 0.0];
+        let mut window_size = [context_config.width, context_config.height];
 
+        let mut overlay = Overlay::new(gl.clone())?;
         controller.initialize(&gl, context_config)?;
 
         event_loop.run(move |event, _, control_flow| {
             *control_flow = ControlFlow::Wait;
             match event {
-                Event::LoopDestroyed => {
-                    return;
-                }
+                Event::LoopDestroyed => {}
                 Event::MainEventsCleared => {
                     window.window().request_redraw();
                 }
                 Event::RedrawRequested(_) => {
                     controller.draw(&gl);
+                    overlay.draw(window_size[0], window_size[1], scale_factor, |gui| {
+                        controller.build_ui(gui)
+                    });
                     window.swap_buffers().unwrap();
+
+                    if controller.has_failed() {
+                        error!("Close viewer due to previous errors");
+                        controller.cleanup(&gl);
+                        overlay.destroy();
+                        *control_flow = ControlFlow::Exit
+                    }
                 }
+                Event::WindowEvent { ref event, .. }
+                    if overlay.handle_event(event, scale_factor) => {}
                 Event::WindowEvent { ref event, .. } => match event {
                     WindowEvent::Resized(physical_size) => {
-                        controller.resize(
-                            &gl,
-                            physical_size.width as u32,
-                            physical_size.height as u32,
-                        );
+                        window_size = [physical_size.width, physical_size.height];
+                        controller.resize(&gl, physical_size.width, physical_size.height);
                         window.resize(*physical_size);
                     }
                     WindowEvent::CloseRequested => {
                         controller.cleanup(&gl);
+                        overlay.destroy();
                         *control_flow = ControlFlow::Exit
                     }
                     WindowEvent::CursorMoved { position, .. } => {
                         let logical_position =
-                            LogicalPosition::from_physical(*position, scale_factor.clone());
+                            LogicalPosition::from_physical(*position, scale_factor);
                         cursor_pos = [logical_position.x, logical_position.y];
                         controller.cursor_move(logical_position.x, logical_position.y);
                     }
@@ -163,15 +212,17 @@ impl<C: ViewerController<Context>> Viewer<C> {
 
                         controller.mouse_button(x, y, *button, pressed);
                     }
+                    WindowEvent::ModifiersChanged(modifiers) => {
+                        controller.modifiers_changed(*modifiers);
+                    }
                     WindowEvent::KeyboardInput {
                         device_id: _,
                         input,
                         is_synthetic: _,
                     } => {
                         let pressed = input.state == ElementState::Pressed;
-                        match input.virtual_keycode {
-                            Some(vk) => controller.keyboard_event(vk, pressed),
-                            None => {}
+                        if let Some(vk) = input.virtual_keycode {
+                            controller.keyboard_event(vk, pressed)
                         }
                     }
                     _ => (),